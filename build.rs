@@ -2,7 +2,16 @@
 
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+#[path = "build_support/morph_check.rs"]
+mod morph_check;
+#[path = "build_support/bg_assets.rs"]
+mod bg_assets;
+#[path = "build_support/ram_budget.rs"]
+mod ram_budget;
+#[path = "build_support/board_pins.rs"]
+mod board_pins;
 
 fn main() {
     // Put the linker script somewhere the linker can find it
@@ -15,6 +24,8 @@ fn main() {
     let mut f = File::create(out.join("memory.x")).unwrap();
     f.write_all(memory_x).unwrap();
     println!("cargo:rerun-if-changed=memory.x");
+    ram_budget::check_and_generate_or_fail(Path::new("memory.x"), &out);
+    println!("cargo:rerun-if-changed=build_support/ram_budget.rs");
 
     // The file `rp235x_riscv.x` is what we specify in `.cargo/config.toml` for
     // RISC-V builds
@@ -26,6 +37,17 @@ fn main() {
     // SVG files need special handling because of the proc_macro
     println!("cargo:rerun-if-changed=img/eyestack-left-gen.svg");
     println!("cargo:rerun-if-changed=img/eyestack-right-gen.svg");
+    morph_check::check_morph_pairs_or_fail(&[
+        Path::new("img/eyestack-left-gen.svg"),
+        Path::new("img/eyestack-right-gen.svg"),
+    ]);
+    println!("cargo:rerun-if-changed=build_support/morph_check.rs");
+
+    bg_assets::generate_or_fail(Path::new("img/eye_backgrounds.txt"), &out);
+    println!("cargo:rerun-if-changed=build_support/bg_assets.rs");
+
+    board_pins::check_and_generate_or_fail(Path::new("boards"), &out);
+    println!("cargo:rerun-if-changed=build_support/board_pins.rs");
 
     println!("cargo:rerun-if-changed=build.rs");
 }
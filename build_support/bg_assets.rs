@@ -0,0 +1,202 @@
+//!
+//! Build-time table of the background image for each eye and emotion.
+//!
+//! The table is read from a manifest (`img/eye_backgrounds.txt`) of lines like
+//! `left Surprise eyebg-left-surprise.qoi`, naming an `EmotionExpression` variant.
+//! An emotion with no line of its own uses that eye's `Neutral` background, if any.
+//! Every listed image is checked to be a full-frame QOI, embedded once however many
+//! emotions share it, and counted against a flash budget so the build fails before
+//! the backgrounds crowd out the program.
+//! This is shared by the firmware and `eyesim` build scripts.
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Every background must fill the frame the eye art is authored for (`ART_SIZE` in src/eyerender),
+/// in the same orientation as the frame buffer; on other panels it's scaled to fit as it's decoded
+const FRAME_WIDTH: u32 = 320;
+const FRAME_HEIGHT: u32 = 240;
+
+/// The budget used when `EYEBG_FLASH_BUDGET_KIB` isn't set
+const DEFAULT_FLASH_BUDGET_KIB: usize = 256;
+
+/// The environment variable that sets the flash budget for all backgrounds, in KiB
+pub const BUDGET_ENV: &str = "EYEBG_FLASH_BUDGET_KIB";
+/// Set this environment variable to print the flash report as build warnings
+pub const REPORT_ENV: &str = "EYEBG_REPORT";
+
+/// The generated source, included by `eyerender`
+pub const GENERATED_FILE: &str = "emotion_bg_assets.rs";
+/// The flash cost report, written next to the generated source
+pub const REPORT_FILE: &str = "emotion_bg_report.txt";
+
+/// One line of the manifest
+struct BgEntry {
+    is_left: bool,
+    emotion: String,
+    file: String,
+}
+
+fn side_name(is_left: bool) -> &'static str {
+    if is_left { "left" } else { "right" }
+}
+
+/// Read the manifest, returning a description of each malformed line
+fn parse_manifest(manifest_path: &Path, text: &str) -> Result<Vec<BgEntry>, Vec<String>> {
+    let mut entries: Vec<BgEntry> = Vec::new();
+    let mut errors = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let where_ = format!("{}:{}", manifest_path.display(), line_idx + 1);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [side, emotion, file] = fields[..] else {
+            errors.push(format!("{}: expected `<left|right> <Emotion> <file>`, got `{}`", where_, line));
+            continue;
+        };
+        let is_left = match side {
+            "left" => true,
+            "right" => false,
+            _ => {
+                errors.push(format!("{}: side must be `left` or `right`, not `{}`", where_, side));
+                continue;
+            }
+        };
+        if !emotion.starts_with(|c: char| c.is_ascii_uppercase()) || !emotion.chars().all(|c| c.is_ascii_alphanumeric()) {
+            errors.push(format!("{}: `{}` isn't an EmotionExpression variant name", where_, emotion));
+            continue;
+        }
+        if entries.iter().any(|entry| entry.is_left == is_left && entry.emotion == emotion) {
+            errors.push(format!("{}: {} {} is listed more than once", where_, side, emotion));
+            continue;
+        }
+        entries.push(BgEntry { is_left, emotion: emotion.to_string(), file: file.to_string() });
+    }
+    if errors.is_empty() { Ok(entries) } else { Err(errors) }
+}
+
+/// Check that the file is a QOI image that fills the frame, returning its size in bytes
+fn check_qoi(path: &Path) -> Result<usize, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+    if bytes.len() < 14 || &bytes[..4] != b"qoif" {
+        return Err(format!("{} isn't a QOI image", path.display()));
+    }
+    let width = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+    let height = u32::from_be_bytes(bytes[8..12].try_into().unwrap());
+    if (width, height) != (FRAME_WIDTH, FRAME_HEIGHT) {
+        return Err(format!("{} is {}x{}, but backgrounds must be {}x{}",
+            path.display(), width, height, FRAME_WIDTH, FRAME_HEIGHT));
+    }
+    Ok(bytes.len())
+}
+
+/// The name of the static holding an image, eg `BG_EYEBG_LEFT_SURPRISE`
+fn static_name(file: &str) -> String {
+    let stem = file.strip_suffix(".qoi").unwrap_or(file);
+    let upper: String = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("BG_{}", upper)
+}
+
+fn flash_budget_bytes() -> Result<usize, String> {
+    match std::env::var(BUDGET_ENV) {
+        Ok(kib) => kib.trim().parse::<usize>()
+            .map(|kib| kib * 1024)
+            .map_err(|_| format!("{} must be a whole number of KiB, not `{}`", BUDGET_ENV, kib)),
+        Err(_) => Ok(DEFAULT_FLASH_BUDGET_KIB * 1024),
+    }
+}
+
+/// Generate the background table from the manifest into `out_dir`, failing the build
+/// if the manifest or any image is bad, or the images don't fit in the flash budget
+pub fn generate_or_fail(manifest_path: &Path, out_dir: &Path) {
+    println!("cargo:rerun-if-changed={}", manifest_path.display());
+    println!("cargo:rerun-if-env-changed={}", BUDGET_ENV);
+    println!("cargo:rerun-if-env-changed={}", REPORT_ENV);
+
+    let text = std::fs::read_to_string(manifest_path)
+        .unwrap_or_else(|err| panic!("can't read {}: {}", manifest_path.display(), err));
+    let entries = parse_manifest(manifest_path, &text).unwrap_or_else(|errors| {
+        panic!("Bad background manifest:\n  {}", errors.join("\n  "))
+    });
+    let img_dir: PathBuf = manifest_path.parent().unwrap_or(Path::new("."))
+        .canonicalize()
+        .unwrap_or_else(|err| panic!("can't find {}: {}", manifest_path.display(), err));
+
+    // file -> (size, the side and emotion of each entry using it)
+    let mut files: BTreeMap<&str, (usize, Vec<String>)> = BTreeMap::new();
+    let mut errors = Vec::new();
+    for entry in &entries {
+        let path = img_dir.join(&entry.file);
+        println!("cargo:rerun-if-changed={}", path.display());
+        let users = format!("{} {}", side_name(entry.is_left), entry.emotion);
+        if let Some((_, file_users)) = files.get_mut(entry.file.as_str()) {
+            file_users.push(users);
+            continue;
+        }
+        match check_qoi(&path) {
+            Ok(size) => { files.insert(&entry.file, (size, vec![users])); }
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        panic!("Bad background images in {}:\n  {}", manifest_path.display(), errors.join("\n  "));
+    }
+
+    let budget = flash_budget_bytes().unwrap_or_else(|err| panic!("{}", err));
+    let total: usize = files.values().map(|(size, _)| size).sum();
+    let mut report = format!("Background images embedded in flash, from {}:\n", manifest_path.display());
+    for (file, (size, users)) in &files {
+        let _ = writeln!(report, "  {:<28} {:>7} bytes  {}", file, size, users.join(", "));
+    }
+    let _ = writeln!(report, "  {:<28} {:>7} bytes  of a {} byte budget ({})", "total", total, budget, BUDGET_ENV);
+
+    std::fs::write(out_dir.join(REPORT_FILE), &report)
+        .unwrap_or_else(|err| panic!("can't write {}: {}", REPORT_FILE, err));
+    if total > budget {
+        panic!("{}Background images need {} bytes of flash, over the budget by {}. \
+            Drop or shrink some images, or raise {} in .cargo/config.toml.",
+            report, total, total - budget, BUDGET_ENV);
+    }
+    if std::env::var_os(REPORT_ENV).is_some() {
+        for line in report.lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+
+    std::fs::write(out_dir.join(GENERATED_FILE), generate_source(manifest_path, &img_dir, &entries, &files))
+        .unwrap_or_else(|err| panic!("can't write {}: {}", GENERATED_FILE, err));
+}
+
+fn generate_source(manifest_path: &Path, img_dir: &Path, entries: &[BgEntry],
+    files: &BTreeMap<&str, (usize, Vec<String>)>) -> String {
+    let mut src = format!("// Generated by build_support/bg_assets.rs from {}, do not edit\n\n", manifest_path.display());
+    for file in files.keys() {
+        let _ = writeln!(src, "static {}: &[u8] = include_bytes!({:?});", static_name(file), img_dir.join(file));
+    }
+    let total: usize = files.values().map(|(size, _)| size).sum();
+    let _ = writeln!(src, "\n/// Flash used by all the embedded background images, in bytes\npub const EMOTION_BG_FLASH_BYTES: usize = {};\n", total);
+
+    src.push_str("/// The background image for an emotion, if any.\n");
+    src.push_str("/// Emotions that don't have their own background use the neutral one.\n");
+    src.push_str("pub const fn get_emotion_bg_bytes(emotion: EmotionExpression, is_left: bool) -> Option<&'static [u8]> {\n");
+    src.push_str("    match (is_left, emotion) {\n");
+    for entry in entries.iter().filter(|entry| entry.emotion != "Neutral") {
+        let _ = writeln!(src, "        ({}, EmotionExpression::{}) => Some({}),",
+            entry.is_left, entry.emotion, static_name(&entry.file));
+    }
+    for is_left in [true, false] {
+        let neutral = entries.iter().find(|entry| entry.is_left == is_left && entry.emotion == "Neutral");
+        match neutral {
+            Some(entry) => { let _ = writeln!(src, "        ({}, _) => Some({}),", is_left, static_name(&entry.file)); }
+            None => { let _ = writeln!(src, "        ({}, _) => None,", is_left); }
+        }
+    }
+    src.push_str("    }\n}\n");
+    src
+}
@@ -0,0 +1,255 @@
+//!
+//! Build-time board profiles: which GPIO each display, button and LED is wired to.
+//!
+//! A profile is a text file in `boards/`, one `<role> <GPIO>` line per pin. The default profile
+//! is `boards/pico2-split.txt`; a `board-<name>` feature picks `boards/<name>.txt` instead, and
+//! `EYE_BOARD_PINS` names any other profile file. The profile is checked here, so that a bad
+//! wiring fails the build with a description of what's wrong rather than with a type error:
+//! every role must be given a pin, no pin may be given twice, each display's SCK and MOSI must be
+//! pins its SPI block can use, and the two backlights must be on separate PWM slices.
+//! With the `ambient-light` feature there's a light sensor too, which must be on an ADC pin;
+//! without it, any `light_sensor` line is skipped.
+//! The pins are then generated into `board_pins.rs`, as the `BoardPins` the firmware takes
+//! from its peripherals with `take_board_pins!`.
+//!
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// The profile used without a `board-<name>` feature or `EYE_BOARD_PINS`
+const DEFAULT_PROFILE: &str = "pico2-split";
+
+/// The environment variable naming a profile file outside `boards/`
+pub const PROFILE_ENV: &str = "EYE_BOARD_PINS";
+
+/// The generated source, included by `board`
+pub const GENERATED_FILE: &str = "board_pins.rs";
+
+/// The RP2350A's GPIO, which are all PWM capable
+const NUM_GPIO: u8 = 30;
+
+/// The pins each display needs, with the SPI function each must have, if any
+const DISPLAY_ROLES: [(&str, Option<SpiFunction>); 6] = [
+    ("sck", Some(SpiFunction::Sck)),
+    ("mosi", Some(SpiFunction::Tx)),
+    ("cs", None),
+    ("dc", None),
+    ("rst", None),
+    ("backlight", None),
+];
+/// The displays, in the order of their SPI blocks
+const DISPLAYS: [&str; 2] = ["left", "right"];
+/// The pins outside the displays
+const OTHER_ROLES: [&str; 3] = ["mode_a_button", "mode_b_button", "led"];
+/// The photoresistor's pin, needed with the `ambient-light` feature
+const LIGHT_SENSOR_ROLE: &str = "light_sensor";
+/// The RP2350A's GPIO that can be ADC inputs
+const ADC_GPIO: std::ops::RangeInclusive<u8> = 26..=29;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SpiFunction {
+    Rx,
+    Csn,
+    Sck,
+    Tx,
+}
+
+/// The SPI block and function a GPIO can take on: they repeat every 8 pins, alternating blocks
+fn spi_function(gpio: u8) -> (usize, SpiFunction) {
+    let function = [SpiFunction::Rx, SpiFunction::Csn, SpiFunction::Sck, SpiFunction::Tx][gpio as usize % 4];
+    ((gpio as usize / 8) % 2, function)
+}
+
+/// The PWM slice and channel (A or B) a GPIO drives
+fn pwm_channel(gpio: u8) -> (u8, char) {
+    ((gpio / 2) % 8, if gpio % 2 == 0 { 'A' } else { 'B' })
+}
+
+fn has_light_sensor() -> bool {
+    std::env::var_os("CARGO_FEATURE_AMBIENT_LIGHT").is_some()
+}
+
+/// The roles outside the displays the build needs
+fn other_roles() -> Vec<&'static str> {
+    let light_sensor = has_light_sensor().then_some(LIGHT_SENSOR_ROLE);
+    OTHER_ROLES.iter().copied().chain(light_sensor).collect()
+}
+
+/// Every role, eg `left.sck` and `mode_a_button`, in the order they're generated
+fn all_roles() -> Vec<String> {
+    DISPLAYS.iter()
+        .flat_map(|display| DISPLAY_ROLES.iter().map(move |(role, _)| format!("{}.{}", display, role)))
+        .chain(other_roles().into_iter().map(|role| role.to_string()))
+        .collect()
+}
+
+/// Read a profile into a pin for each role in `all_roles`, or a description of everything wrong with it
+fn parse_profile(profile_path: &Path, text: &str) -> Result<Vec<u8>, Vec<String>> {
+    let roles = all_roles();
+    let mut pins: Vec<Option<u8>> = vec![None; roles.len()];
+    let mut errors = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let where_ = format!("{}:{}", profile_path.display(), line_idx + 1);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [role, gpio] = fields[..] else {
+            errors.push(format!("{}: expected `<role> <GPIO>`, got `{}`", where_, line));
+            continue;
+        };
+        if role == LIGHT_SENSOR_ROLE && !has_light_sensor() {
+            continue;
+        }
+        let Some(role_idx) = roles.iter().position(|known| known == role) else {
+            errors.push(format!("{}: `{}` isn't a pin role, expected one of {}", where_, role, roles.join(", ")));
+            continue;
+        };
+        let gpio = match gpio.parse::<u8>() {
+            Ok(gpio) if gpio < NUM_GPIO => gpio,
+            _ => {
+                errors.push(format!("{}: `{}` isn't a GPIO from 0 to {}", where_, gpio, NUM_GPIO - 1));
+                continue;
+            }
+        };
+        if pins[role_idx].is_some() {
+            errors.push(format!("{}: {} is given more than once", where_, role));
+            continue;
+        }
+        pins[role_idx] = Some(gpio);
+    }
+    for (role, pin) in roles.iter().zip(&pins) {
+        if pin.is_none() {
+            errors.push(format!("{}: no pin for {}", profile_path.display(), role));
+        }
+    }
+    if errors.is_empty() { Ok(pins.into_iter().flatten().collect()) } else { Err(errors) }
+}
+
+/// Check the pins, one for each role in `all_roles`, returning a description of each problem
+fn check_pins(pins: &[u8]) -> Vec<String> {
+    let roles = all_roles();
+    let mut errors = Vec::new();
+    for (idx, &gpio) in pins.iter().enumerate() {
+        if let Some(first) = pins[..idx].iter().position(|&other| other == gpio) {
+            errors.push(format!("GPIO{} is given to both {} and {}", gpio, roles[first], roles[idx]));
+        }
+    }
+    for (spi, display) in DISPLAYS.iter().enumerate() {
+        for (role_idx, (role, needs)) in DISPLAY_ROLES.iter().enumerate() {
+            let Some(needs) = *needs else { continue };
+            let gpio = pins[spi * DISPLAY_ROLES.len() + role_idx];
+            if spi_function(gpio) != (spi, needs) {
+                let usable: Vec<String> = (0..NUM_GPIO)
+                    .filter(|&other| spi_function(other) == (spi, needs))
+                    .map(|other| other.to_string())
+                    .collect();
+                errors.push(format!("{}.{} is on SPI{}, which can't use GPIO{} for it, only GPIO {}",
+                    display, role, spi, gpio, usable.join(", ")));
+            }
+        }
+    }
+    if let Some(sensor_idx) = roles.iter().position(|role| role == LIGHT_SENSOR_ROLE) {
+        if !ADC_GPIO.contains(&pins[sensor_idx]) {
+            errors.push(format!("{} is on GPIO{}, which isn't an ADC input, only GPIO {} to {} are",
+                LIGHT_SENSOR_ROLE, pins[sensor_idx], ADC_GPIO.start(), ADC_GPIO.end()));
+        }
+    }
+    let backlight_idx = DISPLAY_ROLES.iter().position(|(role, _)| *role == "backlight").unwrap();
+    let (left_bl, right_bl) = (pins[backlight_idx], pins[DISPLAY_ROLES.len() + backlight_idx]);
+    if pwm_channel(left_bl).0 == pwm_channel(right_bl).0 {
+        errors.push(format!("the backlights, GPIO{} and GPIO{}, are both on PWM slice {}, but each needs a slice of its own",
+            left_bl, right_bl, pwm_channel(left_bl).0));
+    }
+    errors
+}
+
+/// The generated `BoardPins`, the structs it's made of, the backlight constructors and `take_board_pins!`
+fn generate_source(profile_path: &Path, pins: &[u8]) -> String {
+    let mut src = String::new();
+    writeln!(src, "// Generated by build_support/board_pins.rs from {}, do not edit\n", profile_path.display()).unwrap();
+    let name = profile_path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    writeln!(src, "/// The board profile these pins come from").unwrap();
+    writeln!(src, "pub const BOARD_NAME: &str = {:?};\n", name).unwrap();
+
+    let mut take = String::new();
+    for (spi, display) in DISPLAYS.iter().enumerate() {
+        let title = format!("{}{}", display[..1].to_uppercase(), &display[1..]);
+        let display_pins = &pins[spi * DISPLAY_ROLES.len()..(spi + 1) * DISPLAY_ROLES.len()];
+        let (slice, channel) = pwm_channel(display_pins[DISPLAY_ROLES.len() - 1]);
+        writeln!(src, "/// The pins wired to the {} eye's display, on SPI{}", display, spi).unwrap();
+        writeln!(src, "pub struct {}DisplayPins {{", title).unwrap();
+        write!(take, "        {}: $crate::board::{}DisplayPins {{ ", display, title).unwrap();
+        for ((role, _), gpio) in DISPLAY_ROLES.iter().zip(display_pins) {
+            writeln!(src, "    pub {}: Peri<'static, peripherals::PIN_{}>,", role, gpio).unwrap();
+            write!(take, "{}: $p.PIN_{}, ", role, gpio).unwrap();
+        }
+        writeln!(src, "    pub backlight_slice: Peri<'static, peripherals::PWM_SLICE{}>,", slice).unwrap();
+        writeln!(take, "backlight_slice: $p.PWM_SLICE{} }},", slice).unwrap();
+        writeln!(src, "}}\n").unwrap();
+        writeln!(src, "impl {}DisplayPins {{", title).unwrap();
+        writeln!(src, "    /// Drive the backlight from PWM slice {}, channel {}", slice, channel).unwrap();
+        writeln!(src, "    pub fn backlight_pwm(backlight: Peri<'static, peripherals::PIN_{}>,", display_pins[DISPLAY_ROLES.len() - 1]).unwrap();
+        writeln!(src, "        slice: Peri<'static, peripherals::PWM_SLICE{}>, config: pwm::Config) -> Pwm<'static>", slice).unwrap();
+        writeln!(src, "    {{\n        Pwm::new_output_{}(slice, backlight, config)\n    }}", channel.to_ascii_lowercase()).unwrap();
+        writeln!(src, "}}\n").unwrap();
+        writeln!(src, "pub type {}CsPin = Peri<'static, peripherals::PIN_{}>;\n", title, display_pins[2]).unwrap();
+    }
+
+    let other_pins = &pins[DISPLAYS.len() * DISPLAY_ROLES.len()..];
+    writeln!(src, "/// Every pin the firmware uses").unwrap();
+    writeln!(src, "pub struct BoardPins {{").unwrap();
+    writeln!(src, "    pub left: LeftDisplayPins,\n    pub right: RightDisplayPins,").unwrap();
+    for (role, gpio) in other_roles().iter().zip(other_pins) {
+        writeln!(src, "    pub {}: Peri<'static, peripherals::PIN_{}>,", role, gpio).unwrap();
+    }
+    writeln!(src, "}}\n").unwrap();
+
+    writeln!(src, "/// Take the board's pins from `$p`, the `embassy_rp::Peripherals`").unwrap();
+    writeln!(src, "macro_rules! take_board_pins {{\n    ($p:ident) => {{ $crate::board::BoardPins {{").unwrap();
+    src.push_str(&take);
+    for (role, gpio) in other_roles().iter().zip(other_pins) {
+        writeln!(src, "        {}: $p.PIN_{},", role, gpio).unwrap();
+    }
+    writeln!(src, "    }} }};\n}}").unwrap();
+    writeln!(src, "pub(crate) use take_board_pins;").unwrap();
+    src
+}
+
+/// The profile picked by `EYE_BOARD_PINS` or a `board-<name>` feature, or the default
+fn profile_path(boards_dir: &Path) -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os(PROFILE_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    let features: Vec<String> = std::env::vars()
+        .filter_map(|(var, _)| var.strip_prefix("CARGO_FEATURE_BOARD_").map(|name| name.to_ascii_lowercase().replace('_', "-")))
+        .collect();
+    match &features[..] {
+        [] => Ok(boards_dir.join(format!("{}.txt", DEFAULT_PROFILE))),
+        [name] => Ok(boards_dir.join(format!("{}.txt", name))),
+        _ => Err(format!("only one board-<name> feature can be enabled, not {}",
+            features.iter().map(|name| format!("board-{}", name)).collect::<Vec<_>>().join(", "))),
+    }
+}
+
+/// Check the selected board profile and generate its pins into `out_dir`, failing the build if it's bad
+pub fn check_and_generate_or_fail(boards_dir: &Path, out_dir: &Path) {
+    println!("cargo:rerun-if-env-changed={}", PROFILE_ENV);
+    let profile_path = profile_path(boards_dir).unwrap_or_else(|err| panic!("Can't pick a board profile: {}", err));
+    println!("cargo:rerun-if-changed={}", profile_path.display());
+
+    let text = std::fs::read_to_string(&profile_path)
+        .unwrap_or_else(|err| panic!("can't read board profile {}: {}", profile_path.display(), err));
+    let pins = parse_profile(&profile_path, &text).unwrap_or_else(|errors| {
+        panic!("Bad board profile:\n  {}", errors.join("\n  "))
+    });
+    let errors = check_pins(&pins);
+    if !errors.is_empty() {
+        panic!("Board profile {} can't be wired that way:\n  {}", profile_path.display(), errors.join("\n  "));
+    }
+
+    let generated = out_dir.join(GENERATED_FILE);
+    std::fs::write(&generated, generate_source(&profile_path, &pins))
+        .unwrap_or_else(|err| panic!("can't write {}: {}", generated.display(), err));
+}
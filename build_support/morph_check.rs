@@ -0,0 +1,169 @@
+//!
+//! Build-time check that SVG paths which may be morphed into each other are compatible.
+//!
+//! Any two paths from the same stepped asset family (eg `iris_11`, `iris_11_0_22`, `iris_00`)
+//! can be interpolated at runtime, which requires their vertices to correspond one-to-one.
+//! Emotion variants (eg `eyebrow_happy`, `upper_lid_bulge_happy_11`) morph to and from the
+//! neutral asset, so they belong to the same family as it.
+//! Paths flatten to matching vertex lists when they have the same sequence of segment kinds,
+//! so that's what we compare here, before the proc macro flattens them.
+//! This is shared by the firmware and `eyesim` build scripts.
+//!
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Find the value of an attribute such as ` id="..."` within a single element's text
+fn attr_value<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=\"", name);
+    let mut search_from = 0;
+    while let Some(offset) = element[search_from..].find(&pattern) {
+        let start = search_from + offset;
+        let preceded_by_space = element[..start].chars().last().is_some_and(char::is_whitespace);
+        let value_start = start + pattern.len();
+        if preceded_by_space {
+            let value_len = element[value_start..].find('"')?;
+            return Some(&element[value_start..value_start + value_len]);
+        }
+        search_from = value_start;
+    }
+    None
+}
+
+/// All (id, d) pairs for `<path>` elements in the SVG text
+fn svg_paths(svg: &str) -> Vec<(String, String)> {
+    let mut paths = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find("<path") {
+        let element_len = rest[start..].find('>').unwrap_or(rest.len() - start);
+        let element = &rest[start..start + element_len];
+        if let (Some(id), Some(d)) = (attr_value(element, "id"), attr_value(element, "d")) {
+            paths.push((id.to_string(), d.to_string()));
+        }
+        rest = &rest[start + element_len..];
+    }
+    paths
+}
+
+/// Number of numeric arguments consumed by each repetition of a path command
+fn command_arg_count(cmd: char) -> usize {
+    match cmd.to_ascii_lowercase() {
+        'm' | 'l' | 't' => 2,
+        'h' | 'v' => 1,
+        'c' => 6,
+        's' | 'q' => 4,
+        'a' => 7,
+        _ => 0,
+    }
+}
+
+/// The sequence of segment kinds in a path, with implicit command repeats expanded.
+/// Relative and absolute forms of a command are treated the same.
+fn segment_kinds(d: &str) -> Vec<char> {
+    let mut kinds = Vec::new();
+    let mut cmd: Option<char> = None;
+    let mut pending_args = 0;
+    let mut chars = d.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            chars.next();
+            if c == 'z' || c == 'Z' {
+                kinds.push('Z');
+                cmd = None;
+            } else {
+                cmd = Some(c);
+                pending_args = 0;
+            }
+        } else if c.is_ascii_digit() || c == '-' || c == '.' || c == '+' {
+            // consume one number, including any exponent
+            let mut prev = c;
+            chars.next();
+            while let Some(&n) = chars.peek() {
+                let is_exponent_sign = (n == '-' || n == '+') && (prev == 'e' || prev == 'E');
+                if n.is_ascii_digit() || n == '.' || n == 'e' || n == 'E' || is_exponent_sign {
+                    prev = n;
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if let Some(cur) = cmd {
+                pending_args += 1;
+                if pending_args == command_arg_count(cur) {
+                    kinds.push(cur.to_ascii_uppercase());
+                    pending_args = 0;
+                    // a moveto followed by more coordinates is an implicit lineto
+                    if cur == 'm' { cmd = Some('l'); }
+                    if cur == 'M' { cmd = Some('L'); }
+                }
+            }
+        } else {
+            chars.next();
+        }
+    }
+    kinds
+}
+
+/// Split a stepped asset id such as `iris_11_0_22` or `iris_00` into its prefix (`iris`).
+/// Returns None for ids that aren't part of a stepped family, such as `eyebrow`.
+fn stepped_prefix(id: &str) -> Option<&str> {
+    let is_digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    let parts: Vec<&str> = id.rsplitn(4, '_').collect();
+    if parts.len() == 4 && is_digits(parts[0], 2) && is_digits(parts[1], 1) && is_digits(parts[2], 2) {
+        return Some(parts[3]);
+    }
+    let (prefix, grid) = id.rsplit_once('_')?;
+    if is_digits(grid, 2) { Some(prefix) } else { None }
+}
+
+/// Keys of emotion variants in asset ids.
+/// This must match `EmotionExpression::ASSET_KEYS`, which build scripts can't depend on.
+const EMOTION_ASSET_KEYS: [&str; 9] =
+    ["surprise", "happy", "curious", "skeptical", "thoughtful", "confused", "shy", "love", "trepidation"];
+
+/// The family an asset id belongs to: its stepped prefix if any, without any emotion key.
+/// eg `upper_lid_bulge_happy_11_0_01` and `upper_lid_bulge_01` are both in `upper_lid_bulge`,
+/// while `eyebrow_happy` is in `eyebrow`
+fn asset_family(id: &str) -> &str {
+    let prefix = stepped_prefix(id).unwrap_or(id);
+    EMOTION_ASSET_KEYS.iter()
+        .find_map(|key| prefix.strip_suffix(key).and_then(|rest| rest.strip_suffix('_')))
+        .unwrap_or(prefix)
+}
+
+/// Check every asset family in the SVG file, returning a description of each mismatch
+pub fn check_svg_morph_pairs(svg_path: &Path) -> Result<(), Vec<String>> {
+    let svg = std::fs::read_to_string(svg_path)
+        .map_err(|err| vec![format!("can't read {}: {}", svg_path.display(), err)])?;
+
+    // family -> (first id seen, its segment kinds)
+    let mut families: BTreeMap<String, (String, Vec<char>)> = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (id, d) in svg_paths(&svg) {
+        let family = asset_family(&id);
+        let kinds = segment_kinds(&d);
+        match families.get(family) {
+            None => { families.insert(family.to_string(), (id, kinds)); }
+            Some((ref_id, ref_kinds)) if *ref_kinds != kinds => {
+                errors.push(format!("{}: `{}` has {} segments {:?}, but `{}` has {} segments {:?}",
+                    svg_path.display(), id, kinds.len(), kinds.iter().collect::<String>(),
+                    ref_id, ref_kinds.len(), ref_kinds.iter().collect::<String>()));
+            }
+            Some(_) => {}
+        }
+    }
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Run the check on each SVG file, failing the build with all mismatches listed
+pub fn check_morph_pairs_or_fail(svg_paths: &[&Path]) {
+    let errors: Vec<String> = svg_paths.iter()
+        .filter_map(|path| check_svg_morph_pairs(path).err())
+        .flatten()
+        .collect();
+    if !errors.is_empty() {
+        panic!("SVG paths in the same asset family can't be morphed into each other:\n  {}\n\
+            Each family's paths need the same sequence of segments (same node count and types).",
+            errors.join("\n  "));
+    }
+}
@@ -0,0 +1,115 @@
+//!
+//! Build-time check that the display buffers fit in RAM, as laid out in `memory.x`.
+//!
+//! Each eye has a full frame buffer, and with the `strip-buffer` feature a pair of strip
+//! buffers that take turns being sent to the display while the next is filled.
+//! With `band-render` frames are rendered straight into the strip buffers, so there are no frame buffers.
+//! Each eye also keeps its static layers, pre-rasterized, in a layer cache.
+//! Those sizes are decided here and generated into `frame_memory.rs` for the firmware
+//! (and the layer cache's for the simulator too), so the check and the buffers can't disagree.
+//!
+
+use std::path::Path;
+
+/// Must match DISPLAY_WIDTH, DISPLAY_HEIGHT and PIXEL_SIZE in src/eyerender
+const FRAME_BYTES: usize = 320 * 240 * 2;
+/// The same, for the 240x240 round panels of the `gc9a01` feature
+const ROUND_FRAME_BYTES: usize = 240 * 240 * 2;
+const NUM_EYES: usize = 2;
+
+/// The size of each strip buffer, when the `strip-buffer` feature is enabled
+const STRIP_BUFFER_BYTES: usize = 24 * 1024;
+/// Enough for one strip to be filled while the other is sent
+const STRIP_BUFFERS_PER_EYE: usize = 2;
+
+/// Room for every static layer of one eye in its layer cache
+const LAYER_CACHE_SPANS: usize = 3072;
+/// The size of each span, which src/eyerender/layer_cache.rs checks
+const LAYER_CACHE_SPAN_BYTES: usize = 8;
+const LAYER_CACHE_BYTES: usize = LAYER_CACHE_SPANS * LAYER_CACHE_SPAN_BYTES + 256;
+
+/// RAM left for everything but the display buffers: stacks (core1's is 24K), executors, USB, and the rest
+const RAM_RESERVE_BYTES: usize = 64 * 1024;
+
+/// The generated source, included by the firmware
+pub const GENERATED_FILE: &str = "frame_memory.rs";
+
+/// Parse a linker script size such as `512K`, `2M` or `0x1000`
+fn parse_size(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let (digits, scale) = match text.chars().last()? {
+        'K' | 'k' => (&text[..text.len() - 1], 1024),
+        'M' | 'm' => (&text[..text.len() - 1], 1024 * 1024),
+        _ => (text, 1),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok()?,
+        None => digits.parse().ok()?,
+    };
+    Some(value * scale)
+}
+
+/// The LENGTH of the named region in the MEMORY block of a linker script
+fn region_length(memory_x: &str, region: &str) -> Option<usize> {
+    memory_x.lines().find_map(|line| {
+        let (name, spec) = line.split_once(':')?;
+        if name.trim() != region {
+            return None;
+        }
+        let length = spec.split(',').find_map(|field| field.trim().strip_prefix("LENGTH"))?;
+        parse_size(length.trim().strip_prefix('=')?)
+    })
+}
+
+/// Check that the display buffers for the enabled features fit in RAM, failing the build
+/// with the breakdown if they don't, and generate their sizes into `out_dir`
+pub fn check_and_generate_or_fail(memory_x_path: &Path, out_dir: &Path) {
+    let memory_x = std::fs::read_to_string(memory_x_path)
+        .unwrap_or_else(|err| panic!("can't read {}: {}", memory_x_path.display(), err));
+    let ram_bytes = region_length(&memory_x, "RAM")
+        .unwrap_or_else(|| panic!("can't find the RAM region's LENGTH in {}", memory_x_path.display()));
+
+    let strip_buffer = std::env::var_os("CARGO_FEATURE_STRIP_BUFFER").is_some();
+    let band_render = std::env::var_os("CARGO_FEATURE_BAND_RENDER").is_some();
+    let round_panel = std::env::var_os("CARGO_FEATURE_GC9A01").is_some();
+    let frame_bytes = match (band_render, round_panel) {
+        (true, _) => 0,
+        (false, true) => ROUND_FRAME_BYTES,
+        (false, false) => FRAME_BYTES,
+    };
+    let strip_bytes_per_eye = if strip_buffer { STRIP_BUFFERS_PER_EYE * STRIP_BUFFER_BYTES } else { 0 };
+    let buffer_bytes = NUM_EYES * (frame_bytes + strip_bytes_per_eye + LAYER_CACHE_BYTES);
+    if buffer_bytes + RAM_RESERVE_BYTES > ram_bytes {
+        panic!("Display buffers don't fit in RAM ({} bytes in {}):\n  \
+            {} eyes x {} byte frame buffer\n  \
+            {} eyes x {} x {} byte strip buffers\n  \
+            {} eyes x {} byte layer cache\n  \
+            {} bytes reserved for stacks and other statics\n\
+            That's {} bytes over. Shrink STRIP_BUFFER_BYTES or LAYER_CACHE_SPANS in {}.",
+            ram_bytes, memory_x_path.display(),
+            NUM_EYES, frame_bytes,
+            NUM_EYES, if strip_buffer { STRIP_BUFFERS_PER_EYE } else { 0 }, STRIP_BUFFER_BYTES,
+            NUM_EYES, LAYER_CACHE_BYTES,
+            RAM_RESERVE_BYTES,
+            buffer_bytes + RAM_RESERVE_BYTES - ram_bytes, file!());
+    }
+    generate(out_dir);
+}
+
+/// Generate the buffer sizes into `out_dir`, without checking them (eg for the host simulator)
+pub fn generate(out_dir: &Path) {
+    let src = format!("// Generated by build_support/ram_budget.rs, do not edit\n\n\
+        /// The size of each of an eye's strip buffers, which take turns being sent to the display\n\
+        #[allow(dead_code)]\n\
+        const STRIP_BUFFER_BYTES: usize = {};\n\
+        #[allow(dead_code)]\n\
+        const STRIP_BUFFERS_PER_EYE: usize = {};\n\
+        /// Room for every static layer of one eye in its layer cache\n\
+        #[allow(dead_code)]\n\
+        pub const LAYER_CACHE_SPANS: usize = {};\n\
+        #[allow(dead_code)]\n\
+        const LAYER_CACHE_SPAN_BYTES: usize = {};\n",
+        STRIP_BUFFER_BYTES, STRIP_BUFFERS_PER_EYE, LAYER_CACHE_SPANS, LAYER_CACHE_SPAN_BYTES);
+    std::fs::write(out_dir.join(GENERATED_FILE), src)
+        .unwrap_or_else(|err| panic!("can't write {}: {}", GENERATED_FILE, err));
+}
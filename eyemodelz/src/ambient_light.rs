@@ -0,0 +1,109 @@
+//!
+//! Backlight brightness that follows the room's light.
+//!
+//! A light sensor (a photoresistor on an ADC pin, or a lux sensor) is read every so often.
+//! Its readings are smoothed, so a passing shadow or a flickering lamp doesn't pump the backlight,
+//! then mapped through an `AmbientCurve` to a brightness. The brightness only moves once it's a few
+//! percent from where it is, so it settles instead of dithering between neighboring values.
+//!
+
+/// Maps a light level, in the sensor's units, to a backlight brightness:
+/// linear between points, and flat beyond the first and last
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AmbientCurve {
+    /// (light level, brightness percent), in order of increasing light level
+    pub points: &'static [(u16, u8)],
+}
+
+impl AmbientCurve {
+    /// A photoresistor pulling a 12-bit ADC pin up as the light rises: dim in a dark room, full in daylight
+    pub const PHOTORESISTOR: Self = Self { points: &[(150, 8), (800, 25), (2200, 60), (3500, 100)] };
+    /// A lux sensor: a lamp-lit room around 100 lux, an overcast day outdoors in the thousands
+    pub const LUX: Self = Self { points: &[(1, 8), (50, 25), (400, 60), (5000, 100)] };
+
+    /// The brightness for a light `level`
+    pub fn brightness_pct(&self, level: u16) -> u8 {
+        let Some(&(first_level, first_pct)) = self.points.first() else {
+            return 100;
+        };
+        if level <= first_level {
+            return first_pct;
+        }
+        for pair in self.points.windows(2) {
+            let ((lo_level, lo_pct), (hi_level, hi_pct)) = (pair[0], pair[1]);
+            if level <= hi_level {
+                let span = (hi_level - lo_level).max(1) as i32;
+                let rise = hi_pct as i32 - lo_pct as i32;
+                let offset = (level - lo_level) as i32 * rise;
+                return (lo_pct as i32 + (2 * offset + span * offset.signum()) / (2 * span)) as u8;
+            }
+        }
+        self.points.last().map_or(100, |&(_, pct)| pct)
+    }
+}
+
+/// How quickly the brightness follows the light
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AmbientTiming {
+    /// Readings are smoothed over roughly this long: a step change in the light is
+    /// about two thirds followed after this time
+    pub time_constant_ms: u32,
+    /// The brightness holds until the smoothed light calls for one at least this far away
+    pub deadband_pct: u8,
+}
+
+impl AmbientTiming {
+    /// Slow enough to ride out a hand waved past the sensor, quick enough to follow the lights going out
+    pub const DEFAULT: Self = Self { time_constant_ms: 2000, deadband_pct: 3 };
+}
+
+/// Smooths light readings into a steady backlight brightness
+#[derive(Clone, Debug)]
+pub struct AmbientBrightness {
+    curve: AmbientCurve,
+    timing: AmbientTiming,
+    /// The smoothed light level, in 256ths, and when it was last updated
+    smoothed: Option<(u32, u64)>,
+    brightness_pct: u8,
+}
+
+impl AmbientBrightness {
+    pub fn new(curve: AmbientCurve, timing: AmbientTiming) -> Self {
+        Self { curve, timing, smoothed: None, brightness_pct: curve.brightness_pct(0) }
+    }
+
+    /// The brightness to show, as of the last reading
+    pub fn brightness_pct(&self) -> u8 {
+        self.brightness_pct
+    }
+
+    /// The smoothed light level, as of the last reading, if there's been one
+    pub fn smoothed_level(&self) -> Option<u16> {
+        self.smoothed.map(|(level, _)| ((level + 128) >> 8) as u16)
+    }
+
+    /// Take a light reading at `now_ms`, returning the brightness to show.
+    /// The first reading is taken as it is; later ones are blended in by how long it's been since the last.
+    pub fn update(&mut self, level: u16, now_ms: u64) -> u8 {
+        let reading = (level as u32) << 8;
+        let smoothed = match self.smoothed {
+            None => reading,
+            Some((smoothed, last_ms)) => {
+                let elapsed = now_ms.saturating_sub(last_ms).min(u32::MAX as u64) as i64;
+                let time_constant = self.timing.time_constant_ms as i64;
+                // an exponential moving average, weighted by the time since the last reading
+                let delta = reading as i64 - smoothed as i64;
+                (smoothed as i64 + delta * elapsed / (time_constant + elapsed).max(1)) as u32
+            }
+        };
+        self.smoothed = Some((smoothed, now_ms));
+
+        let target = self.curve.brightness_pct(((smoothed + 128) >> 8) as u16);
+        let at_an_end = [self.curve.brightness_pct(0), self.curve.brightness_pct(u16::MAX)].contains(&target);
+        // always reach the ends of the curve, so the darkest room gets the dimmest setting
+        if target.abs_diff(self.brightness_pct) >= self.timing.deadband_pct || at_an_end {
+            self.brightness_pct = target;
+        }
+        self.brightness_pct
+    }
+}
@@ -0,0 +1,102 @@
+//!
+//! Anti-aliased polygon fills.
+//!
+//! Each pixel row is sampled along a few sub-rows, and each sub-row's crossings are kept to a
+//! sixteenth of a pixel, so every pixel the polygon touches gets a coverage: how much of it is inside.
+//! Pixels wholly inside come out in solid runs, and the pixels along the edges with partial coverage,
+//! to be blended over what's already drawn.
+//!
+
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::Point;
+
+/// The coverage of a pixel wholly inside the polygon
+pub const FULL_COVERAGE: u8 = (SUB_ROWS * SUB_COLS) as u8;
+
+/// Samples per pixel row
+const SUB_ROWS: i32 = 4;
+/// Resolution of the crossings along each sub-row, per pixel
+const SUB_COLS: i32 = 16;
+
+/// The most edges expected to cross one sub-row; any beyond are ignored
+const MAX_CROSSINGS: usize = 32;
+
+/// Inclusive-exclusive ranges of x inside the polygon along each sub-row of a pixel row, in sub-columns
+type SubSpans = heapless::Vec<(i32, i32), { 2 * MAX_CROSSINGS }>;
+
+fn floor_div(num: i32, den: i32) -> i32 {
+    if den < 0 { (-num).div_euclid(-den) } else { num.div_euclid(den) }
+}
+
+/// Blend `over` onto `under` by `coverage`, from 0 (all `under`) to `FULL_COVERAGE` (all `over`)
+pub fn blend_rgb565(under: Rgb565, over: Rgb565, coverage: u8) -> Rgb565 {
+    let coverage = coverage.min(FULL_COVERAGE) as u16;
+    let full = FULL_COVERAGE as u16;
+    let mix = |a: u8, b: u8| ((a as u16 * (full - coverage) + b as u16 * coverage + full / 2) / full) as u8;
+    Rgb565::new(mix(under.r(), over.r()), mix(under.g(), over.g()), mix(under.b(), over.b()))
+}
+
+/// Add the spans inside the closed polygon along sub-row `sub_y` (in eighths of a pixel, always odd)
+fn add_sub_row(vertices: &[Point], sub_y: i32, spans: &mut SubSpans) {
+    let mut crossings: heapless::Vec<i32, MAX_CROSSINGS> = heapless::Vec::new();
+    for (a, b) in vertices.iter().zip(vertices[1..].iter().chain(&vertices[..1])) {
+        let (a_y, b_y) = (2 * SUB_ROWS * a.y, 2 * SUB_ROWS * b.y);
+        // sub-rows fall between pixel rows, so they never pass exactly through a vertex
+        if (a_y < sub_y) != (b_y < sub_y) {
+            let (dx, dy) = (b.x - a.x, b_y - a_y);
+            // where the edge crosses, rounded to the nearest sub-column
+            let offset = SUB_COLS * (sub_y - a_y) * dx * dy.signum();
+            let x = SUB_COLS * a.x + floor_div(2 * offset + dy.abs(), 2 * dy.abs());
+            let _ = crossings.push(x);
+        }
+    }
+    crossings.sort_unstable();
+    for pair in crossings.chunks_exact(2) {
+        if pair[0] < pair[1] {
+            let _ = spans.push((pair[0], pair[1]));
+        }
+    }
+}
+
+/// The coverage of pixel column `x` by the spans
+fn coverage_at(spans: &SubSpans, x: i32) -> i32 {
+    let (left, right) = (x * SUB_COLS, (x + 1) * SUB_COLS);
+    spans.iter().map(|&(start, end)| (end.min(right) - start.max(left)).max(0)).sum()
+}
+
+/// Fill the closed polygon through `vertices` with anti-aliased edges.
+/// `fill_span` is given each run of pixels along a row that share a coverage: its first pixel,
+/// its length and its coverage, from 1 to `FULL_COVERAGE`. Each pixel is given once at most.
+/// Vertices should be within a few thousand pixels of the origin, so the arithmetic fits in an `i32`.
+pub fn fill_anti_aliased<F: FnMut(Point, u32, u8)>(vertices: &[Point], mut fill_span: F) {
+    if vertices.len() < 3 {
+        return;
+    }
+    let min_y = vertices.iter().map(|point| point.y).min().unwrap_or(0);
+    let max_y = vertices.iter().map(|point| point.y).max().unwrap_or(0);
+    let mut spans = SubSpans::new();
+    let mut emit = |x: i32, y: i32, len: i32, coverage: i32| {
+        if len > 0 && coverage > 0 {
+            fill_span(Point::new(x, y), len as u32, coverage as u8);
+        }
+    };
+    for y in min_y..max_y {
+        spans.clear();
+        for sub_row in 0..SUB_ROWS {
+            add_sub_row(vertices, 2 * SUB_ROWS * y + 2 * sub_row + 1, &mut spans);
+        }
+        // the pixels where a span starts or ends partway; between them, the coverage doesn't change
+        let mut edges: heapless::Vec<i32, { 4 * MAX_CROSSINGS }> = spans.iter()
+            .flat_map(|&(start, end)| [floor_div(start, SUB_COLS), floor_div(end - 1, SUB_COLS)])
+            .collect();
+        edges.sort_unstable();
+        for (idx, &x) in edges.iter().enumerate() {
+            if idx == 0 || edges[idx - 1] != x {
+                emit(x, y, 1, coverage_at(&spans, x));
+            }
+            if let Some(&next_x) = edges.get(idx + 1) {
+                emit(x + 1, y, next_x - x - 1, coverage_at(&spans, x + 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//!
+//! Backlight dimming that looks even to the eye.
+//!
+//! Brightness percentages are perceived lightness, as in CIE 1931 L*, and `LIGHTNESS_TO_LUMINANCE`
+//! turns each into the share of the time the backlight LEDs are lit. Halfway to full brightness
+//! looks half as bright, and each step near the bottom is a small fraction of a percent of duty,
+//! where a linear duty cycle would jump visibly.
+//!
+//! Changes of brightness go through a `BacklightFade`, which moves toward the brightness asked for
+//! at a limited rate, so a change of mode or a jump from the light sensor glides instead of snapping.
+//!
+
+/// Full luminance in `LIGHTNESS_TO_LUMINANCE`
+pub const FULL_LUMINANCE: u16 = u16::MAX;
+
+/// The luminance (from 0 to `FULL_LUMINANCE`) of each whole percent of lightness, from 0 to 100
+pub const LIGHTNESS_TO_LUMINANCE: [u16; 101] = lightness_table();
+
+const fn lightness_table() -> [u16; 101] {
+    let mut table = [0u16; 101];
+    let full = FULL_LUMINANCE as u64;
+    let mut pct = 0;
+    while pct <= 100 {
+        let lightness = pct as u64;
+        // CIE 1931: linear below L* = 8, then ((L* + 16) / 116) cubed
+        table[pct] = if lightness <= 8 {
+            ((full * lightness * 10 + 9033 / 2) / 9033) as u16
+        } else {
+            let base = lightness + 16;
+            ((full * base * base * base + 116 * 116 * 116 / 2) / (116 * 116 * 116)) as u16
+        };
+        pct += 1;
+    }
+    table
+}
+
+/// The luminance for a lightness in 256ths of a percent, interpolated between whole percents
+pub fn luminance(lightness_x256: u32) -> u16 {
+    let lightness_x256 = lightness_x256.min(100 << 8);
+    let pct = (lightness_x256 >> 8) as usize;
+    let frac = lightness_x256 & 0xFF;
+    let lo = LIGHTNESS_TO_LUMINANCE[pct] as u32;
+    let hi = LIGHTNESS_TO_LUMINANCE[(pct + 1).min(100)] as u32;
+    (lo + ((hi - lo) * frac + 128) / 256) as u16
+}
+
+/// The PWM duty cycle, out of `max_duty`, for a luminance.
+/// Any light at all gets a duty of at least 1, so the dimmest setting never goes dark.
+pub fn duty_cycle(luminance: u16, max_duty: u16) -> u16 {
+    if luminance == 0 {
+        return 0;
+    }
+    let duty = (luminance as u32 * max_duty as u32 + FULL_LUMINANCE as u32 / 2) / FULL_LUMINANCE as u32;
+    duty.max(1) as u16
+}
+
+/// Moves the backlight toward the brightness asked for at a limited rate
+#[derive(Clone, Debug)]
+pub struct BacklightFade {
+    /// How fast the lightness may change, in percent per second
+    rate_pct_per_s: u32,
+    /// The lightness, in 256ths of a percent
+    lightness_x256: u32,
+    last_ms: Option<u64>,
+}
+
+impl BacklightFade {
+    /// Fast enough that a change of mode settles in a fraction of a second, slow enough not to blink
+    pub const DEFAULT_RATE_PCT_PER_S: u32 = 200;
+
+    /// Start dark, so the first brightness asked for fades in
+    pub fn new(rate_pct_per_s: u32) -> Self {
+        Self { rate_pct_per_s, lightness_x256: 0, last_ms: None }
+    }
+
+    /// The lightness, in 256ths of a percent
+    pub fn lightness_x256(&self) -> u32 {
+        self.lightness_x256
+    }
+
+    /// Whether the fade has arrived at `target_pct`
+    pub fn is_at(&self, target_pct: u8) -> bool {
+        self.lightness_x256 == (target_pct.min(100) as u32) << 8
+    }
+
+    /// Move toward `target_pct` as far as the rate allows since the last update, returning the luminance to show
+    pub fn update(&mut self, target_pct: u8, now_ms: u64) -> u16 {
+        let target = (target_pct.min(100) as u32) << 8;
+        let elapsed = self.last_ms.map_or(0, |last_ms| now_ms.saturating_sub(last_ms));
+        self.last_ms = Some(now_ms);
+        let max_step = (elapsed.saturating_mul(self.rate_pct_per_s as u64 * 256) / 1000).min(u32::MAX as u64) as u32;
+        self.lightness_x256 = if target > self.lightness_x256 {
+            self.lightness_x256.saturating_add(max_step).min(target)
+        } else {
+            self.lightness_x256.saturating_sub(max_step).max(target)
+        };
+        luminance(self.lightness_x256)
+    }
+}
@@ -0,0 +1,317 @@
+//!
+//! Blinking: a time-driven state machine for lid closure, and the lid geometry to render it.
+//!
+//! The state machine runs on its own millisecond clock, independent of the frame rate,
+//! and reports how far each eye's lids are closed as a `MorphFraction`.
+//!
+
+use embedded_graphics::prelude::Point;
+
+use crate::morph::{MorphFraction, MorphVertices, MAX_MORPH_VERTICES};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlinkPhase {
+    Open,
+    Closing,
+    Closed,
+    Opening,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlinkKind {
+    /// Both eyes blink once
+    Single,
+    /// Both eyes blink twice in quick succession
+    Double,
+    WinkLeft,
+    WinkRight,
+}
+
+impl BlinkKind {
+    /// Whether this blink closes the lids of the given eye
+    pub const fn closes_eye(self, is_left: bool) -> bool {
+        match self {
+            BlinkKind::Single | BlinkKind::Double => true,
+            BlinkKind::WinkLeft => is_left,
+            BlinkKind::WinkRight => !is_left,
+        }
+    }
+}
+
+/// Durations of each blink phase, and how often spontaneous blinks happen
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlinkTiming {
+    pub closing_ms: u32,
+    pub closed_ms: u32,
+    pub opening_ms: u32,
+    /// Pause between the two blinks of a double blink
+    pub double_gap_ms: u32,
+    /// Spontaneous blinks happen at a random interval in this range
+    pub min_interval_ms: u32,
+    pub max_interval_ms: u32,
+    /// One in this many spontaneous blinks is a double blink (0 for never)
+    pub double_one_in: u32,
+}
+
+impl BlinkTiming {
+    /// Roughly human timing: lids close faster than they open
+    pub const HUMAN: Self = Self {
+        closing_ms: 90,
+        closed_ms: 50,
+        opening_ms: 170,
+        double_gap_ms: 120,
+        min_interval_ms: 2000,
+        max_interval_ms: 6000,
+        double_one_in: 6,
+    };
+}
+
+/// How far each eye's lids are closed, from open (START) to shut (END)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LidClosure {
+    pub left: MorphFraction,
+    pub right: MorphFraction,
+}
+
+impl LidClosure {
+    pub const OPEN: Self = Self { left: MorphFraction::START, right: MorphFraction::START };
+
+    pub const fn for_eye_side(&self, is_left: bool) -> MorphFraction {
+        if is_left { self.left } else { self.right }
+    }
+}
+
+/// Blink state machine. Call `update` with the current time before rendering each frame.
+pub struct BlinkController {
+    timing: BlinkTiming,
+    phase: BlinkPhase,
+    phase_start_ms: u64,
+    kind: BlinkKind,
+    /// A triggered blink (or the second half of a double blink) to start, and when
+    queued: Option<(BlinkKind, u64)>,
+    /// The next spontaneous blink, and when, if spontaneous blinking is enabled
+    next_spontaneous: Option<(BlinkKind, u64)>,
+    spontaneous: bool,
+    rng_state: u32,
+}
+
+impl BlinkController {
+    /// Create a controller with open eyes. The seed varies the spontaneous blink intervals.
+    pub fn new(timing: BlinkTiming, seed: u32, now_ms: u64) -> Self {
+        let mut controller = Self {
+            timing,
+            phase: BlinkPhase::Open,
+            phase_start_ms: now_ms,
+            kind: BlinkKind::Single,
+            queued: None,
+            next_spontaneous: None,
+            spontaneous: true,
+            rng_state: seed | 1, // xorshift state must be nonzero
+        };
+        controller.schedule_spontaneous(now_ms);
+        controller
+    }
+
+    pub fn phase(&self) -> BlinkPhase {
+        self.phase
+    }
+
+    /// The blink in progress, if any
+    pub fn active_kind(&self) -> Option<BlinkKind> {
+        if self.phase == BlinkPhase::Open { None } else { Some(self.kind) }
+    }
+
+    /// Whether a blink is in progress, or a triggered one (such as the second half of a double blink) is waiting
+    pub fn is_busy(&self) -> bool {
+        self.phase != BlinkPhase::Open || self.queued.is_some()
+    }
+
+    /// Enable or disable spontaneous blinking. Triggered blinks still happen.
+    pub fn set_spontaneous(&mut self, enabled: bool, now_ms: u64) {
+        if enabled != self.spontaneous {
+            self.spontaneous = enabled;
+            self.schedule_spontaneous(now_ms);
+        }
+    }
+
+    /// Request a blink. It starts immediately if the eyes are open,
+    /// otherwise as soon as the current blink finishes. A request made while another is
+    /// already waiting, or during a double blink, is dropped rather than cutting in.
+    pub fn trigger(&mut self, kind: BlinkKind, now_ms: u64) {
+        let double_in_progress = self.phase != BlinkPhase::Open && self.kind == BlinkKind::Double;
+        if self.queued.is_some() || double_in_progress {
+            return;
+        }
+        self.queued = Some((kind, now_ms));
+        if self.phase == BlinkPhase::Open {
+            self.update(now_ms);
+        }
+    }
+
+    fn next_random(&mut self) -> u32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        x
+    }
+
+    fn schedule_spontaneous(&mut self, now_ms: u64) {
+        if !self.spontaneous {
+            self.next_spontaneous = None;
+            return;
+        }
+        let span = self.timing.max_interval_ms.saturating_sub(self.timing.min_interval_ms);
+        let interval = self.timing.min_interval_ms + if span > 0 { self.next_random() % span } else { 0 };
+        let kind =
+            if self.timing.double_one_in > 0 && self.next_random().is_multiple_of(self.timing.double_one_in) { BlinkKind::Double }
+            else { BlinkKind::Single };
+        self.next_spontaneous = Some((kind, now_ms + interval as u64));
+    }
+
+    /// Take whichever of the triggered or spontaneous blinks is due first, if either is due
+    fn next_due_blink(&mut self, now_ms: u64) -> Option<(BlinkKind, u64)> {
+        let queued_first = match (self.queued, self.next_spontaneous) {
+            (Some((_, queued_ms)), Some((_, spontaneous_ms))) => queued_ms <= spontaneous_ms,
+            (queued, _) => queued.is_some(),
+        };
+        let slot = if queued_first { &mut self.queued } else { &mut self.next_spontaneous };
+        match *slot {
+            Some((_, start_ms)) if now_ms >= start_ms => slot.take(),
+            _ => None,
+        }
+    }
+
+    /// Advance the state machine to `now_ms`, and return the resulting lid closure for each eye
+    pub fn update(&mut self, now_ms: u64) -> LidClosure {
+        // step through every phase boundary we've passed, in case frames are slow
+        loop {
+            let elapsed = now_ms.saturating_sub(self.phase_start_ms);
+            let (duration, next_phase) = match self.phase {
+                BlinkPhase::Open => match self.next_due_blink(now_ms) {
+                    Some((kind, start_ms)) => {
+                        self.kind = kind;
+                        self.phase = BlinkPhase::Closing;
+                        // a blink triggered during another starts when the eyes have opened
+                        self.phase_start_ms = start_ms.max(self.phase_start_ms);
+                        continue;
+                    }
+                    _ => break,
+                },
+                BlinkPhase::Closing => (self.timing.closing_ms, BlinkPhase::Closed),
+                BlinkPhase::Closed => (self.timing.closed_ms, BlinkPhase::Opening),
+                BlinkPhase::Opening => (self.timing.opening_ms, BlinkPhase::Open),
+            };
+            if elapsed < duration as u64 {
+                break;
+            }
+            let boundary_ms = self.phase_start_ms + duration as u64;
+            self.phase = next_phase;
+            self.phase_start_ms = boundary_ms;
+            if next_phase == BlinkPhase::Open {
+                if self.kind == BlinkKind::Double && self.queued.is_none() {
+                    // the second half of a double blink is an ordinary blink
+                    self.queued = Some((BlinkKind::Single, boundary_ms + self.timing.double_gap_ms as u64));
+                }
+                // the spontaneous interval restarts after every blink
+                self.schedule_spontaneous(boundary_ms);
+            }
+        }
+        self.closure(now_ms)
+    }
+
+    fn closure(&self, now_ms: u64) -> LidClosure {
+        let elapsed = now_ms.saturating_sub(self.phase_start_ms) as u32;
+        let amount = match self.phase {
+            BlinkPhase::Open => return LidClosure::OPEN,
+            BlinkPhase::Closing => MorphFraction::from_ratio(elapsed, self.timing.closing_ms),
+            BlinkPhase::Closed => MorphFraction::END,
+            BlinkPhase::Opening => MorphFraction::from_ratio(elapsed, self.timing.opening_ms).inverse(),
+        };
+        let for_eye = |is_left| if self.kind.closes_eye(is_left) { amount } else { MorphFraction::START };
+        LidClosure { left: for_eye(true), right: for_eye(false) }
+    }
+}
+
+/// Share of the gap between the lids that the upper lid covers when closing; the lower lid rises the rest
+pub const UPPER_LID_BLINK_SHARE_PCT: i32 = 85;
+
+/// The top and bottom of a polygon's outline along the vertical line at `x`
+pub fn vertical_span_at(vertices: &[Point], x: i32) -> Option<(i32, i32)> {
+    let mut span: Option<(i32, i32)> = None;
+    let mut include = |y: i32| {
+        span = Some(match span {
+            None => (y, y),
+            Some((top, bottom)) => (top.min(y), bottom.max(y)),
+        });
+    };
+    for (idx, p0) in vertices.iter().enumerate() {
+        let p1 = vertices[(idx + 1) % vertices.len()];
+        if x < p0.x.min(p1.x) || x > p0.x.max(p1.x) {
+            continue;
+        }
+        if p0.x == p1.x {
+            include(p0.y);
+            include(p1.y);
+        }
+        else {
+            include(p0.y + (p1.y - p0.y) * (x - p0.x) / (p1.x - p0.x));
+        }
+    }
+    span
+}
+
+/// Clamp x into the horizontal extent of the polygon, so that every x has a span
+fn clamp_to_x_range(vertices: &[Point], x: i32) -> i32 {
+    let min_x = vertices.iter().map(|p| p.x).min().unwrap_or(x);
+    let max_x = vertices.iter().map(|p| p.x).max().unwrap_or(x);
+    x.clamp(min_x, max_x)
+}
+
+/// Where the upper and lower lids meet when fully closed, at `x`
+pub fn lid_meeting_y(upper_lid: &[Point], lower_lid: &[Point], x: i32) -> Option<i32> {
+    let (_, upper_bottom) = vertical_span_at(upper_lid, clamp_to_x_range(upper_lid, x))?;
+    let (lower_top, _) = vertical_span_at(lower_lid, clamp_to_x_range(lower_lid, x))?;
+    Some(upper_bottom + (lower_top - upper_bottom) * UPPER_LID_BLINK_SHARE_PCT / 100)
+}
+
+/// Close part of an eyelid (such as its shine or shadow) by the given fraction.
+/// The lid is treated as a band: its outer edge stays put, and its inner (lash) edge moves
+/// toward the line where the lids meet, stretching everything in between.
+/// `upper_lid` and `lower_lid` are the main shapes of each lid, which define the bands.
+pub fn close_lid_vertices(part: &[Point], is_upper: bool, upper_lid: &[Point], lower_lid: &[Point],
+    closure: MorphFraction) -> Option<MorphVertices>
+{
+    if part.len() > MAX_MORPH_VERTICES {
+        return None;
+    }
+    let band = if is_upper { upper_lid } else { lower_lid };
+    let mut vertices = MorphVertices::new();
+    for vertex in part {
+        let mut shifted = *vertex;
+        let span = vertical_span_at(band, clamp_to_x_range(band, vertex.x));
+        let meeting = lid_meeting_y(upper_lid, lower_lid, vertex.x);
+        if let (Some((band_top, band_bottom)), Some(meeting_y)) = (span, meeting) {
+            let height = band_bottom - band_top;
+            // how far this vertex is from the lid's fixed edge toward its moving edge, out of height,
+            // and how far the moving edge travels when fully closed
+            let (depth, travel) =
+                if is_upper { (vertex.y - band_top, meeting_y - band_bottom) }
+                else { (band_bottom - vertex.y, meeting_y - band_top) };
+            let closing_travel = if is_upper { travel > 0 } else { travel < 0 };
+            if height > 0 && closing_travel {
+                let depth = depth.clamp(0, height) as i64;
+                let scaled = travel as i64 * depth * closure.raw() as i64;
+                shifted.y += (scaled / (height as i64 * MorphFraction::SCALE as i64)) as i32;
+            }
+        }
+        // capacity was checked above
+        let _ = vertices.push(shifted);
+    }
+    Some(vertices)
+}
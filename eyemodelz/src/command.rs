@@ -0,0 +1,220 @@
+//!
+//! A line-based text protocol for controlling the eyes live, eg over USB serial.
+//!
+//! Each line is a command word followed by its arguments, separated by spaces.
+//! Words are case-insensitive. Setting a value to `auto` hands it back to the current mode.
+//! - `mode <name|index>`
+//! - `gaze <direction>`, as a name (NorthWest, ...) or grid digits (00..22)
+//! - `gaze <x> <y>`, each -1..1, x from west to east and y from north to south
+//! - `gaze auto`
+//! - `emotion <name|index|auto>`
+//! - `iris <RRGGBB|auto>` and `skin <RRGGBB|auto>`, as hex with an optional leading `#`
+//! - `brightness <0..100|auto>`
+//! - `pupil <60..150|auto>`, as a percentage of the pupil's authored size
+//! - `status` replies with the current state
+//! - `help`
+//!
+//! Replies are a single line: `ok`, `err <reason>`, or the status.
+//!
+
+use core::fmt;
+use core::str::SplitAsciiWhitespace;
+
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+
+use crate::pupil::PupilSize;
+use crate::{EmotionExpression, GazeDirection, GazeVector};
+
+/// The longest command line we accept, in bytes
+pub const MAX_COMMAND_LEN: usize = 64;
+
+pub const HELP_TEXT: &str =
+    "commands: mode <name|index> | gaze <dir|x y|auto> | emotion <name|index|auto> \
+    | iris <RRGGBB|auto> | skin <RRGGBB|auto> | brightness <0..100|auto> | pupil <60..150|auto> | status | help";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    /// Switch to the mode at this index
+    SetMode(u8),
+    /// Hold the gaze here, or None to follow the mode again
+    SetGaze(Option<GazeVector>),
+    SetEmotion(Option<EmotionExpression>),
+    SetIrisColor(Option<Rgb565>),
+    SetSkinColor(Option<Rgb565>),
+    /// Hold the backlight at this percentage, or None to follow the mode again
+    SetBrightness(Option<u8>),
+    /// Hold the pupil at this size, or None to follow the light and emotion again
+    SetPupil(Option<PupilSize>),
+    Status,
+    Help,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandError {
+    Empty,
+    TooLong,
+    UnknownCommand,
+    MissingArgument,
+    BadArgument,
+    ExtraArgument,
+}
+
+impl CommandError {
+    pub const fn message(self) -> &'static str {
+        match self {
+            CommandError::Empty => "empty command",
+            CommandError::TooLong => "command too long",
+            CommandError::UnknownCommand => "unknown command",
+            CommandError::MissingArgument => "missing argument",
+            CommandError::BadArgument => "bad argument",
+            CommandError::ExtraArgument => "too many arguments",
+        }
+    }
+}
+
+/// Match a (case-insensitive) name from the list, or its index
+fn parse_name_or_index(arg: &str, names: &[&str]) -> Option<u8> {
+    if let Ok(idx) = arg.parse::<u8>() {
+        return if (idx as usize) < names.len() { Some(idx) } else { None };
+    }
+    names.iter().position(|name| name.eq_ignore_ascii_case(arg)).map(|idx| idx as u8)
+}
+
+fn parse_gaze_direction(arg: &str) -> Option<GazeDirection> {
+    // note that two digits are grid coordinates, not an enum index
+    let digits = arg.as_bytes();
+    if let [row @ b'0'..=b'9', col @ b'0'..=b'9'] = digits {
+        return GazeDirection::from_row_col((row - b'0') as i8, (col - b'0') as i8);
+    }
+    (0..GazeDirection::MaxCount as u8)
+        .filter_map(|idx| GazeDirection::try_from(idx).ok())
+        .find(|dir| dir.name().eq_ignore_ascii_case(arg))
+}
+
+fn parse_unit_float(arg: &str) -> Option<f32> {
+    arg.parse::<f32>().ok().filter(|val| (-1.0..=1.0).contains(val))
+}
+
+fn parse_hex_color(arg: &str) -> Option<Rgb565> {
+    let hex = arg.strip_prefix('#').unwrap_or(arg);
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    let (r, g, b) = ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+    Some(Rgb565::new(r >> 3, g >> 2, b >> 3))
+}
+
+/// Take the next argument, treating `auto` as None
+fn next_setting<'a>(args: &mut SplitAsciiWhitespace<'a>) -> Result<Option<&'a str>, CommandError> {
+    match args.next() {
+        None => Err(CommandError::MissingArgument),
+        Some(arg) if arg.eq_ignore_ascii_case("auto") => Ok(None),
+        Some(arg) => Ok(Some(arg)),
+    }
+}
+
+/// Parse an optional setting: `auto`, or a value the parser accepts
+fn parse_setting<T>(args: &mut SplitAsciiWhitespace, parse: impl FnOnce(&str) -> Option<T>)
+    -> Result<Option<T>, CommandError>
+{
+    match next_setting(args)? {
+        None => Ok(None),
+        Some(arg) => parse(arg).map(Some).ok_or(CommandError::BadArgument),
+    }
+}
+
+/// Parse one command line (without its line ending).
+/// `mode_names` are the names of the modes, in index order.
+pub fn parse_command(line: &str, mode_names: &[&str]) -> Result<Command, CommandError> {
+    if line.len() > MAX_COMMAND_LEN {
+        return Err(CommandError::TooLong);
+    }
+    let mut args = line.split_ascii_whitespace();
+    let word = args.next().ok_or(CommandError::Empty)?;
+    let is_word = |expected: &str| word.eq_ignore_ascii_case(expected);
+
+    let command =
+        if is_word("mode") {
+            let arg = args.next().ok_or(CommandError::MissingArgument)?;
+            Command::SetMode(parse_name_or_index(arg, mode_names).ok_or(CommandError::BadArgument)?)
+        }
+        else if is_word("gaze") {
+            match next_setting(&mut args)? {
+                None => Command::SetGaze(None),
+                Some(first) => match args.next() {
+                    // a second argument means x and y
+                    Some(second) => match (parse_unit_float(first), parse_unit_float(second)) {
+                        (Some(x), Some(y)) => Command::SetGaze(Some(GazeVector::from_f32(x, y))),
+                        _ => return Err(CommandError::BadArgument),
+                    },
+                    None => Command::SetGaze(Some(
+                        parse_gaze_direction(first).ok_or(CommandError::BadArgument)?.into())),
+                },
+            }
+        }
+        else if is_word("emotion") {
+            Command::SetEmotion(parse_setting(&mut args,
+                |arg| parse_name_or_index(arg, &EmotionExpression::NAMES).and_then(|idx| EmotionExpression::try_from(idx).ok()))?)
+        }
+        else if is_word("iris") {
+            Command::SetIrisColor(parse_setting(&mut args, parse_hex_color)?)
+        }
+        else if is_word("skin") {
+            Command::SetSkinColor(parse_setting(&mut args, parse_hex_color)?)
+        }
+        else if is_word("brightness") {
+            Command::SetBrightness(parse_setting(&mut args,
+                |arg| arg.parse::<u8>().ok().filter(|pct| *pct <= 100))?)
+        }
+        else if is_word("pupil") {
+            Command::SetPupil(parse_setting(&mut args, |arg| arg.parse::<u8>().ok()
+                .filter(|pct| (PupilSize::MIN.pct()..=PupilSize::MAX.pct()).contains(pct))
+                .map(PupilSize::from_pct))?)
+        }
+        else if is_word("status") {
+            Command::Status
+        }
+        else if is_word("help") {
+            Command::Help
+        }
+        else {
+            return Err(CommandError::UnknownCommand);
+        };
+
+    if args.next().is_some() {
+        return Err(CommandError::ExtraArgument);
+    }
+    Ok(command)
+}
+
+/// A snapshot of what the eyes are showing, for the `status` reply
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeStatus<'a> {
+    pub mode_name: &'a str,
+    pub gaze: GazeVector,
+    pub emotion: EmotionExpression,
+    pub iris_color: Rgb565,
+    pub skin_color: Rgb565,
+    pub brightness_pct: u8,
+    pub pupil: PupilSize,
+}
+
+/// Write a color as RGB888 hex, widening each channel so that full scale stays full scale
+fn write_hex_color(f: &mut fmt::Formatter<'_>, color: Rgb565) -> fmt::Result {
+    let (r, g, b) = (color.r(), color.g(), color.b());
+    write!(f, "#{:02X}{:02X}{:02X}", (r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2))
+}
+
+impl fmt::Display for EyeStatus<'_> {
+    /// eg `mode=Meander gaze=-1.000,0.500 emotion=Neutral iris=#405D80 skin=#8CB24A brightness=75 pupil=100`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.gaze.to_f32();
+        write!(f, "mode={} gaze={:.3},{:.3} emotion={} iris=", self.mode_name, x, y, self.emotion.name())?;
+        write_hex_color(f, self.iris_color)?;
+        f.write_str(" skin=")?;
+        write_hex_color(f, self.skin_color)?;
+        write!(f, " brightness={} pupil={}", self.brightness_pct, self.pupil.pct())
+    }
+}
@@ -0,0 +1,129 @@
+//!
+//! Tracking which part of a frame changed, so only that part is sent to the display.
+//!
+//! Every shape drawn into a frame buffer adds its bounding box to a `DirtyRect`,
+//! which grows to the smallest rectangle holding them all.
+//! Sending a rectangle narrower than the frame takes one address window per row,
+//! so `transfer_rect` decides when whole rows, in one window, are cheaper.
+//! Alternatively the rectangle can be packed, a band at a time, into a small buffer
+//! that's sent in one window (see `bands` and `pack_rect`).
+//!
+
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+
+const BYTES_PER_PIXEL: u32 = 2;
+
+/// The union of the regions drawn into a frame since it was last taken
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DirtyRect {
+    /// inclusive min and max corners, as (x, y)
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+impl DirtyRect {
+    /// Nothing drawn yet
+    pub const fn new() -> Self {
+        Self { bounds: None }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_none()
+    }
+
+    /// Add a rectangle that was drawn into
+    pub fn add_rect(&mut self, rect: Rectangle) {
+        if let Some(bottom_right) = rect.bottom_right() {
+            self.add_corners((rect.top_left.x, rect.top_left.y), (bottom_right.x, bottom_right.y));
+        }
+    }
+
+    /// Add the bounding box of a shape's outline, grown by `margin` on every side
+    /// for strokes that extend beyond the outline
+    pub fn add_points(&mut self, points: &[Point], margin: u32) {
+        let Some(first) = points.first() else { return };
+        let (mut min, mut max) = ((first.x, first.y), (first.x, first.y));
+        for point in &points[1..] {
+            min = (min.0.min(point.x), min.1.min(point.y));
+            max = (max.0.max(point.x), max.1.max(point.y));
+        }
+        let margin = margin as i32;
+        self.add_corners((min.0 - margin, min.1 - margin), (max.0 + margin, max.1 + margin));
+    }
+
+    /// Mark the whole frame as drawn
+    pub fn add_frame(&mut self, frame_size: Size) {
+        self.add_rect(Rectangle::new(Point::zero(), frame_size));
+    }
+
+    fn add_corners(&mut self, min: (i32, i32), max: (i32, i32)) {
+        self.bounds = Some(match self.bounds {
+            None => (min, max),
+            Some((old_min, old_max)) => (
+                (old_min.0.min(min.0), old_min.1.min(min.1)),
+                (old_max.0.max(max.0), old_max.1.max(max.1)),
+            ),
+        });
+    }
+
+    /// The region drawn, clipped to a frame of `frame_size`, or None if nothing on the frame was drawn
+    pub fn bounds(&self, frame_size: Size) -> Option<Rectangle> {
+        let (min, max) = self.bounds?;
+        let frame_max = (frame_size.width as i32 - 1, frame_size.height as i32 - 1);
+        let min = (min.0.max(0), min.1.max(0));
+        let max = (max.0.min(frame_max.0), max.1.min(frame_max.1));
+        if min.0 > max.0 || min.1 > max.1 {
+            return None;
+        }
+        Some(Rectangle::with_corners(Point::new(min.0, min.1), Point::new(max.0, max.1)))
+    }
+
+    /// The region drawn, clipped to the frame, leaving this empty for the next frame
+    pub fn take(&mut self, frame_size: Size) -> Option<Rectangle> {
+        let bounds = self.bounds(frame_size);
+        self.bounds = None;
+        bounds
+    }
+}
+
+/// The region of a frame to send to the display so that `dirty` is updated: either `dirty` itself,
+/// sent one row at a time, or the full width rows it spans, sent in one go, whichever is cheaper.
+/// `window_overhead_bytes` is the cost of setting up each address window on the display
+/// (commands, bus turnarounds) in the time it would take to send that many bytes of pixel data.
+pub fn transfer_rect(dirty: Rectangle, frame_width: u32, window_overhead_bytes: u32) -> Rectangle {
+    let rows = dirty.size.height;
+    let per_row_cost = rows * (window_overhead_bytes + dirty.size.width * BYTES_PER_PIXEL);
+    let full_rows_cost = window_overhead_bytes + rows * frame_width * BYTES_PER_PIXEL;
+    if full_rows_cost <= per_row_cost {
+        Rectangle::new(Point::new(0, dirty.top_left.y), Size::new(frame_width, rows))
+    } else {
+        dirty
+    }
+}
+
+/// Split a region into bands of whole rows, top to bottom, each small enough to pack into
+/// `max_band_bytes`, so that each band can be copied out and sent while the next is prepared
+pub fn bands(rect: Rectangle, max_band_bytes: usize) -> impl Iterator<Item = Rectangle> {
+    let row_bytes = (rect.size.width * BYTES_PER_PIXEL) as usize;
+    let band_rows = (max_band_bytes / row_bytes.max(1)).max(1) as u32;
+    let num_bands = rect.size.height.div_ceil(band_rows);
+    (0..num_bands).map(move |band_idx| {
+        let first_row = band_idx * band_rows;
+        let rows = band_rows.min(rect.size.height - first_row);
+        Rectangle::new(rect.top_left + Point::new(0, first_row as i32), Size::new(rect.size.width, rows))
+    })
+}
+
+/// Copy the pixels of `rect` out of a frame `frame_width` pixels wide into `packed`,
+/// row after row with no gaps, returning how many bytes were copied
+pub fn pack_rect(frame: &[u8], frame_width: u32, rect: Rectangle, packed: &mut [u8]) -> usize {
+    let frame_row_bytes = (frame_width * BYTES_PER_PIXEL) as usize;
+    let x_bytes = rect.top_left.x as usize * BYTES_PER_PIXEL as usize;
+    let row_bytes = (rect.size.width * BYTES_PER_PIXEL) as usize;
+    for (row_idx, row) in (rect.top_left.y as usize..).take(rect.size.height as usize).enumerate() {
+        let start = row * frame_row_bytes + x_bytes;
+        packed[row_idx * row_bytes..(row_idx + 1) * row_bytes].copy_from_slice(&frame[start..start + row_bytes]);
+    }
+    row_bytes * rect.size.height as usize
+}
@@ -0,0 +1,120 @@
+//!
+//! Blending from one emotion to another over time.
+//!
+//! An emotion change doesn't snap: the brow and lids morph between the two emotions'
+//! shapes and the backgrounds cross-fade, over a configurable duration.
+//! The blender runs on its own millisecond clock, like blinking and gaze control.
+//!
+
+use crate::morph::MorphFraction;
+use crate::EmotionExpression;
+
+/// How long an emotion change takes by default
+pub const DEFAULT_EMOTION_BLEND_MS: u32 = 400;
+
+/// A point along the change from one emotion to another.
+/// When `progress` is START the expression is entirely `from`, at END entirely `to`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EmotionBlend {
+    pub from: EmotionExpression,
+    pub to: EmotionExpression,
+    pub progress: MorphFraction,
+}
+
+impl EmotionBlend {
+    /// Holding a single emotion
+    pub const fn steady(emotion: EmotionExpression) -> Self {
+        Self { from: emotion, to: emotion, progress: MorphFraction::END }
+    }
+
+    /// Whether this looks like a single emotion, with nothing to blend
+    pub fn is_steady(&self) -> bool {
+        self.from == self.to || self.progress == MorphFraction::START || self.progress == MorphFraction::END
+    }
+
+    /// Pack into a u32, eg for an `AtomicU32`, so that both eyes see the same blend
+    pub const fn to_bits(self) -> u32 {
+        ((self.from as u32) << 24) | ((self.to as u32) << 16) | self.progress.raw() as u32
+    }
+
+    /// Unpack from `to_bits`. Invalid emotions fall back to Neutral.
+    pub fn from_bits(bits: u32) -> Self {
+        let emotion = |val: u32| EmotionExpression::try_from(val as u8).unwrap_or(EmotionExpression::Neutral);
+        Self {
+            from: emotion(bits >> 24),
+            to: emotion((bits >> 16) & 0xFF),
+            progress: MorphFraction::from_raw(bits as u16),
+        }
+    }
+}
+
+/// Ease in and out of a blend, so shapes don't start or stop moving abruptly
+fn ease_in_out(t: MorphFraction) -> MorphFraction {
+    let t = t.to_f32();
+    MorphFraction::from_f32(t * t * (3.0 - 2.0 * t))
+}
+
+/// Emotion change state machine. Call `update` with the wanted emotion and the current time
+/// before rendering each frame.
+pub struct EmotionBlender {
+    duration_ms: u32,
+    from: EmotionExpression,
+    to: EmotionExpression,
+    start_ms: u64,
+    /// How far into the blend it already was at `start_ms`, eg after turning around
+    start_offset_ms: u64,
+}
+
+impl EmotionBlender {
+    /// Create a blender holding `emotion`, which blends to new emotions over `duration_ms`
+    pub fn new(emotion: EmotionExpression, duration_ms: u32) -> Self {
+        Self { duration_ms, from: emotion, to: emotion, start_ms: 0, start_offset_ms: 0 }
+    }
+
+    fn elapsed_ms(&self, now_ms: u64) -> u64 {
+        now_ms.saturating_sub(self.start_ms) + self.start_offset_ms
+    }
+
+    /// Whether a blend is in progress
+    pub fn is_busy(&self, now_ms: u64) -> bool {
+        self.from != self.to && self.elapsed_ms(now_ms) < self.duration_ms as u64
+    }
+
+    /// The linear progress of the blend in progress, before easing
+    fn linear_progress(&self, now_ms: u64) -> MorphFraction {
+        if self.from == self.to {
+            return MorphFraction::END;
+        }
+        MorphFraction::from_ratio(self.elapsed_ms(now_ms).min(u32::MAX as u64) as u32, self.duration_ms)
+    }
+
+    /// Advance to `now_ms`, heading for `target`, and return the resulting blend.
+    /// Changing target partway through a blend reverses smoothly back to where it came from,
+    /// or starts a fresh blend from whichever emotion the face is closer to.
+    pub fn update(&mut self, target: EmotionExpression, now_ms: u64) -> EmotionBlend {
+        let progress = self.linear_progress(now_ms);
+        if progress == MorphFraction::END {
+            self.from = self.to;
+        }
+        if target != self.to {
+            if target == self.from && self.from != self.to {
+                // turn around, keeping the same position along the blend
+                self.start_offset_ms =
+                    progress.inverse().raw() as u64 * self.duration_ms as u64 / MorphFraction::SCALE as u64;
+                self.from = self.to;
+            } else {
+                self.from = if progress < MorphFraction::HALF { self.from } else { self.to };
+                self.start_offset_ms = 0;
+            }
+            self.start_ms = now_ms;
+            self.to = target;
+        }
+        let progress = self.linear_progress(now_ms);
+        if progress == MorphFraction::END {
+            self.from = self.to;
+            return EmotionBlend::steady(self.to);
+        }
+        EmotionBlend { from: self.from, to: self.to, progress: ease_in_out(progress) }
+    }
+}
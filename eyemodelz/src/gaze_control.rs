@@ -0,0 +1,269 @@
+//!
+//! A gaze controller with human-like timing: fixations with a random dwell time,
+//! fast saccades between fixations, microsaccades around the fixation point,
+//! and occasional slow smooth pursuit toward the next target.
+//!
+//! Like the blink controller, it runs on its own millisecond clock, independent of the frame rate.
+//!
+
+use crate::morph::MorphFraction;
+use crate::{GazeDirection, GazeTransition};
+
+/// Where the controller looks next when a fixation ends
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GazeTargets {
+    /// Random targets, favoring StraightAhead
+    Random,
+    /// Visit targets in this order, repeating
+    Sequence(&'static [GazeDirection]),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GazeMotion {
+    Fixation,
+    Saccade,
+    Pursuit,
+}
+
+/// Durations for each kind of eye movement.
+/// Distances are measured in grid cells: one cell is from StraightAhead to an adjacent direction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GazeTiming {
+    /// Each fixation dwells for a random time in this range
+    pub min_fixation_ms: u32,
+    pub max_fixation_ms: u32,
+    /// Saccade duration grows with distance
+    pub saccade_base_ms: u32,
+    pub saccade_ms_per_cell: u32,
+    /// Smooth pursuit moves at a constant, much slower speed
+    pub pursuit_ms_per_cell: u32,
+    /// One in this many moves is a smooth pursuit rather than a saccade (0 for never)
+    pub pursuit_one_in: u32,
+    /// Microsaccades happen at a random interval in this range while fixating
+    pub min_microsaccade_interval_ms: u32,
+    pub max_microsaccade_interval_ms: u32,
+    /// How far a microsaccade moves toward a neighboring direction
+    pub microsaccade_amplitude: MorphFraction,
+}
+
+impl GazeTiming {
+    pub const HUMAN: Self = Self {
+        min_fixation_ms: 250,
+        max_fixation_ms: 1800,
+        saccade_base_ms: 25,
+        saccade_ms_per_cell: 35,
+        pursuit_ms_per_cell: 900,
+        pursuit_one_in: 4,
+        min_microsaccade_interval_ms: 300,
+        max_microsaccade_interval_ms: 1000,
+        microsaccade_amplitude: MorphFraction::from_ratio(1, 8),
+    };
+}
+
+/// Gaze state machine. Call `update` with the current time before rendering each frame.
+pub struct GazeController {
+    timing: GazeTiming,
+    targets: GazeTargets,
+    sequence_idx: usize,
+    motion: GazeMotion,
+    /// The fixation we're at, or moving away from
+    from: GazeDirection,
+    /// The fixation we're moving toward (equal to `from` while fixating)
+    to: GazeDirection,
+    motion_start_ms: u64,
+    motion_duration_ms: u32,
+    fixation_end_ms: u64,
+    /// The neighboring direction we've microsaccaded toward, if we're off-center within a fixation
+    micro_offset: Option<GazeDirection>,
+    next_microsaccade_ms: u64,
+    rng_state: u32,
+}
+
+impl GazeController {
+    /// Create a controller fixating StraightAhead. The seed varies targets and timing.
+    pub fn new(timing: GazeTiming, targets: GazeTargets, seed: u32, now_ms: u64) -> Self {
+        let mut controller = Self {
+            timing,
+            targets,
+            sequence_idx: 0,
+            motion: GazeMotion::Fixation,
+            from: GazeDirection::StraightAhead,
+            to: GazeDirection::StraightAhead,
+            motion_start_ms: now_ms,
+            motion_duration_ms: 0,
+            fixation_end_ms: now_ms,
+            micro_offset: None,
+            next_microsaccade_ms: now_ms,
+            rng_state: seed | 1, // xorshift state must be nonzero
+        };
+        controller.begin_fixation(now_ms);
+        controller
+    }
+
+    pub fn motion(&self) -> GazeMotion {
+        self.motion
+    }
+
+    /// The current fixation target, or the target of the movement in progress
+    pub fn target(&self) -> GazeDirection {
+        self.to
+    }
+
+    /// Change where future fixations go. The current fixation or movement completes as normal.
+    pub fn set_targets(&mut self, targets: GazeTargets) {
+        if targets != self.targets {
+            self.targets = targets;
+            self.sequence_idx = 0;
+        }
+    }
+
+    /// Start a saccade toward `target` now, interrupting any fixation
+    pub fn saccade_to(&mut self, target: GazeDirection, now_ms: u64) {
+        let duration = self.saccade_duration_ms(self.to, target);
+        self.begin_motion(GazeMotion::Saccade, target, duration, now_ms);
+    }
+
+    /// Start a smooth pursuit toward `target` now, interrupting any fixation
+    pub fn pursue(&mut self, target: GazeDirection, now_ms: u64) {
+        let duration = self.pursuit_duration_ms(self.to, target);
+        self.begin_motion(GazeMotion::Pursuit, target, duration, now_ms);
+    }
+
+    fn next_random(&mut self) -> u32 {
+        // xorshift32
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        x
+    }
+
+    fn random_in(&mut self, min: u32, max: u32) -> u32 {
+        let span = max.saturating_sub(min);
+        min + if span > 0 { self.next_random() % span } else { 0 }
+    }
+
+    /// Distance between two directions in grid cells (diagonal neighbors are one cell apart)
+    fn cells_between(a: GazeDirection, b: GazeDirection) -> u32 {
+        let ((row_a, col_a), (row_b, col_b)) = (a.row_col(), b.row_col());
+        row_a.abs_diff(row_b).max(col_a.abs_diff(col_b)) as u32
+    }
+
+    fn saccade_duration_ms(&self, from: GazeDirection, to: GazeDirection) -> u32 {
+        self.timing.saccade_base_ms + self.timing.saccade_ms_per_cell * Self::cells_between(from, to)
+    }
+
+    fn pursuit_duration_ms(&self, from: GazeDirection, to: GazeDirection) -> u32 {
+        self.timing.pursuit_ms_per_cell * Self::cells_between(from, to)
+    }
+
+    fn begin_motion(&mut self, motion: GazeMotion, target: GazeDirection, duration_ms: u32, start_ms: u64) {
+        // a new movement starts from the fixation point, not from any microsaccade offset
+        self.from = self.to;
+        self.to = target;
+        self.motion = motion;
+        self.motion_start_ms = start_ms;
+        self.motion_duration_ms = duration_ms.max(1);
+        self.micro_offset = None;
+    }
+
+    fn begin_fixation(&mut self, start_ms: u64) {
+        self.from = self.to;
+        self.motion = GazeMotion::Fixation;
+        self.micro_offset = None;
+        self.fixation_end_ms = start_ms
+            + self.random_in(self.timing.min_fixation_ms, self.timing.max_fixation_ms).max(1) as u64;
+        self.next_microsaccade_ms = start_ms + self.random_in(
+            self.timing.min_microsaccade_interval_ms, self.timing.max_microsaccade_interval_ms).max(1) as u64;
+    }
+
+    fn choose_next_target(&mut self) -> GazeDirection {
+        match self.targets {
+            GazeTargets::Sequence(order) if !order.is_empty() => {
+                let target = order[self.sequence_idx % order.len()];
+                self.sequence_idx = (self.sequence_idx + 1) % order.len();
+                target
+            }
+            _ => {
+                // people return to looking straight ahead often; otherwise pick any other direction
+                let roll = self.next_random() % 3;
+                if roll == 0 && self.to != GazeDirection::StraightAhead {
+                    GazeDirection::StraightAhead
+                }
+                else {
+                    let others = GazeDirection::MaxCount as u32 - 1;
+                    let mut idx = (self.next_random() % others) as u8;
+                    if idx >= self.to as u8 {
+                        idx += 1;
+                    }
+                    GazeDirection::try_from(idx).unwrap_or(GazeDirection::StraightAhead)
+                }
+            }
+        }
+    }
+
+    fn random_neighbor(&mut self, center: GazeDirection) -> GazeDirection {
+        let (row, col) = center.row_col();
+        let neighbor_idx = self.next_random() % 8;
+        // walk the 8 surrounding cells from the chosen start, taking the first on the grid
+        for offset in 0..8 {
+            let (d_row, d_col) = [(-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1)]
+                [((neighbor_idx + offset) % 8) as usize];
+            if let Some(neighbor) = GazeDirection::from_row_col(row as i8 + d_row, col as i8 + d_col) {
+                return neighbor;
+            }
+        }
+        center
+    }
+
+    /// Advance the state machine to `now_ms`, and return the gaze to render
+    pub fn update(&mut self, now_ms: u64) -> GazeTransition {
+        // step through every movement boundary we've passed, in case frames are slow
+        loop {
+            match self.motion {
+                GazeMotion::Saccade | GazeMotion::Pursuit => {
+                    let elapsed = now_ms.saturating_sub(self.motion_start_ms);
+                    if elapsed < self.motion_duration_ms as u64 {
+                        return GazeTransition {
+                            start: self.from,
+                            end: self.to,
+                            progress: MorphFraction::from_ratio(elapsed as u32, self.motion_duration_ms),
+                        };
+                    }
+                    self.begin_fixation(self.motion_start_ms + self.motion_duration_ms as u64);
+                }
+                GazeMotion::Fixation => {
+                    if now_ms >= self.fixation_end_ms {
+                        let target = self.choose_next_target();
+                        let is_pursuit = self.timing.pursuit_one_in > 0
+                            && self.next_random().is_multiple_of(self.timing.pursuit_one_in);
+                        let (motion, duration) =
+                            if is_pursuit { (GazeMotion::Pursuit, self.pursuit_duration_ms(self.to, target)) }
+                            else { (GazeMotion::Saccade, self.saccade_duration_ms(self.to, target)) };
+                        self.begin_motion(motion, target, duration, self.fixation_end_ms);
+                        continue;
+                    }
+                    // microsaccades alternate between a small offset and back to the fixation point
+                    while now_ms >= self.next_microsaccade_ms {
+                        self.micro_offset = match self.micro_offset {
+                            Some(_) => None,
+                            None => Some(self.random_neighbor(self.to)),
+                        };
+                        self.next_microsaccade_ms += self.random_in(
+                            self.timing.min_microsaccade_interval_ms, self.timing.max_microsaccade_interval_ms).max(1) as u64;
+                    }
+                    return match self.micro_offset {
+                        Some(offset) => GazeTransition {
+                            start: self.to,
+                            end: offset,
+                            progress: self.timing.microsaccade_amplitude,
+                        },
+                        None => GazeTransition { start: self.to, end: self.to, progress: MorphFraction::START },
+                    };
+                }
+            }
+        }
+    }
+}
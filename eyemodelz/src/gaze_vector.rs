@@ -0,0 +1,136 @@
+//!
+//! Continuous gaze coordinates, and how they map onto the authored assets.
+//!
+//! The assets form a sparse 7x7 grid of control points: StraightAhead in the middle,
+//! and the authored look steps along the eight arms out to each `GazeDirection`.
+//! Any gaze in between is resolved to the two arms on either side of it,
+//! each at the same distance from center, blended together.
+//!
+
+use embedded_graphics::prelude::Point;
+
+use crate::morph::{div_round, lerp_point, MorphFraction};
+use crate::{GazeDirection, GazeTransition, LAST_LOOK_STEP_IDX, SPARSE_GRID_ARM_LEN, SPARSE_GRID_DIM};
+
+/// Resolved fractions this close to an authored step snap onto it, absorbing fixed-point rounding
+const SNAP_TOLERANCE: u16 = 4;
+
+/// Where the eyes are looking, from the observer's perspective.
+/// `x` runs from west (-SCALE) to east (SCALE), `y` from north (-SCALE) to south (SCALE),
+/// so (SCALE, SCALE) is the same as GazeDirection::SouthEast.
+/// Stored as fixed point, so it packs into an `AtomicU32` for sharing between cores.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GazeVector {
+    pub x: i16,
+    pub y: i16,
+}
+
+/// A gaze resolved to the assets that describe it: the shape for `primary`,
+/// morphed `weight` of the way toward the shape for `secondary`.
+/// Both are outward transitions from StraightAhead, on neighboring arms, with the same progress.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GazeBlend {
+    pub primary: GazeTransition,
+    pub secondary: GazeTransition,
+    pub weight: MorphFraction,
+}
+
+impl GazeVector {
+    /// The fixed-point value for the edge of the gaze range in each axis
+    pub const SCALE: i16 = 1 << 14;
+    pub const STRAIGHT_AHEAD: Self = Self { x: 0, y: 0 };
+
+    /// Create a gaze vector, clamping each axis to -SCALE..=SCALE
+    pub const fn new(x: i16, y: i16) -> Self {
+        const fn clamp(val: i16) -> i16 {
+            if val > GazeVector::SCALE { GazeVector::SCALE }
+            else if val < -GazeVector::SCALE { -GazeVector::SCALE }
+            else { val }
+        }
+        Self { x: clamp(x), y: clamp(y) }
+    }
+
+    /// Convert from floats in -1..=1, clamping out-of-range values (and NaN to 0)
+    pub fn from_f32(x: f32, y: f32) -> Self {
+        let to_fixed = |val: f32| {
+            let val = if val.is_nan() { 0.0 } else { val.clamp(-1.0, 1.0) };
+            let scaled = val * Self::SCALE as f32;
+            (if scaled < 0.0 { scaled - 0.5 } else { scaled + 0.5 }) as i16
+        };
+        Self::new(to_fixed(x), to_fixed(y))
+    }
+
+    pub fn to_f32(self) -> (f32, f32) {
+        (self.x as f32 / Self::SCALE as f32, self.y as f32 / Self::SCALE as f32)
+    }
+
+    /// The position of a cell of the sparse 7x7 grid, given its (row, column).
+    /// Only the center and the cells along the eight arms are control points with authored assets.
+    pub fn from_sparse_grid(row: u8, col: u8) -> Option<Self> {
+        if row >= SPARSE_GRID_DIM || col >= SPARSE_GRID_DIM {
+            return None;
+        }
+        let arm_len = SPARSE_GRID_ARM_LEN as i32;
+        let to_fixed = |idx: u8| div_round((idx as i32 - arm_len) * Self::SCALE as i32, arm_len) as i16;
+        Some(Self::new(to_fixed(col), to_fixed(row)))
+    }
+
+    /// Interpolate between two gaze vectors, with t from start to end
+    pub fn lerp(start: Self, end: Self, t: MorphFraction) -> Self {
+        let point = lerp_point(Point::new(start.x as i32, start.y as i32), Point::new(end.x as i32, end.y as i32),
+            t.raw() as i32, MorphFraction::SCALE as i32);
+        Self::new(point.x as i16, point.y as i16)
+    }
+
+    /// The grid direction closest to this gaze
+    pub fn nearest_direction(self) -> GazeDirection {
+        let to_grid = |val: i16| if val > Self::SCALE / 2 { 2 } else if val < -Self::SCALE / 2 { 0 } else { 1 };
+        GazeDirection::from_row_col(to_grid(self.y), to_grid(self.x)).unwrap_or(GazeDirection::StraightAhead)
+    }
+
+    /// Pack into a u32, eg for an `AtomicU32`
+    pub const fn to_bits(self) -> u32 {
+        ((self.x as u16 as u32) << 16) | self.y as u16 as u32
+    }
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self::new((bits >> 16) as u16 as i16, bits as u16 as i16)
+    }
+
+    /// Resolve to the arms on either side of this gaze.
+    /// The eight arms divide the grid into triangular sectors; within a sector, the distance
+    /// out along the arms is the gaze's distance from center along the nearest axis,
+    /// and the weight is how far around the sector it is, from the axis arm toward the diagonal arm.
+    pub fn resolve(self) -> GazeBlend {
+        let (abs_x, abs_y) = (self.x.unsigned_abs() as u32, self.y.unsigned_abs() as u32);
+        let (row, col) = (self.y.signum() as i8 + 1, self.x.signum() as i8 + 1);
+        let (axis_row, axis_col, reach, toward_diagonal) =
+            if abs_x >= abs_y { (1, col, abs_x, abs_y) } else { (row, 1, abs_y, abs_x) };
+        let axis = GazeDirection::from_row_col(axis_row, axis_col).unwrap_or(GazeDirection::StraightAhead);
+        let diagonal = GazeDirection::from_row_col(row, col).unwrap_or(GazeDirection::StraightAhead);
+
+        let progress = MorphFraction::from_ratio(reach, Self::SCALE as u32)
+            .snap_to_step(LAST_LOOK_STEP_IDX, SNAP_TOLERANCE);
+        let weight =
+            if reach == 0 { MorphFraction::START }
+            else { MorphFraction::from_ratio(toward_diagonal, reach).snap_to_step(1, SNAP_TOLERANCE) };
+        let outward = |end| GazeTransition { start: GazeDirection::StraightAhead, end, progress };
+        GazeBlend { primary: outward(axis), secondary: outward(diagonal), weight }
+    }
+}
+
+impl From<GazeDirection> for GazeVector {
+    fn from(direction: GazeDirection) -> Self {
+        let (row, col) = direction.row_col();
+        Self::new((col as i16 - 1) * Self::SCALE, (row as i16 - 1) * Self::SCALE)
+    }
+}
+
+impl From<GazeTransition> for GazeVector {
+    /// The gaze partway along a transition, moving in a straight line between its ends
+    fn from(gaze: GazeTransition) -> Self {
+        Self::lerp(gaze.start.into(), gaze.end.into(), gaze.progress)
+    }
+}
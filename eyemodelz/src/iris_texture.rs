@@ -0,0 +1,208 @@
+//!
+//! A procedural iris texture, to fill the iris instead of one flat color.
+//!
+//! Each pixel is shaded from where it lies on the iris: its angle around the center, and its depth,
+//! how far it is from the pupil's rim (0) out to the edge of the iris, the limbus (`FULL_DEPTH`).
+//! Fibers radiate out from the pupil, lighter and darker by angle, finer strands over broader ones,
+//! wavering a little as they go. A lighter collarette rings the inner iris, tinted warmer toward the pupil,
+//! a dark ruff edges the pupil, and the limbal ring darkens the outer edge.
+//!
+//! The iris is taken to be the ellipse filling its bounds, so as the gaze turns the iris away, the pattern
+//! moves and squashes with it, and as the pupil constricts or dilates the fibers stretch or bunch between
+//! its rim and the limbus. Each eye's pattern comes from its own seed, and it's all integer math,
+//! on Rgb565 channels, so the firmware and the simulator shade every pixel the same.
+//!
+
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::Point;
+use embedded_graphics::primitives::Rectangle;
+
+/// Steps in a full turn around the iris
+pub const ANGLE_STEPS: u32 = 1024;
+/// The depth of the limbus, the outer edge of the iris
+pub const FULL_DEPTH: u32 = 256;
+
+/// The furthest from the center measured, in 256ths of the radius
+const MAX_OFFSET: i32 = 4 * FULL_DEPTH as i32;
+/// Angle steps per cell of the broad and fine fiber noise, each dividing `ANGLE_STEPS`
+const BROAD_FIBER_STEPS: u32 = 32;
+const FINE_FIBER_STEPS: u32 = 8;
+/// Depth per cell of the noise that makes the fibers waver
+const WAVER_DEPTH_STEPS: u32 = 64;
+/// Where the collarette is brightest, and how far either side it fades out
+const COLLARETTE_DEPTH: u32 = 80;
+const COLLARETTE_HALF_WIDTH: u32 = 40;
+/// How deep the ruff around the pupil reaches
+const RUFF_DEPTH: u32 = 16;
+/// Where the limbal ring begins to darken the iris, and how dark it is at the limbus, out of 256
+const LIMBAL_DEPTH: u32 = 200;
+const LIMBUS_LIGHTNESS: u32 = 100;
+/// How far the inner iris is tinted toward `WARM_TINT` at most, out of 256
+const MAX_WARMTH: u32 = 72;
+/// A light amber, as in the inner iris of many eyes
+const WARM_TINT: Rgb565 = Rgb565::new(0x19, 0x2E, 0x0B);
+
+/// Where a pixel lies on the iris
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IrisCoords {
+    /// From 0 to `ANGLE_STEPS`, counterclockwise on the panel from the right
+    pub angle: u32,
+    /// From 0 at the pupil's rim to `FULL_DEPTH` at the limbus, and kept there beyond it
+    pub depth: u32,
+}
+
+/// Shades an iris of one color, with the pattern from `seed`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IrisTexture {
+    pub color: Rgb565,
+    pub seed: u32,
+}
+
+impl IrisTexture {
+    pub const fn new(color: Rgb565, seed: u32) -> Self {
+        Self { color, seed }
+    }
+
+    /// The shader for an iris filling `iris`, around a pupil filling `pupil`, each given by its bounds
+    pub fn shader(self, iris: Rectangle, pupil: Rectangle) -> IrisShader {
+        let width = iris.size.width.max(1) as i32;
+        let height = iris.size.height.max(1) as i32;
+        // the pupil's radius as a share of the iris's, across and down, averaged
+        let pupil_across = pupil.size.width * FULL_DEPTH / width as u32;
+        let pupil_down = pupil.size.height * FULL_DEPTH / height as u32;
+        IrisShader {
+            texture: self,
+            center_x2: Point::new(2 * iris.top_left.x + width - 1, 2 * iris.top_left.y + height - 1),
+            scale_x: (1 << 16) / width,
+            scale_y: (1 << 16) / height,
+            pupil_radius: ((pupil_across + pupil_down) / 2).min(FULL_DEPTH - 1),
+        }
+    }
+}
+
+/// The texture fitted to one iris, as drawn on one frame
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IrisShader {
+    texture: IrisTexture,
+    /// The iris's center, in half pixels
+    center_x2: Point,
+    /// 65536 over the iris's width and height, to measure offsets in 256ths of its radius
+    scale_x: i32,
+    scale_y: i32,
+    /// The pupil's radius, in 256ths of the iris's
+    pupil_radius: u32,
+}
+
+impl IrisShader {
+    /// Where the pixel `at` lies on the iris
+    pub fn coords(&self, at: Point) -> IrisCoords {
+        // offsets from the center in 256ths of the radius, from the middle of the pixel,
+        // kept to a few radii so they square without overflowing
+        let offset = |from_x2: i32, scale: i32| ((from_x2 * scale) >> 8).clamp(-MAX_OFFSET, MAX_OFFSET);
+        let u = offset(2 * at.x - self.center_x2.x, self.scale_x);
+        let v = offset(2 * at.y - self.center_x2.y, self.scale_y);
+        let radius = isqrt((u * u + v * v) as u32);
+        let depth = radius.saturating_sub(self.pupil_radius) * FULL_DEPTH / (FULL_DEPTH - self.pupil_radius);
+        // up is negative on the panel
+        IrisCoords { angle: angle_steps(u, -v), depth: depth.min(FULL_DEPTH) }
+    }
+
+    /// The color of the iris at the pixel `at`
+    pub fn shade(&self, at: Point) -> Rgb565 {
+        let IrisCoords { angle, depth } = self.coords(at);
+        let seed = self.texture.seed;
+
+        // fibers waver a few degrees either way as they run out from the pupil
+        let waver = value_noise(seed ^ 0x9E37_79B9, depth, WAVER_DEPTH_STEPS, FULL_DEPTH) as u32;
+        let fiber_angle = (angle + ANGLE_STEPS + waver / 16 - 8) % ANGLE_STEPS;
+        let broad = value_noise(seed, angle, BROAD_FIBER_STEPS, ANGLE_STEPS) as u32;
+        let fine = value_noise(seed ^ 0x85EB_CA6B, fiber_angle, FINE_FIBER_STEPS, ANGLE_STEPS) as u32;
+
+        // lightness out of 256: fibers from about 80% to 120%, then the rings
+        let mut lightness = 200 + (3 * broad + 5 * fine) * 112 / (8 * 256);
+        lightness += COLLARETTE_HALF_WIDTH.saturating_sub(depth.abs_diff(COLLARETTE_DEPTH));
+        lightness -= RUFF_DEPTH.saturating_sub(depth) * 6;
+        if depth > LIMBAL_DEPTH {
+            let into_ring = depth - LIMBAL_DEPTH;
+            lightness = lightness * (256 - into_ring * (256 - LIMBUS_LIGHTNESS) / (FULL_DEPTH - LIMBAL_DEPTH)) / 256;
+        }
+
+        // warmer toward the pupil, in patches along the broad fibers
+        let warmth = (FULL_DEPTH / 2).saturating_sub(depth) * MAX_WARMTH / (FULL_DEPTH / 2) * (128 + broad / 2) / 256;
+        scale_rgb565(mix_rgb565(self.texture.color, WARM_TINT, warmth), lightness)
+    }
+}
+
+/// The integer square root, rounded down
+fn isqrt(value: u32) -> u32 {
+    let mut root = 0u32;
+    let mut bit = 1u32 << 30;
+    let mut rest = value;
+    while bit > rest {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if rest >= root + bit {
+            rest -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+/// The angle of (`x`, `y`) counterclockwise from the x axis, in `ANGLE_STEPS` to the turn,
+/// to within a few tenths of a degree
+fn angle_steps(x: i32, y: i32) -> u32 {
+    let (ax, ay) = (x.unsigned_abs(), y.unsigned_abs());
+    if ax == 0 && ay == 0 {
+        return 0;
+    }
+    // atan(z) ≈ z + 0.273 z (1 - z) (4 / π) eighth turns, for z from 0 to 1, here in 256ths
+    let z = ax.min(ay) * 256 / ax.max(ay);
+    let octant = z + ((89 * z * (256 - z)) >> 16);
+    let eighth = ANGLE_STEPS / 8;
+    let from_x = octant * eighth / 256;
+    let quadrant = if ay > ax { 2 * eighth - from_x } else { from_x };
+    let angle = match (x < 0, y < 0) {
+        (false, false) => quadrant,
+        (true, false) => ANGLE_STEPS / 2 - quadrant,
+        (true, true) => ANGLE_STEPS / 2 + quadrant,
+        (false, true) => ANGLE_STEPS - quadrant,
+    };
+    angle % ANGLE_STEPS
+}
+
+/// Mix the bits of `value` thoroughly (Chris Wellons' lowbias32)
+fn hash(mut value: u32) -> u32 {
+    value ^= value >> 16;
+    value = value.wrapping_mul(0x7FEB_352D);
+    value ^= value >> 15;
+    value = value.wrapping_mul(0x846C_A68B);
+    value ^ value >> 16
+}
+
+/// Smooth noise from 0 to 255 along `pos`, with a random value every `cell_steps`,
+/// repeating every `period` (a multiple of `cell_steps`) so it joins up around the iris
+fn value_noise(seed: u32, pos: u32, cell_steps: u32, period: u32) -> u8 {
+    let cells = period / cell_steps;
+    let cell = pos / cell_steps % cells;
+    let frac = pos % cell_steps;
+    let at = |cell: u32| hash(seed.wrapping_add(cell.wrapping_mul(0x2545_F491))) >> 24;
+    let (lo, hi) = (at(cell), at((cell + 1) % cells));
+    ((lo * (cell_steps - frac) + hi * frac) / cell_steps) as u8
+}
+
+/// Mix `over` into `under`, by `amount` out of 256
+fn mix_rgb565(under: Rgb565, over: Rgb565, amount: u32) -> Rgb565 {
+    let mix = |a: u8, b: u8| ((a as u32 * (256 - amount) + b as u32 * amount + 128) / 256) as u8;
+    Rgb565::new(mix(under.r(), over.r()), mix(under.g(), over.g()), mix(under.b(), over.b()))
+}
+
+/// Scale each channel by `lightness` out of 256, saturating
+fn scale_rgb565(color: Rgb565, lightness: u32) -> Rgb565 {
+    let scale = |channel: u8, max: u8| ((channel as u32 * lightness + 128) / 256).min(max as u32) as u8;
+    Rgb565::new(scale(color.r(), Rgb565::MAX_R), scale(color.g(), Rgb565::MAX_G), scale(color.b(), Rgb565::MAX_B))
+}
@@ -1,14 +1,38 @@
-use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+#![no_std]
+
+//!
+//! Models of eye gaze, expression, and coloring, independent of any display hardware.
+//! This crate builds for the rp2350 target as well as the host, where `cargo test` runs.
+//!
+
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888, RgbColor};
 use num_enum::TryFromPrimitive;
 use heapless::String; // fixed-capacity, no allocator, stack-based
+
+pub mod morph;
+pub mod blink;
+pub mod gaze_control;
+pub mod gaze_vector;
+pub mod command;
+pub mod settings;
+pub mod emotion_blend;
+pub mod dirty_rect;
+pub mod thick_stroke;
+pub mod anti_alias;
+pub mod viewport;
+pub mod ambient_light;
+pub mod backlight;
+pub mod pupil;
+pub mod iris_texture;
+pub use morph::MorphFraction;
+pub use gaze_vector::{GazeBlend, GazeVector};
 // use heapless::consts::*;
-use defmt::Format;
 
 
 
 pub const ORIGINAL_ASSET_GRID_DIM: u8 = 3;
 pub const ORIGINAL_ASSET_GRID_AREA: u8 = ORIGINAL_ASSET_GRID_DIM*ORIGINAL_ASSET_GRID_DIM;
-pub const NUM_TWEEN_MORPH_STEPS: u8 = 2; // The number of tween morph steps we support
+pub const NUM_TWEEN_MORPH_STEPS: u8 = 2; // The number of tween steps authored in the SVG assets; others are morphed at runtime
 pub const NUM_LOOK_STEPS: u8 = NUM_TWEEN_MORPH_STEPS + 2; //includes start and end points, and transitions
 pub const NUM_SHORT_SWEEP_STEPS: u8 = NUM_LOOK_STEPS*2 - 1; // start, middle, end with transitions
 pub const SHORT_SWEEP_FLIP_IDX:u8 = (NUM_SHORT_SWEEP_STEPS/2) + 1;
@@ -35,26 +59,49 @@ pub const SPARSE_CTR_IDX: u8 = HALF_SPARSE_LEN;
 
 /// Trait for enums that can be converted into a single ASCII digit.
 pub trait AsDigit {
+    #[allow(clippy::wrong_self_convention)] // implementors are small Copy enums
     fn as_digit(self) -> u8;
 }
 
 // Look direction is a 3x3 grid, with row-col, 00 is northwest, 22 is southeast, 11 is straight ahead
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, Format)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum EmotionExpression {
     Neutral, // no strong expression
-    // Happy = 9,
     Surprise,
-    // Curious 
-    // Skeptical
-    // Thoughtful
-    // Confused
-    // Shy
-    // Love
-    // Trepidation 
+    Happy,
+    Curious,
+    Skeptical,
+    Thoughtful,
+    Confused,
+    Shy,
+    Love,
+    Trepidation,
     MaxCount
 }
 
+impl EmotionExpression {
+    /// Names for each expression, in index order, eg for text commands
+    pub const NAMES: [&'static str; EmotionExpression::MaxCount as usize] = [
+        "Neutral", "Surprise", "Happy", "Curious", "Skeptical", "Thoughtful", "Confused", "Shy", "Love", "Trepidation",
+    ];
+
+    /// Keys for each expression, in index order, as used in SVG asset IDs.
+    /// Neutral assets have no key.
+    pub const ASSET_KEYS: [&'static str; EmotionExpression::MaxCount as usize] = [
+        "", "surprise", "happy", "curious", "skeptical", "thoughtful", "confused", "shy", "love", "trepidation",
+    ];
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES.get(self as usize).copied().unwrap_or("MaxCount")
+    }
+
+    pub fn asset_key(self) -> &'static str {
+        Self::ASSET_KEYS.get(self as usize).copied().unwrap_or("")
+    }
+}
+
 impl AsDigit for EmotionExpression {
     #[inline]
     fn as_digit(self) -> u8 {
@@ -64,7 +111,9 @@ impl AsDigit for EmotionExpression {
 
 /// A 3x3 grid describing the direction the eyes are looking, 
 /// from the observer's perspective.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, Format)]
+/// For any direction in between, see `GazeVector`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum GazeDirection {
     NorthWest = 0,
@@ -166,6 +215,26 @@ impl GazeDirection {
         Self::gaze_and_step_for_arm_order(mono_count, &GazeDirection::CARDINAL_VSWEEP_ORDER)
     }
 
+    /// Given a monotonically increasing counter and a gaze target order,
+    /// return a transition that moves directly from each target to the next,
+    /// without first returning to StraightAhead.
+    /// Each leg takes `steps_per_leg` frames: the arrival at one target is step 0 of the next leg.
+    pub fn gaze_transition_for_target_order(counter: usize, target_order: &[GazeDirection], steps_per_leg: usize) -> GazeTransition {
+        let steps_per_leg = steps_per_leg.max(1);
+        let (leg, offset) = (counter / steps_per_leg, counter % steps_per_leg);
+        GazeTransition {
+            start: target_order[leg % target_order.len()],
+            end: target_order[(leg + 1) % target_order.len()],
+            progress: MorphFraction::from_ratio(offset as u32, steps_per_leg as u32),
+        }
+    }
+
+    /// Given a raw monotonically increasing counter, visit each row in a meandering order,
+    /// moving directly between neighboring targets.
+    pub fn gaze_transition_for_meander(mono_count: usize) -> GazeTransition {
+        Self::gaze_transition_for_target_order(mono_count, &GazeDirection::CARDINAL_H8_ORDER, LAST_LOOK_STEP_IDX as usize)
+    }
+
 }
 
 impl GazeDirection {
@@ -186,6 +255,31 @@ impl GazeDirection {
         }
     }
 
+    /// The gaze direction at a (row, column) 3x3 grid index, if it's on the grid
+    pub fn from_row_col(row: i8, col: i8) -> Option<GazeDirection> {
+        let dim = ORIGINAL_ASSET_GRID_DIM as i8;
+        if !(0..dim).contains(&row) || !(0..dim).contains(&col) {
+            return None;
+        }
+        GazeDirection::try_from((row * dim + col) as u8).ok()
+    }
+
+    /// Provide the name of a gaze direction, eg for text commands
+    pub fn name(&self) -> &'static str {
+        match self {
+            GazeDirection::NorthWest => "NorthWest",
+            GazeDirection::North => "North",
+            GazeDirection::NorthEast => "NorthEast",
+            GazeDirection::West => "West",
+            GazeDirection::StraightAhead => "StraightAhead",
+            GazeDirection::East => "East",
+            GazeDirection::SouthWest => "SouthWest",
+            GazeDirection::South => "South",
+            GazeDirection::SouthEast => "SouthEast",
+            GazeDirection::MaxCount => "MaxCount",
+        }
+    }
+
     /// Provide the string code for a gaze direction as a 3x3 grid index 
     pub fn to_digits(&self) -> &str {
         match self {
@@ -205,13 +299,57 @@ impl GazeDirection {
 
 
 
+/// A move between two gaze directions.
+/// At progress 0 the eye is at `start`, at progress 1 it has arrived at `end`.
+/// Progress is continuous, so a move can take any number of frames;
+/// the authored look steps (0..=LAST_LOOK_STEP_IDX) are evenly spaced along it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GazeTransition {
+    pub start: GazeDirection,
+    pub end: GazeDirection,
+    pub progress: MorphFraction,
+}
+
+impl GazeTransition {
+    /// A transition outward from StraightAhead, the only kind the original assets describe
+    pub const fn from_center(end: GazeDirection, look_step: u8) -> Self {
+        Self::at_look_step(GazeDirection::StraightAhead, end, look_step)
+    }
+
+    /// A transition at one of the authored look steps
+    pub const fn at_look_step(start: GazeDirection, end: GazeDirection, look_step: u8) -> Self {
+        Self { start, end, progress: MorphFraction::from_ratio(look_step as u32, LAST_LOOK_STEP_IDX as u32) }
+    }
+
+    /// The same move traversed in the opposite direction, landing on the same pose
+    pub const fn reversed(&self) -> Self {
+        Self { start: self.end, end: self.start, progress: self.progress.inverse() }
+    }
+
+    /// The authored look step this transition is at, if it's exactly on one
+    pub const fn look_step(&self) -> Option<u8> {
+        self.progress.exact_step(LAST_LOOK_STEP_IDX)
+    }
+
+    /// The direction the eye is actually looking toward, if it's at a keyframe
+    pub fn keyframe(&self) -> Option<GazeDirection> {
+        if self.start == self.end || self.progress == MorphFraction::START { Some(self.start) }
+        else if self.progress == MorphFraction::END { Some(self.end) }
+        else { None }
+    }
+}
+
+/// Longest SVG asset ID we generate, eg "upper_lid_shadow_trepidation_11_1_01"
+pub const MAX_ASSET_NAME_LEN: usize = 48;
+
 /// Helper function for generating unique IDs for step-by-step morphed SVG assets.
 /// Two formats are supported:
 /// - eg "iris_10_0_to_11" or  "iris_10_2_to_11" includes a tween step index
 /// - eg "iris_10" is the first asset, "iris_11" is the last, in a 10 -> 11 transition
-pub fn stepped_asset_name_full(prefix: &str, start_direction: GazeDirection, end_direction: GazeDirection, look_step_idx: u8) -> String<32>
+pub fn stepped_asset_name_full(prefix: &str, start_direction: GazeDirection, end_direction: GazeDirection, look_step_idx: u8) -> String<MAX_ASSET_NAME_LEN>
 {
-    let mut s: String<32> = String::new();
+    let mut s: String<MAX_ASSET_NAME_LEN> = String::new();
     s.push_str(prefix).unwrap();
     s.push('_').unwrap();
     if end_direction != start_direction {
@@ -242,11 +380,25 @@ pub fn stepped_asset_name_full(prefix: &str, start_direction: GazeDirection, end
 /// Helper function for generating unique IDs for step-by-step morphed SVG assets.
 /// See stepped_asset_name_full for a description of the formats returned.
 /// This version assumes a start_direction of  GazeDirection::StraightAhead
-pub fn stepped_asset_name(prefix: &str, end_direction: GazeDirection, look_step: u8) -> String<32>
+pub fn stepped_asset_name(prefix: &str, end_direction: GazeDirection, look_step: u8) -> String<MAX_ASSET_NAME_LEN>
 {
     stepped_asset_name_full(prefix, GazeDirection::StraightAhead, end_direction, look_step)
 }
 
+/// Helper function for the ID prefix of an emotion's variant of an SVG asset.
+/// - eg "eyebrow" for Neutral
+/// - eg "eyebrow_happy" for Happy, or "upper_lid_bulge_happy" which is then stepped like any other prefix
+pub fn emotion_asset_prefix(prefix: &str, emotion: EmotionExpression) -> String<MAX_ASSET_NAME_LEN>
+{
+    let mut s: String<MAX_ASSET_NAME_LEN> = String::new();
+    s.push_str(prefix).unwrap();
+    if emotion != EmotionExpression::Neutral {
+        s.push('_').unwrap();
+        s.push_str(emotion.asset_key()).unwrap();
+    }
+    s
+}
+
 
 
 
@@ -282,4 +434,14 @@ pub fn adjust_lightness_rgb565(color: Rgb565, factor: isize) -> Rgb565 {
     
     // Create new Rgb565 color
     Rgb565::new(r_final, g_final, b_final)
-}
\ No newline at end of file
+}
+
+/// Mix two colors, at `t` of the way from `start` to `end`, eg to cross-fade backgrounds.
+/// Mixing happens at 8 bits per channel, before any reduction to Rgb565.
+pub fn mix_rgb888(start: Rgb888, end: Rgb888, t: MorphFraction) -> Rgb888 {
+    let mix = |a: u8, b: u8| {
+        let a = a as i32;
+        (a + morph::div_round((b as i32 - a) * t.raw() as i32, MorphFraction::SCALE as i32)) as u8
+    };
+    Rgb888::new(mix(start.r(), end.r()), mix(start.g(), end.g()), mix(start.b(), end.b()))
+}
@@ -0,0 +1,135 @@
+//!
+//! Synthesizing in-between polygons from keyframe polygons.
+//! Keyframes must share a vertex count (the same sequence of path segments)
+//! for their vertices to correspond one-to-one.
+//!
+
+use embedded_graphics::prelude::Point;
+use heapless::Vec;
+
+/// The largest keyframe polygon we can synthesize tweens for
+pub const MAX_MORPH_VERTICES: usize = 128;
+
+/// Fixed-capacity vertex storage for a synthesized polygon
+pub type MorphVertices = Vec<Point, MAX_MORPH_VERTICES>;
+
+/// Divide, rounding half away from zero, so that interpolation is symmetric about zero
+pub(crate) fn div_round(num: i32, den: i32) -> i32 {
+    if (num < 0) == (den < 0) { (num + den / 2) / den } else { (num - den / 2) / den }
+}
+
+/// Linear interpolation of a single point, at the fraction `num / den` of the way from `start` to `end`
+pub fn lerp_point(start: Point, end: Point, num: i32, den: i32) -> Point {
+    Point::new(
+        start.x + div_round((end.x - start.x) * num, den),
+        start.y + div_round((end.y - start.y) * num, den),
+    )
+}
+
+/// Interpolate each vertex of two keyframe polygons, at the fraction `num / den` (0..=den).
+/// Returns None if the keyframes don't share a vertex count, or are too large to morph.
+pub fn interpolate_vertices(start: &[Point], end: &[Point], num: i32, den: i32) -> Option<MorphVertices> {
+    if start.len() != end.len() || start.len() > MAX_MORPH_VERTICES || den <= 0 {
+        return None;
+    }
+    let num = num.clamp(0, den);
+    let mut vertices = MorphVertices::new();
+    for (a, b) in start.iter().zip(end.iter()) {
+        // capacity was checked above
+        let _ = vertices.push(lerp_point(*a, *b, num, den));
+    }
+    Some(vertices)
+}
+
+/// Each vertex moved by `offset`, or None if there are too many to hold
+pub fn translate_vertices(vertices: &[Point], offset: Point) -> Option<MorphVertices> {
+    if vertices.len() > MAX_MORPH_VERTICES {
+        return None;
+    }
+    Some(vertices.iter().map(|&point| point + offset).collect())
+}
+
+/// A continuous position along a morph, from 0 (the start shape) to 1 (the end shape).
+/// Stored as fixed point, so it's cheap to share between cores in an `AtomicU16`
+/// and interpolation stays in integer math.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MorphFraction(u16);
+
+impl MorphFraction {
+    /// The raw value corresponding to 1.0
+    pub const SCALE: u16 = 1 << 15;
+    pub const START: Self = Self(0);
+    pub const HALF: Self = Self(Self::SCALE / 2);
+    pub const END: Self = Self(Self::SCALE);
+
+    /// Wrap a raw fixed-point value, clamping to 0..=SCALE
+    pub const fn from_raw(raw: u16) -> Self {
+        Self(if raw > Self::SCALE { Self::SCALE } else { raw })
+    }
+
+    pub const fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// The fraction `num / den`, eg step 2 of a 5 step animation. Clamped to 0..=1.
+    pub const fn from_ratio(num: u32, den: u32) -> Self {
+        if den == 0 || num >= den {
+            return Self::END;
+        }
+        Self(((num as u64 * Self::SCALE as u64 + den as u64 / 2) / den as u64) as u16)
+    }
+
+    /// Convert from a float in 0..=1, clamping out-of-range values (and NaN to 0)
+    pub fn from_f32(t: f32) -> Self {
+        let t = if t > 0.0 { t.min(1.0) } else { 0.0 };
+        Self((t * Self::SCALE as f32 + 0.5) as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::SCALE as f32
+    }
+
+    /// The same position measured from the other end of the morph
+    pub const fn inverse(self) -> Self {
+        Self(Self::SCALE - self.0)
+    }
+
+    /// If this fraction is step `idx` of `0..=last_step` (as built by `from_ratio`), return that index
+    pub const fn exact_step(self, last_step: u8) -> Option<u8> {
+        let scaled = self.0 as u32 * last_step as u32;
+        let nearest = ((scaled + Self::SCALE as u32 / 2) / Self::SCALE as u32) as u8;
+        if Self::from_ratio(nearest as u32, last_step as u32).0 == self.0 { Some(nearest) } else { None }
+    }
+
+    /// Move onto the nearest of steps `0..=last_step` if within `tolerance` (in raw units) of it
+    pub const fn snap_to_step(self, last_step: u8, tolerance: u16) -> Self {
+        if last_step == 0 {
+            return self;
+        }
+        let scaled = self.0 as u32 * last_step as u32;
+        let nearest = Self::from_ratio((scaled + Self::SCALE as u32 / 2) / Self::SCALE as u32, last_step as u32);
+        if self.0.abs_diff(nearest.0) <= tolerance { nearest } else { self }
+    }
+
+    /// Locate this fraction between evenly spaced steps `0..=last_step`:
+    /// returns the steps just below and above, and the fraction of the way between them.
+    pub const fn between_steps(self, last_step: u8) -> (u8, u8, MorphFraction) {
+        if last_step == 0 {
+            return (0, 0, Self::START);
+        }
+        let scaled = self.0 as u32 * last_step as u32;
+        let mut lower = (scaled / Self::SCALE as u32) as u8;
+        if lower >= last_step {
+            lower = last_step - 1;
+        }
+        let local = scaled - lower as u32 * Self::SCALE as u32;
+        (lower, lower + 1, Self(local as u16))
+    }
+}
+
+/// Interpolate two keyframe polygons at any point along the morph between them.
+/// Returns None if the keyframes don't share a vertex count, or are too large to morph.
+pub fn morph_vertices(start: &[Point], end: &[Point], t: MorphFraction) -> Option<MorphVertices> {
+    interpolate_vertices(start, end, t.raw() as i32, MorphFraction::SCALE as i32)
+}
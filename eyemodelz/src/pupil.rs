@@ -0,0 +1,147 @@
+//!
+//! Pupil dilation and constriction.
+//!
+//! Each gaze's pupil asset is authored at one size; a `PupilSize` scales it about the center of the iris.
+//! The size the eye wants follows the light, as a real pupil does: the brighter the backlight the smaller
+//! the pupil. Surprise and Love dilate it further. A `PupilAnimator` moves the shown size toward that
+//! target the way a pupil responds, constricting within a fraction of a second and dilating more slowly.
+//!
+//! The glints sit on the pupil's rim, so they move out or in with it (see `PupilSize::glint_shift`),
+//! keeping their own size, since they're reflections on the cornea rather than part of the pupil.
+//! They stay within the iris, which is narrow where the gaze turns it away.
+//!
+
+use embedded_graphics::prelude::Point;
+use embedded_graphics::primitives::Rectangle;
+
+use crate::morph::{MorphVertices, MAX_MORPH_VERTICES};
+use crate::EmotionExpression;
+
+/// The size of the pupil, as a percentage of its authored size
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PupilSize(u8);
+
+impl PupilSize {
+    pub const AUTHORED: Self = Self(100);
+    /// Constricted to a pinpoint, well inside the iris
+    pub const MIN: Self = Self(60);
+    /// Dilated until just a ring of iris shows
+    pub const MAX: Self = Self(150);
+
+    /// The size for a percentage of the authored size, kept between `MIN` and `MAX`
+    pub const fn from_pct(pct: u8) -> Self {
+        if pct < Self::MIN.0 {
+            Self::MIN
+        } else if pct > Self::MAX.0 {
+            Self::MAX
+        } else {
+            Self(pct)
+        }
+    }
+
+    pub const fn pct(self) -> u8 {
+        self.0
+    }
+
+    /// The size the pupil settles at for the backlight's brightness and the emotion shown:
+    /// from 125% in the dark to 80% at full brightness, with Surprise and Love opening it wider
+    pub fn target(brightness_pct: u8, emotion: EmotionExpression) -> Self {
+        let for_light = 125 - 45 * brightness_pct.min(100) as i32 / 100;
+        let for_emotion = match emotion {
+            EmotionExpression::Surprise => 25,
+            EmotionExpression::Love => 20,
+            _ => 0,
+        };
+        Self::from_pct((for_light + for_emotion).clamp(0, u8::MAX as i32) as u8)
+    }
+
+    /// `value` scaled by this size, rounded to the nearest
+    fn scale(self, value: i32) -> i32 {
+        let scaled = value * self.0 as i32;
+        (scaled + 50 * scaled.signum()) / 100
+    }
+
+    /// The pupil's vertices scaled about the iris center, or None if there are too many to hold
+    pub fn scale_about(self, vertices: &[Point], center: Point) -> Option<MorphVertices> {
+        if vertices.len() > MAX_MORPH_VERTICES {
+            return None;
+        }
+        Some(vertices.iter()
+            .map(|&point| center + Point::new(self.scale(point.x - center.x), self.scale(point.y - center.y)))
+            .collect())
+    }
+
+    /// How far a feature authored at `at` moves to stay at the same place on the pupil's rim,
+    /// as the pupil is scaled about `center`
+    pub fn rim_shift(self, at: Point, center: Point) -> Point {
+        let offset = at - center;
+        Point::new(self.scale(offset.x), self.scale(offset.y)) - offset
+    }
+
+    /// How far a glint bounded by `glint` moves with the rim of the pupil scaled about the center of `iris`,
+    /// held back where it would leave the iris
+    pub fn glint_shift(self, glint: Rectangle, iris: Rectangle) -> Point {
+        let shift = self.rim_shift(glint.center(), iris.center());
+        let room_before = iris.top_left - glint.top_left;
+        let room_after = room_before + iris.size - glint.size;
+        // a glint already outside the iris is never pushed further out
+        let keep_within = |shift: i32, before: i32, after: i32| shift.clamp(before.min(0), after.max(0));
+        Point::new(keep_within(shift.x, room_before.x, room_after.x), keep_within(shift.y, room_before.y, room_after.y))
+    }
+}
+
+impl Default for PupilSize {
+    fn default() -> Self {
+        Self::AUTHORED
+    }
+}
+
+/// How quickly the pupil follows its target: each is roughly the time to get two thirds of the way there
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PupilTiming {
+    pub constrict_ms: u32,
+    pub dilate_ms: u32,
+}
+
+impl PupilTiming {
+    /// The light reflex constricts the pupil several times faster than it dilates
+    pub const HUMAN: Self = Self { constrict_ms: 300, dilate_ms: 1200 };
+}
+
+/// Moves the shown pupil size smoothly toward a target
+#[derive(Clone, Debug)]
+pub struct PupilAnimator {
+    timing: PupilTiming,
+    /// The shown size, in 256ths of a percent, and when it was last updated
+    size_x256: u32,
+    last_ms: u64,
+}
+
+impl PupilAnimator {
+    pub fn new(timing: PupilTiming, size: PupilSize, now_ms: u64) -> Self {
+        Self { timing, size_x256: (size.0 as u32) << 8, last_ms: now_ms }
+    }
+
+    /// The size shown, as of the last update
+    pub fn size(&self) -> PupilSize {
+        PupilSize::from_pct(((self.size_x256 + 128) >> 8) as u8)
+    }
+
+    /// Move toward `target` for the time since the last update, returning the size to show
+    pub fn update(&mut self, target: PupilSize, now_ms: u64) -> PupilSize {
+        let elapsed = now_ms.saturating_sub(self.last_ms).min(u32::MAX as u64) as i64;
+        self.last_ms = now_ms;
+        let target_x256 = (target.0 as i64) << 8;
+        let delta = target_x256 - self.size_x256 as i64;
+        let time_constant = if delta < 0 { self.timing.constrict_ms } else { self.timing.dilate_ms } as i64;
+        // an exponential approach, weighted by the time since the last update
+        let mut step = delta * elapsed / (time_constant + elapsed).max(1);
+        // finish off the last fraction of a percent rather than creeping up on it
+        if step == 0 && elapsed > 0 && delta.abs() < 256 {
+            step = delta;
+        }
+        self.size_x256 = (self.size_x256 as i64 + step) as u32;
+        self.size()
+    }
+}
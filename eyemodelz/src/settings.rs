@@ -0,0 +1,346 @@
+//!
+//! User settings that survive a reset, kept as records in a reserved region of NOR flash.
+//!
+//! Every save appends a fixed-size record to the next free slot in the region, so writes
+//! are spread over the whole region and each sector is only erased once all of its slots
+//! have been used. The region is at least two sectors, so the latest record is never in
+//! the sector being erased. On load, the valid record with the highest sequence number wins.
+//!
+//! Each record is little-endian:
+//! - magic `u16`, format version `u8`, payload length `u8`
+//! - sequence number `u32`
+//! - payload (see `Settings::encode`)
+//! - CRC-32 `u32` over everything before it
+//!
+//! A record with the wrong magic, an unknown version or a bad CRC (eg a write torn by a reset)
+//! is skipped, so a load falls back to the previous good record, or to no settings at all.
+//!
+
+use embedded_graphics::pixelcolor::{raw::RawU16, Rgb565};
+use embedded_graphics::prelude::IntoStorage;
+use embedded_storage::nor_flash::NorFlash;
+
+use crate::blink::BlinkTiming;
+use crate::gaze_control::GazeTiming;
+
+/// Identifies a settings record
+pub const RECORD_MAGIC: u16 = 0x5945; // "EY"
+/// The current record format; bump this whenever the payload layout changes
+pub const SETTINGS_VERSION: u8 = 1;
+/// Every record takes one slot of this size
+pub const RECORD_SIZE: usize = 32;
+
+const HEADER_LEN: usize = 8;
+const CRC_LEN: usize = 4;
+const PAYLOAD_CAPACITY: usize = RECORD_SIZE - HEADER_LEN - CRC_LEN;
+const PAYLOAD_LEN: usize = 20;
+const _: () = assert!(PAYLOAD_LEN <= PAYLOAD_CAPACITY);
+const ERASED_BYTE: u8 = 0xFF;
+// Optional values are stored as an out-of-range value
+const NO_BRIGHTNESS: u8 = u8::MAX;
+
+/// How the backlight ramps up and down in modes that cycle it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BrightnessCurve {
+    pub min_pct: u8,
+    pub max_pct: u8,
+    /// Time for one ramp from min to max (or back)
+    pub ramp_ms: u16,
+}
+
+impl BrightnessCurve {
+    pub const DEFAULT: Self = Self { min_pct: 5, max_pct: 100, ramp_ms: 5000 };
+
+    /// Whether the range is in order and within 100%, as clamping to it needs
+    pub fn is_valid(&self) -> bool {
+        self.min_pct <= self.max_pct && self.max_pct <= 100
+    }
+
+    /// How far to step the brightness for one frame, never less than 1%
+    pub fn step_pct(&self, frame_ms: u32) -> u8 {
+        let range = self.max_pct.saturating_sub(self.min_pct) as u32;
+        let step = range * frame_ms / (self.ramp_ms as u32).max(1);
+        step.clamp(1, 100) as u8
+    }
+}
+
+/// Timing of the autonomous eye behaviors
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BehaviorParams {
+    /// Spontaneous blinks happen at a random interval in this range
+    pub min_blink_interval_ms: u16,
+    pub max_blink_interval_ms: u16,
+    /// Each gaze fixation dwells for a random time in this range
+    pub min_fixation_ms: u16,
+    pub max_fixation_ms: u16,
+}
+
+impl BehaviorParams {
+    pub const DEFAULT: Self = Self {
+        min_blink_interval_ms: BlinkTiming::HUMAN.min_interval_ms as u16,
+        max_blink_interval_ms: BlinkTiming::HUMAN.max_interval_ms as u16,
+        min_fixation_ms: GazeTiming::HUMAN.min_fixation_ms as u16,
+        max_fixation_ms: GazeTiming::HUMAN.max_fixation_ms as u16,
+    };
+
+    /// Whether each range is in order
+    pub fn is_valid(&self) -> bool {
+        self.min_blink_interval_ms <= self.max_blink_interval_ms && self.min_fixation_ms <= self.max_fixation_ms
+    }
+
+    /// Apply these intervals to a base blink timing
+    pub fn blink_timing(&self, base: BlinkTiming) -> BlinkTiming {
+        BlinkTiming {
+            min_interval_ms: self.min_blink_interval_ms as u32,
+            max_interval_ms: self.max_blink_interval_ms.max(self.min_blink_interval_ms) as u32,
+            ..base
+        }
+    }
+
+    /// Apply these intervals to a base gaze timing
+    pub fn gaze_timing(&self, base: GazeTiming) -> GazeTiming {
+        GazeTiming {
+            min_fixation_ms: self.min_fixation_ms as u32,
+            max_fixation_ms: self.max_fixation_ms.max(self.min_fixation_ms) as u32,
+            ..base
+        }
+    }
+}
+
+/// Everything the user can choose that should survive a reset
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Settings {
+    /// Index of the selected mode
+    pub mode_a: u8,
+    /// Colors chosen by the user, or None to follow the mode
+    pub iris_color: Option<Rgb565>,
+    pub skin_color: Option<Rgb565>,
+    /// Fixed backlight brightness, or None to follow the mode
+    pub brightness_pct: Option<u8>,
+    pub brightness_curve: BrightnessCurve,
+    pub behavior: BehaviorParams,
+}
+
+/// Why a record couldn't be decoded
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RecordError {
+    /// Nothing has been written to this slot since it was erased
+    Blank,
+    BadMagic,
+    UnsupportedVersion(u8),
+    BadLength,
+    BadCrc,
+}
+
+fn encode_color(color: Option<Rgb565>) -> [u8; 3] {
+    let raw = color.map_or(0, |color| color.into_storage());
+    let [lo, hi] = raw.to_le_bytes();
+    [color.is_some() as u8, lo, hi]
+}
+
+fn decode_color(bytes: &[u8]) -> Option<Rgb565> {
+    (bytes[0] != 0).then(|| Rgb565::from(RawU16::new(u16::from_le_bytes([bytes[1], bytes[2]]))))
+}
+
+impl Settings {
+    /// The settings before the user has changed anything, starting in the given mode
+    pub const fn defaults(mode_a: u8) -> Self {
+        Self {
+            mode_a,
+            iris_color: None,
+            skin_color: None,
+            brightness_pct: None,
+            brightness_curve: BrightnessCurve::DEFAULT,
+            behavior: BehaviorParams::DEFAULT,
+        }
+    }
+
+    /// Whether these settings can be used as they are, with `num_modes` modes to choose from.
+    /// A record can pass its CRC check and still fail this, eg if it was written by a buggy build.
+    pub fn is_valid(&self, num_modes: u8) -> bool {
+        self.mode_a < num_modes
+            && self.brightness_pct.is_none_or(|pct| pct <= 100)
+            && self.brightness_curve.is_valid()
+            && self.behavior.is_valid()
+    }
+
+    fn encode(&self) -> [u8; PAYLOAD_LEN] {
+        let mut payload = [0u8; PAYLOAD_LEN];
+        payload[0] = self.mode_a;
+        payload[1..4].copy_from_slice(&encode_color(self.iris_color));
+        payload[4..7].copy_from_slice(&encode_color(self.skin_color));
+        payload[7] = self.brightness_pct.unwrap_or(NO_BRIGHTNESS);
+        payload[8] = self.brightness_curve.min_pct;
+        payload[9] = self.brightness_curve.max_pct;
+        payload[10..12].copy_from_slice(&self.brightness_curve.ramp_ms.to_le_bytes());
+        let behavior = &self.behavior;
+        payload[12..14].copy_from_slice(&behavior.min_blink_interval_ms.to_le_bytes());
+        payload[14..16].copy_from_slice(&behavior.max_blink_interval_ms.to_le_bytes());
+        payload[16..18].copy_from_slice(&behavior.min_fixation_ms.to_le_bytes());
+        payload[18..20].copy_from_slice(&behavior.max_fixation_ms.to_le_bytes());
+        payload
+    }
+
+    fn decode(payload: &[u8]) -> Self {
+        let read_u16 = |idx: usize| u16::from_le_bytes([payload[idx], payload[idx + 1]]);
+        Self {
+            mode_a: payload[0],
+            iris_color: decode_color(&payload[1..4]),
+            skin_color: decode_color(&payload[4..7]),
+            brightness_pct: (payload[7] != NO_BRIGHTNESS).then_some(payload[7]),
+            brightness_curve: BrightnessCurve {
+                min_pct: payload[8],
+                max_pct: payload[9],
+                ramp_ms: read_u16(10),
+            },
+            behavior: BehaviorParams {
+                min_blink_interval_ms: read_u16(12),
+                max_blink_interval_ms: read_u16(14),
+                min_fixation_ms: read_u16(16),
+                max_fixation_ms: read_u16(18),
+            },
+        }
+    }
+}
+
+/// CRC-32 (IEEE), bitwise since records are tiny
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Build the record for these settings, with the given sequence number
+pub fn encode_record(settings: &Settings, sequence: u32) -> [u8; RECORD_SIZE] {
+    let mut record = [ERASED_BYTE; RECORD_SIZE];
+    record[0..2].copy_from_slice(&RECORD_MAGIC.to_le_bytes());
+    record[2] = SETTINGS_VERSION;
+    record[3] = PAYLOAD_LEN as u8;
+    record[4..8].copy_from_slice(&sequence.to_le_bytes());
+    record[HEADER_LEN..HEADER_LEN + PAYLOAD_LEN].copy_from_slice(&settings.encode());
+    let crc = crc32(&record[..RECORD_SIZE - CRC_LEN]);
+    record[RECORD_SIZE - CRC_LEN..].copy_from_slice(&crc.to_le_bytes());
+    record
+}
+
+/// Check and decode a record, returning its settings and sequence number
+pub fn decode_record(record: &[u8; RECORD_SIZE]) -> Result<(Settings, u32), RecordError> {
+    if record.iter().all(|byte| *byte == ERASED_BYTE) {
+        return Err(RecordError::Blank);
+    }
+    if u16::from_le_bytes([record[0], record[1]]) != RECORD_MAGIC {
+        return Err(RecordError::BadMagic);
+    }
+    let crc_bytes = &record[RECORD_SIZE - CRC_LEN..];
+    let crc = u32::from_le_bytes([crc_bytes[0], crc_bytes[1], crc_bytes[2], crc_bytes[3]]);
+    if crc != crc32(&record[..RECORD_SIZE - CRC_LEN]) {
+        return Err(RecordError::BadCrc);
+    }
+    if record[2] != SETTINGS_VERSION {
+        return Err(RecordError::UnsupportedVersion(record[2]));
+    }
+    if record[3] as usize != PAYLOAD_LEN {
+        return Err(RecordError::BadLength);
+    }
+    let sequence = u32::from_le_bytes([record[4], record[5], record[6], record[7]]);
+    Ok((Settings::decode(&record[HEADER_LEN..HEADER_LEN + PAYLOAD_LEN]), sequence))
+}
+
+/// Loads and saves settings records in a region of flash, spreading writes across the region
+pub struct SettingsStore<F> {
+    flash: F,
+    /// Start of the region, from the start of flash
+    offset: u32,
+    num_slots: u32,
+    /// Where the next record goes
+    next_slot: u32,
+    next_sequence: u32,
+}
+
+impl<F: NorFlash> SettingsStore<F> {
+    /// Use `len` bytes of flash starting at `offset` (from the start of flash),
+    /// both of which must be whole sectors, and at least two of them,
+    /// so that erasing one sector never takes the latest record with it.
+    pub fn new(flash: F, offset: u32, len: u32) -> Self {
+        let erase_size = F::ERASE_SIZE as u32;
+        assert!(offset.is_multiple_of(erase_size) && len.is_multiple_of(erase_size), "settings region must be whole sectors");
+        assert!(len >= 2 * erase_size, "settings region must be at least two sectors");
+        assert!(RECORD_SIZE.is_multiple_of(F::WRITE_SIZE) && RECORD_SIZE.is_multiple_of(F::READ_SIZE)
+            && F::ERASE_SIZE.is_multiple_of(RECORD_SIZE));
+        Self { flash, offset, num_slots: len / RECORD_SIZE as u32, next_slot: 0, next_sequence: 0 }
+    }
+
+    /// Give back the flash
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    fn slot_offset(&self, slot: u32) -> u32 {
+        self.offset + slot * RECORD_SIZE as u32
+    }
+
+    fn read_slot(&mut self, slot: u32) -> Result<[u8; RECORD_SIZE], F::Error> {
+        let mut record = [0u8; RECORD_SIZE];
+        self.flash.read(self.slot_offset(slot), &mut record)?;
+        Ok(record)
+    }
+
+    fn is_sector_start(&self, slot: u32) -> bool {
+        (slot * RECORD_SIZE as u32).is_multiple_of(F::ERASE_SIZE as u32)
+    }
+
+    /// Find the latest good record, if any, and remember where the next one goes
+    pub fn load(&mut self) -> Result<Option<Settings>, F::Error> {
+        let mut latest: Option<(Settings, u32, u32)> = None;
+        for slot in 0..self.num_slots {
+            let record = self.read_slot(slot)?;
+            if let Ok((settings, sequence)) = decode_record(&record) {
+                if latest.is_none_or(|(_, latest_sequence, _)| sequence > latest_sequence) {
+                    latest = Some((settings, sequence, slot));
+                }
+            }
+        }
+        match latest {
+            Some((settings, sequence, slot)) => {
+                self.next_slot = (slot + 1) % self.num_slots;
+                self.next_sequence = sequence.wrapping_add(1);
+                Ok(Some(settings))
+            }
+            None => {
+                self.next_slot = 0;
+                self.next_sequence = 0;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Append a record for these settings.
+    /// Erases the sector ahead only once the write has reached it, so the latest record,
+    /// in the sector behind, stays intact even if the write never happens.
+    pub fn save(&mut self, settings: &Settings) -> Result<(), F::Error> {
+        let mut slot = self.next_slot;
+        while self.read_slot(slot)?.iter().any(|byte| *byte != ERASED_BYTE) {
+            if self.is_sector_start(slot) {
+                // wrapped around to the oldest sector
+                let sector = self.slot_offset(slot);
+                self.flash.erase(sector, sector + F::ERASE_SIZE as u32)?;
+                break;
+            }
+            // leftovers from a torn write
+            slot = (slot + 1) % self.num_slots;
+        }
+        let record = encode_record(settings, self.next_sequence);
+        self.flash.write(self.slot_offset(slot), &record)?;
+        self.next_slot = (slot + 1) % self.num_slots;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        Ok(())
+    }
+}
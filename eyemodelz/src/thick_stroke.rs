@@ -0,0 +1,221 @@
+//!
+//! A scanline rasterizer for the thick outlines of closed polygons.
+//!
+//! embedded-graphics strokes a polygon one edge at a time, each as its own thick line,
+//! which gets slow beyond a pixel wide. Instead, each row of the outline is worked out at once:
+//! the pixels within reach of every edge (a capsule, so corners come out round) are merged into runs,
+//! which are clipped to the inside or outside of the polygon for `Inside` and `Outside` alignment,
+//! then filled a run at a time.
+//!
+//! Inside and Outside outlines, and even width Center outlines, follow the polygon's edge where it
+//! falls between pixels, as the fill does, so an Inside outline never leaves the fill and an Outside
+//! outline never touches it. Odd width Center outlines are centered on the pixels the vertices name.
+//! Coordinates are doubled internally to keep those half pixel offsets in integers.
+//!
+
+use embedded_graphics::prelude::{DrawTarget, Point, Size};
+use embedded_graphics::primitives::{Rectangle, StrokeAlignment};
+
+/// The most separate runs of outline expected in a row; any more are filled in batches
+const MAX_ROW_RUNS: usize = 64;
+
+/// Inclusive ranges of pixel x along a row
+type Runs = heapless::Vec<(i32, i32), MAX_ROW_RUNS>;
+
+/// The most edges worked on at once; polygons with more are outlined a batch of edges at a time
+const MAX_EDGES: usize = 32;
+
+fn floor_div(num: i32, den: i32) -> i32 {
+    if den < 0 { (-num).div_euclid(-den) } else { num.div_euclid(den) }
+}
+
+fn ceil_div(num: i32, den: i32) -> i32 {
+    -floor_div(-num, den)
+}
+
+/// The largest integer whose square is at most `n`
+fn isqrt(n: i64) -> i64 {
+    if n < 2 {
+        return n.max(0);
+    }
+    // start from a power of two above the root, so Newton's method converges in a few steps
+    let mut root = 1i64 << ((64 - n.leading_zeros()).div_ceil(2));
+    let mut next = (root + n / root) / 2;
+    while next < root {
+        root = next;
+        next = (root + n / root) / 2;
+    }
+    root
+}
+
+/// The range of integer `x` for which `k * x + m` is within `lo..=hi`, if any
+fn solve_linear(k: i32, m: i32, lo: i32, hi: i32) -> Option<(i32, i32)> {
+    let range = match k.signum() {
+        0 => return (lo..=hi).contains(&m).then_some((i32::MIN / 4, i32::MAX / 4)),
+        1 => (ceil_div(lo - m, k), floor_div(hi - m, k)),
+        _ => (ceil_div(hi - m, k), floor_div(lo - m, k)),
+    };
+    (range.0 <= range.1).then_some(range)
+}
+
+/// A polygon edge in doubled coordinates, with what every row it reaches needs worked out up front
+struct Edge {
+    a: (i32, i32),
+    b: (i32, i32),
+    len2: i32,
+    /// How far across the edge the outline reaches, scaled by the edge's length
+    max_across: i32,
+    /// The doubled rows the outline of this edge reaches
+    min_y2: i32,
+    max_y2: i32,
+}
+
+impl Edge {
+    fn new(a: (i32, i32), b: (i32, i32), reach: i32) -> Self {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len2 = dx * dx + dy * dy;
+        let max_across = isqrt(reach as i64 * reach as i64 * len2 as i64) as i32;
+        Self { a, b, len2, max_across, min_y2: a.1.min(b.1) - reach, max_y2: a.1.max(b.1) + reach }
+    }
+
+    /// On the doubled row `y2`, the doubled x range within `reach` of the edge: a capsule, round at each end
+    fn span(&self, y2: i32, reach: i32) -> Option<(i32, i32)> {
+        let mut span: Option<(i32, i32)> = None;
+        let mut add = |lo: i32, hi: i32| {
+            if lo <= hi {
+                span = Some(span.map_or((lo, hi), |(old_lo, old_hi)| (old_lo.min(lo), old_hi.max(hi))));
+            }
+        };
+        // the round ends
+        for (end_x, end_y) in [self.a, self.b] {
+            let room = reach * reach - (y2 - end_y) * (y2 - end_y);
+            if room >= 0 {
+                let half_width = isqrt(room as i64) as i32;
+                add(end_x - half_width, end_x + half_width);
+            }
+        }
+        // the band alongside the edge
+        if self.len2 > 0 {
+            let (a, dx, dy) = (self.a, self.b.0 - self.a.0, self.b.1 - self.a.1);
+            // how far along the edge: dx * (x - ax) + dy * (y - ay), from 0 to len2
+            let along = solve_linear(dx, dy * (y2 - a.1) - dx * a.0, 0, self.len2);
+            // how far across it, scaled by its length: dx * (y - ay) - dy * (x - ax)
+            let across = solve_linear(-dy, dx * (y2 - a.1) + dy * a.0, -self.max_across, self.max_across);
+            if let (Some(along), Some(across)) = (along, across) {
+                add(along.0.max(across.0), along.1.min(across.1));
+            }
+        }
+        span
+    }
+}
+
+/// The doubled vertices, shifted by `shift` half pixels up and left
+fn doubled(vertices: &[Point], shift: i32) -> impl Iterator<Item = ((i32, i32), (i32, i32))> + '_ {
+    let point = move |p: &Point| (2 * p.x + shift, 2 * p.y + shift);
+    vertices.iter().zip(vertices[1..].iter().chain(&vertices[..1])).map(move |(a, b)| (point(a), point(b)))
+}
+
+/// The first pixel of each run inside the polygon on doubled row `y2`, followed by the first pixel after it
+fn interior_bounds(vertices: &[Point], shift: i32, y2: i32) -> heapless::Vec<i32, MAX_ROW_RUNS> {
+    let mut bounds: heapless::Vec<i32, MAX_ROW_RUNS> = heapless::Vec::new();
+    for (a, b) in doubled(vertices, shift) {
+        if (a.1 <= y2 && y2 < b.1) || (b.1 <= y2 && y2 < a.1) {
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            // the first pixel x at or right of where the edge crosses the row
+            let first = ceil_div(a.0 * dy + (y2 - a.1) * dx, 2 * dy);
+            let _ = bounds.push(first);
+        }
+    }
+    bounds.sort_unstable();
+    bounds
+}
+
+fn fill_run<D: DrawTarget>(target: &mut D, y: i32, (x0, x1): (i32, i32), color: D::Color) -> Result<(), D::Error> {
+    if x0 > x1 {
+        return Ok(());
+    }
+    target.fill_solid(&Rectangle::new(Point::new(x0, y), Size::new((x1 - x0 + 1) as u32, 1)), color)
+}
+
+/// Merge overlapping and touching runs, then fill them, clipped to or around the interior as aligned
+fn fill_runs<D: DrawTarget>(target: &mut D, y: i32, runs: &mut Runs, interior: &[i32],
+    alignment: StrokeAlignment, color: D::Color) -> Result<(), D::Error>
+{
+    runs.sort_unstable();
+    let mut merged = 0;
+    for idx in 0..runs.len() {
+        if merged > 0 && runs[idx].0 <= runs[merged - 1].1 + 1 {
+            runs[merged - 1].1 = runs[merged - 1].1.max(runs[idx].1);
+        } else {
+            runs[merged] = runs[idx];
+            merged += 1;
+        }
+    }
+    runs.truncate(merged);
+
+    for &(x0, x1) in runs.iter() {
+        match alignment {
+            StrokeAlignment::Center => fill_run(target, y, (x0, x1), color)?,
+            StrokeAlignment::Inside => {
+                for inside in interior.chunks_exact(2) {
+                    fill_run(target, y, (x0.max(inside[0]), x1.min(inside[1] - 1)), color)?;
+                }
+            }
+            StrokeAlignment::Outside => {
+                let mut start = x0;
+                for inside in interior.chunks_exact(2) {
+                    fill_run(target, y, (start, x1.min(inside[0] - 1)), color)?;
+                    start = start.max(inside[1]);
+                }
+                fill_run(target, y, (start, x1), color)?;
+            }
+        }
+    }
+    runs.clear();
+    Ok(())
+}
+
+/// Draw the outline of the closed polygon through `vertices`, `stroke_width` pixels wide.
+/// Vertices should be within a few thousand pixels of the origin, so the arithmetic fits in an `i32`.
+pub fn draw_thick_outline<D: DrawTarget>(vertices: &[Point], stroke_width: u32, alignment: StrokeAlignment,
+    color: D::Color, target: &mut D) -> Result<(), D::Error>
+{
+    if vertices.len() < 2 || stroke_width == 0 {
+        return Ok(());
+    }
+    let width = stroke_width as i32;
+    // in doubled coordinates
+    let (reach, shift) = match alignment {
+        StrokeAlignment::Center => (width, if width % 2 == 0 { -1 } else { 0 }),
+        StrokeAlignment::Inside | StrokeAlignment::Outside => (2 * width, -1),
+    };
+
+    let mut runs = Runs::new();
+    let mut all_edges = doubled(vertices, shift).map(|(a, b)| Edge::new(a, b, reach)).peekable();
+    while all_edges.peek().is_some() {
+        let edges: heapless::Vec<Edge, MAX_EDGES> = all_edges.by_ref().take(MAX_EDGES).collect();
+        let min_y2 = edges.iter().map(|edge| edge.min_y2).min().unwrap_or(0);
+        let max_y2 = edges.iter().map(|edge| edge.max_y2).max().unwrap_or(-1);
+        for y in ceil_div(min_y2, 2)..=floor_div(max_y2, 2) {
+            let y2 = 2 * y;
+            let interior = match alignment {
+                StrokeAlignment::Center => heapless::Vec::new(),
+                _ => interior_bounds(vertices, shift, y2),
+            };
+            for edge in edges.iter().filter(|edge| (edge.min_y2..=edge.max_y2).contains(&y2)) {
+                if let Some((lo, hi)) = edge.span(y2, reach) {
+                    let run = (ceil_div(lo, 2), floor_div(hi, 2));
+                    if run.0 > run.1 {
+                        continue;
+                    }
+                    if runs.is_full() {
+                        fill_runs(target, y, &mut runs, &interior, alignment, color)?;
+                    }
+                    let _ = runs.push(run);
+                }
+            }
+            fill_runs(target, y, &mut runs, &interior, alignment, color)?;
+        }
+    }
+    Ok(())
+}
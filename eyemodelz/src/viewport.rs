@@ -0,0 +1,137 @@
+//!
+//! Fitting the eye art onto display panels of other sizes.
+//!
+//! The eye art (the SVG stacks and background images) is authored for a 320x240 frame.
+//! On a panel of another size, it's scaled uniformly to fit, and centered: onto a 240x240 round
+//! panel that's by 3/4, which puts the whole eye, corners and all, inside the circle.
+//! Polygons are scaled a vertex at a time. Background images can only be decoded in order,
+//! so they're scaled a row at a time, to the nearest pixel, with their top and bottom rows
+//! stretched over any panel rows the art doesn't reach.
+//!
+
+use embedded_graphics::prelude::{Point, Size};
+
+use crate::morph::{MorphVertices, MAX_MORPH_VERTICES};
+
+/// The widest art a background row can be kept for
+pub const MAX_ART_WIDTH: usize = 320;
+
+fn floor_div(num: i32, den: i32) -> i32 {
+    num.div_euclid(den)
+}
+
+/// Where the eye art lands on a panel
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Viewport {
+    art_size: Size,
+    panel_size: Size,
+    /// The scale from art to panel, as a ratio
+    scale_num: i32,
+    scale_den: i32,
+    /// Where the art's top left corner lands on the panel
+    offset: Point,
+}
+
+impl Viewport {
+    /// Fit art of `art_size` onto a panel of `panel_size`, as large as it goes, centered
+    pub const fn new(art_size: Size, panel_size: Size) -> Self {
+        let (art_w, art_h) = (art_size.width as i32, art_size.height as i32);
+        let (panel_w, panel_h) = (panel_size.width as i32, panel_size.height as i32);
+        // whichever of the width and height is the tighter fit sets the scale
+        let (scale_num, scale_den) = if panel_w * art_h <= panel_h * art_w { (panel_w, art_w) } else { (panel_h, art_h) };
+        let offset = Point::new(
+            (panel_w - art_w * scale_num / scale_den) / 2,
+            (panel_h - art_h * scale_num / scale_den) / 2,
+        );
+        Self { art_size, panel_size, scale_num, scale_den, offset }
+    }
+
+    /// Whether the art is drawn just as it's authored
+    pub const fn is_identity(&self) -> bool {
+        self.scale_num == self.scale_den && self.offset.x == 0 && self.offset.y == 0
+    }
+
+    pub const fn panel_size(&self) -> Size {
+        self.panel_size
+    }
+
+    /// Where a point of the art lands on the panel, to the nearest pixel
+    pub fn to_panel(&self, point: Point) -> Point {
+        let scale = |value: i32| floor_div(2 * value * self.scale_num + self.scale_den, 2 * self.scale_den);
+        self.offset + Point::new(scale(point.x), scale(point.y))
+    }
+
+    /// The art's vertices on the panel, or None if there are too many to hold
+    pub fn to_panel_vertices(&self, vertices: &[Point]) -> Option<MorphVertices> {
+        if vertices.len() > MAX_MORPH_VERTICES {
+            return None;
+        }
+        Some(vertices.iter().map(|&point| self.to_panel(point)).collect())
+    }
+
+    /// The art column or row nearest the middle of a panel column or row, kept within the art
+    fn art_coord(&self, panel_coord: i32, offset: i32, art_len: u32) -> u32 {
+        let art_coord = floor_div((2 * (panel_coord - offset) + 1) * self.scale_den, 2 * self.scale_num);
+        art_coord.clamp(0, art_len as i32 - 1) as u32
+    }
+
+    /// The art column that shows in panel column `panel_x`
+    pub fn art_column(&self, panel_x: i32) -> u32 {
+        self.art_coord(panel_x, self.offset.x, self.art_size.width)
+    }
+
+    /// The art row that shows in panel row `panel_y`
+    pub fn art_row(&self, panel_y: i32) -> u32 {
+        self.art_coord(panel_y, self.offset.y, self.art_size.height)
+    }
+}
+
+/// The pixels of the panel, in order, from the pixels of the art, in order
+pub struct ViewportPixels<I: Iterator> {
+    viewport: Viewport,
+    art_pixels: I,
+    /// The art row last read
+    row: heapless::Vec<I::Item, MAX_ART_WIDTH>,
+    row_idx: Option<u32>,
+    /// The next panel pixel
+    panel_idx: u32,
+}
+
+impl<I: Iterator> ViewportPixels<I> where I::Item: Copy {
+    /// `art_pixels` must be the art's full rows, no wider than `MAX_ART_WIDTH`
+    pub fn new(viewport: Viewport, art_pixels: I) -> Self {
+        Self { viewport, art_pixels, row: heapless::Vec::new(), row_idx: None, panel_idx: 0 }
+    }
+
+    /// Read art rows up to and including `art_row`, keeping the last
+    fn read_to_row(&mut self, art_row: u32) -> Option<()> {
+        while self.row_idx.is_none_or(|row_idx| row_idx < art_row) {
+            self.row.clear();
+            for _ in 0..self.viewport.art_size.width {
+                let _ = self.row.push(self.art_pixels.next()?);
+            }
+            self.row_idx = Some(self.row_idx.map_or(0, |row_idx| row_idx + 1));
+        }
+        Some(())
+    }
+}
+
+impl<I: Iterator> Iterator for ViewportPixels<I> where I::Item: Copy {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        if self.viewport.is_identity() {
+            return self.art_pixels.next();
+        }
+        let panel_width = self.viewport.panel_size.width;
+        if self.panel_idx >= panel_width * self.viewport.panel_size.height {
+            return None;
+        }
+        let (panel_x, panel_y) = ((self.panel_idx % panel_width) as i32, (self.panel_idx / panel_width) as i32);
+        if panel_x == 0 {
+            self.read_to_row(self.viewport.art_row(panel_y))?;
+        }
+        self.panel_idx += 1;
+        self.row.get(self.viewport.art_column(panel_x) as usize).copied()
+    }
+}
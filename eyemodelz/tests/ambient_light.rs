@@ -0,0 +1,73 @@
+use eyemodelz::ambient_light::*;
+
+const CURVE: AmbientCurve = AmbientCurve { points: &[(100, 10), (1100, 60), (2100, 100)] };
+const TIMING: AmbientTiming = AmbientTiming { time_constant_ms: 1000, deadband_pct: 3 };
+
+#[test]
+fn curve_is_linear_between_points_and_flat_beyond() {
+    assert_eq!(CURVE.brightness_pct(0), 10);
+    assert_eq!(CURVE.brightness_pct(100), 10);
+    assert_eq!(CURVE.brightness_pct(600), 35);
+    assert_eq!(CURVE.brightness_pct(1100), 60);
+    assert_eq!(CURVE.brightness_pct(1350), 70);
+    assert_eq!(CURVE.brightness_pct(2100), 100);
+    assert_eq!(CURVE.brightness_pct(u16::MAX), 100);
+}
+
+#[test]
+fn stock_curves_rise_with_the_light() {
+    for curve in [AmbientCurve::PHOTORESISTOR, AmbientCurve::LUX] {
+        let mut last_pct = 0;
+        for level in (0..=u16::MAX).step_by(97) {
+            let pct = curve.brightness_pct(level);
+            assert!(pct >= last_pct && pct <= 100, "{} at {}", pct, level);
+            last_pct = pct;
+        }
+        assert_eq!(last_pct, 100);
+    }
+}
+
+#[test]
+fn first_reading_is_taken_as_it_is() {
+    let mut ambient = AmbientBrightness::new(CURVE, TIMING);
+    assert_eq!(ambient.smoothed_level(), None);
+    assert_eq!(ambient.update(1100, 5000), 60);
+    assert_eq!(ambient.smoothed_level(), Some(1100));
+}
+
+#[test]
+fn step_in_the_light_is_followed_gradually() {
+    let mut ambient = AmbientBrightness::new(CURVE, TIMING);
+    ambient.update(100, 0);
+    // read every 100ms after the lights come on
+    let pcts: Vec<u8> = (1..=100).map(|tick| ambient.update(2100, tick * 100)).collect();
+    assert!(pcts.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", pcts);
+    // the light about two thirds of the way up after one time constant, which the curve puts near 70%,
+    // and all the way after several
+    assert!((65..75).contains(&pcts[9]), "{:?}", pcts);
+    assert_eq!(pcts[99], 100);
+}
+
+#[test]
+fn flicker_is_smoothed_out() {
+    let mut ambient = AmbientBrightness::new(CURVE, TIMING);
+    ambient.update(1100, 0);
+    // a lamp flickering 20% either way, read every 50ms
+    for tick in 1..=200u64 {
+        let level = if tick % 2 == 0 { 1300 } else { 900 };
+        assert_eq!(ambient.update(level, tick * 50), 60, "at tick {}", tick);
+    }
+}
+
+#[test]
+fn small_changes_are_held_until_past_the_deadband() {
+    let mut ambient = AmbientBrightness::new(CURVE, TIMING);
+    ambient.update(1100, 0);
+    // a steady light calling for 62%, two percent up, never moves it
+    assert_eq!(ambient.update(1140, 60_000), 60);
+    // one calling for 64% does
+    assert_eq!(ambient.update(1200, 120_000), 64);
+    // the dimmest setting is always reached, however close
+    ambient.update(180, 180_000);
+    assert_eq!(ambient.update(100, 240_000), 10);
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::prelude::Point;
+use eyemodelz::anti_alias::*;
+
+/// The coverage of every pixel filled, checking that none is given twice
+fn coverage_map(vertices: &[Point]) -> HashMap<(i32, i32), u8> {
+    let mut pixels = HashMap::new();
+    fill_anti_aliased(vertices, |start, len, coverage| {
+        assert!((1..=FULL_COVERAGE).contains(&coverage), "coverage {} at {:?}", coverage, start);
+        for x in start.x..start.x + len as i32 {
+            assert!(pixels.insert((x, start.y), coverage).is_none(), "{},{} given twice", x, start.y);
+        }
+    });
+    pixels
+}
+
+#[test]
+fn nothing_to_fill() {
+    assert!(coverage_map(&[]).is_empty());
+    assert!(coverage_map(&[Point::new(0, 0), Point::new(10, 10)]).is_empty());
+}
+
+#[test]
+fn square_edges_need_no_blending() {
+    let square = [Point::new(0, 0), Point::new(20, 0), Point::new(20, 20), Point::new(0, 20)];
+    let pixels = coverage_map(&square);
+    assert_eq!(pixels.len(), 400);
+    assert!(pixels.iter().all(|(&(x, y), &coverage)| {
+        (0..20).contains(&x) && (0..20).contains(&y) && coverage == FULL_COVERAGE
+    }));
+}
+
+#[test]
+fn diagonal_edge_pixels_are_half_covered() {
+    // the hypotenuse runs corner to corner through each pixel with x + y == 19
+    let triangle = [Point::new(0, 0), Point::new(20, 0), Point::new(0, 20)];
+    let pixels = coverage_map(&triangle);
+    for (&(x, y), &coverage) in &pixels {
+        assert!(x >= 0 && y >= 0 && x + y <= 19, "{},{} outside", x, y);
+        let expected = if x + y < 19 { FULL_COVERAGE } else { FULL_COVERAGE / 2 };
+        assert_eq!(coverage, expected, "at {},{}", x, y);
+    }
+    assert_eq!(pixels.len(), 210);
+}
+
+#[test]
+fn coverage_adds_up_to_the_area() {
+    let shapes: [&[Point]; 3] = [
+        &[Point::new(0, 0), Point::new(37, 5), Point::new(12, 29)],
+        &[Point::new(3, 1), Point::new(30, 9), Point::new(25, 31), Point::new(-4, 22)],
+        &[Point::new(0, 0), Point::new(30, 2), Point::new(14, 12), Point::new(28, 28), Point::new(-3, 25)],
+    ];
+    for vertices in shapes {
+        // the shoelace formula, doubled
+        let double_area: i32 = vertices.iter().zip(vertices.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        let covered: u32 = coverage_map(vertices).values().map(|&coverage| coverage as u32).sum();
+        let area = double_area.abs() as f32 / 2.0;
+        let covered_area = covered as f32 / FULL_COVERAGE as f32;
+        assert!((covered_area - area).abs() < 0.01 * area, "area {} covered {}", area, covered_area);
+    }
+}
+
+#[test]
+fn blend_runs_from_under_to_over() {
+    let (under, over) = (Rgb565::new(31, 0, 8), Rgb565::new(1, 63, 24));
+    assert_eq!(blend_rgb565(under, over, 0), under);
+    assert_eq!(blend_rgb565(under, over, FULL_COVERAGE), over);
+    assert_eq!(blend_rgb565(under, over, FULL_COVERAGE / 2), Rgb565::new(16, 32, 16));
+    assert_eq!(blend_rgb565(Rgb565::BLACK, Rgb565::WHITE, FULL_COVERAGE / 4), Rgb565::new(8, 16, 8));
+}
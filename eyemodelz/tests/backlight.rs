@@ -0,0 +1,72 @@
+use eyemodelz::backlight::*;
+
+#[test]
+fn lightness_table_runs_from_dark_to_full() {
+    assert_eq!(LIGHTNESS_TO_LUMINANCE[0], 0);
+    assert_eq!(LIGHTNESS_TO_LUMINANCE[100], FULL_LUMINANCE);
+    assert!(LIGHTNESS_TO_LUMINANCE.windows(2).all(|pair| pair[0] < pair[1]));
+    // half as bright to the eye is under a fifth of the light
+    let half = LIGHTNESS_TO_LUMINANCE[50] as f32 / FULL_LUMINANCE as f32;
+    assert!((half - 0.184).abs() < 0.001, "{}", half);
+    // the bottom steps are far finer than a percent of duty
+    assert!(LIGHTNESS_TO_LUMINANCE[1] < FULL_LUMINANCE / 500);
+}
+
+#[test]
+fn lightness_table_is_continuous_where_the_curve_changes() {
+    // the linear part meets the cubic at L* = 8
+    let (below, at, above) = (LIGHTNESS_TO_LUMINANCE[7], LIGHTNESS_TO_LUMINANCE[8], LIGHTNESS_TO_LUMINANCE[9]);
+    assert!(at - below < above - at + 8, "{} {} {}", below, at, above);
+}
+
+#[test]
+fn luminance_interpolates_between_percents() {
+    assert_eq!(luminance(40 << 8), LIGHTNESS_TO_LUMINANCE[40]);
+    let halfway = luminance((40 << 8) + 128) as u32;
+    let (lo, hi) = (LIGHTNESS_TO_LUMINANCE[40] as u32, LIGHTNESS_TO_LUMINANCE[41] as u32);
+    assert!(halfway.abs_diff((lo + hi) / 2) <= 1);
+    assert_eq!(luminance(200 << 8), FULL_LUMINANCE);
+}
+
+#[test]
+fn duty_cycle_scales_to_the_pwm() {
+    assert_eq!(duty_cycle(0, 7999), 0);
+    assert_eq!(duty_cycle(FULL_LUMINANCE, 7999), 7999);
+    assert_eq!(duty_cycle(FULL_LUMINANCE / 2, 8000), 4000);
+    // the faintest light still lights
+    assert_eq!(duty_cycle(1, 7999), 1);
+}
+
+#[test]
+fn fade_moves_at_a_limited_rate() {
+    let mut fade = BacklightFade::new(100);
+    // the first update starts from dark, and goes nowhere until time passes
+    assert_eq!(fade.update(80, 1000), 0);
+    fade.update(80, 1100);
+    assert_eq!(fade.lightness_x256(), 10 << 8);
+    fade.update(80, 1400);
+    assert_eq!(fade.lightness_x256(), 40 << 8);
+    // arrives and stays
+    assert_eq!(fade.update(80, 5000), LIGHTNESS_TO_LUMINANCE[80]);
+    assert!(fade.is_at(80));
+    // and back down, just as fast
+    fade.update(20, 5250);
+    assert_eq!(fade.lightness_x256(), 55 << 8);
+    fade.update(20, 9000);
+    assert!(fade.is_at(20));
+}
+
+#[test]
+fn fade_steps_are_small_at_frame_rate() {
+    let mut fade = BacklightFade::new(BacklightFade::DEFAULT_RATE_PCT_PER_S);
+    fade.update(100, 0);
+    let mut last = fade.lightness_x256();
+    // 30ms frames
+    for frame in 1..=100 {
+        fade.update(100, frame * 30);
+        let step = fade.lightness_x256() - last;
+        assert!(step <= (BacklightFade::DEFAULT_RATE_PCT_PER_S * 30 * 256).div_ceil(1000));
+        last = fade.lightness_x256();
+    }
+    assert!(fade.is_at(100));
+}
@@ -0,0 +1,159 @@
+use embedded_graphics::prelude::Point;
+use eyemodelz::blink::*;
+use eyemodelz::MorphFraction;
+
+const TIMING: BlinkTiming = BlinkTiming {
+    closing_ms: 100,
+    closed_ms: 50,
+    opening_ms: 200,
+    double_gap_ms: 80,
+    min_interval_ms: 1000,
+    max_interval_ms: 3000,
+    double_one_in: 0,
+};
+
+fn quiet_controller() -> BlinkController {
+    let mut controller = BlinkController::new(TIMING, 7, 0);
+    controller.set_spontaneous(false, 0);
+    controller
+}
+
+#[test]
+fn triggered_blink_runs_through_every_phase() {
+    let mut controller = quiet_controller();
+    assert_eq!(controller.update(500), LidClosure::OPEN);
+    controller.trigger(BlinkKind::Single, 1000);
+    assert_eq!(controller.phase(), BlinkPhase::Closing);
+
+    let half_closed = controller.update(1050);
+    assert_eq!(half_closed.left, MorphFraction::HALF);
+    assert_eq!(half_closed.right, MorphFraction::HALF);
+
+    assert_eq!(controller.update(1120).left, MorphFraction::END);
+    assert_eq!(controller.phase(), BlinkPhase::Closed);
+
+    assert_eq!(controller.update(1250).left, MorphFraction::HALF);
+    assert_eq!(controller.phase(), BlinkPhase::Opening);
+
+    assert_eq!(controller.update(1350), LidClosure::OPEN);
+    assert_eq!(controller.phase(), BlinkPhase::Open);
+    assert!(!controller.is_busy());
+}
+
+#[test]
+fn slow_frames_skip_through_phases() {
+    let mut controller = quiet_controller();
+    controller.trigger(BlinkKind::Single, 0);
+    // a single late update lands in the right phase
+    assert_eq!(controller.update(200).left, MorphFraction::from_ratio(50, 200).inverse());
+    assert_eq!(controller.update(10_000), LidClosure::OPEN);
+}
+
+#[test]
+fn double_blink_closes_twice() {
+    let mut controller = quiet_controller();
+    controller.trigger(BlinkKind::Double, 0);
+    let closed_frames: Vec<u64> = (0..1000).step_by(10)
+        .filter(|now| controller.update(*now).left == MorphFraction::END)
+        .collect();
+    // two separate closed periods: 100..=150, then reopening until 350, the gap, and closing from 430
+    assert_eq!(closed_frames, vec![100, 110, 120, 130, 140, 150, 530, 540, 550, 560, 570, 580]);
+    assert!(!controller.is_busy());
+}
+
+#[test]
+fn trigger_during_a_double_blink_is_dropped() {
+    let mut controller = quiet_controller();
+    controller.trigger(BlinkKind::Double, 0);
+    controller.trigger(BlinkKind::WinkLeft, 0);
+    let closed_frames: Vec<u64> = (0..1000).step_by(10)
+        .filter(|now| controller.update(*now) == LidClosure { left: MorphFraction::END, right: MorphFraction::END })
+        .collect();
+    // both halves of the double blink, closing both eyes, and no wink after
+    assert_eq!(closed_frames, vec![100, 110, 120, 130, 140, 150, 530, 540, 550, 560, 570, 580]);
+    assert!(!controller.is_busy());
+}
+
+#[test]
+fn trigger_waits_for_the_current_blink_and_keeps_the_first_request() {
+    let mut controller = quiet_controller();
+    controller.trigger(BlinkKind::Single, 0);
+    controller.trigger(BlinkKind::WinkRight, 50);
+    controller.trigger(BlinkKind::WinkLeft, 60);
+    assert_eq!(controller.update(120).left, MorphFraction::END);
+    assert_eq!(controller.active_kind(), Some(BlinkKind::Single));
+    // the wink starts as the single blink finishes opening, at 350
+    assert_eq!(controller.update(350), LidClosure::OPEN);
+    let closure = controller.update(470);
+    assert_eq!(closure.right, MorphFraction::END);
+    assert_eq!(closure.left, MorphFraction::START);
+    assert_eq!(controller.active_kind(), Some(BlinkKind::WinkRight));
+    assert_eq!(controller.update(10_000), LidClosure::OPEN);
+    assert!(!controller.is_busy());
+}
+
+#[test]
+fn wink_closes_only_one_eye() {
+    let mut controller = quiet_controller();
+    controller.trigger(BlinkKind::WinkRight, 0);
+    let closure = controller.update(120);
+    assert_eq!(closure.right, MorphFraction::END);
+    assert_eq!(closure.left, MorphFraction::START);
+    assert_eq!(closure.for_eye_side(false), MorphFraction::END);
+    assert_eq!(controller.active_kind(), Some(BlinkKind::WinkRight));
+}
+
+#[test]
+fn spontaneous_blinks_happen_within_interval() {
+    let mut controller = BlinkController::new(TIMING, 12345, 0);
+    let mut blink_starts = Vec::new();
+    let mut was_open = true;
+    for now in (0..60_000).step_by(10) {
+        let open = controller.update(now) == LidClosure::OPEN;
+        if was_open && !open {
+            blink_starts.push(now);
+        }
+        was_open = open;
+    }
+    assert!(blink_starts.len() >= 15, "only {} blinks", blink_starts.len());
+    for pair in blink_starts.windows(2) {
+        let gap = pair[1] - pair[0];
+        let blink_ms = (TIMING.closing_ms + TIMING.closed_ms + TIMING.opening_ms) as u64;
+        assert!(gap >= TIMING.min_interval_ms as u64 + blink_ms, "gap {}", gap);
+        assert!(gap <= TIMING.max_interval_ms as u64 + blink_ms + 10, "gap {}", gap);
+    }
+}
+
+/// A simple eye: an upper lid band from y=0 to y=40, and a lower lid band from y=100 to y=120
+fn rect(top: i32, bottom: i32) -> [Point; 4] {
+    [Point::new(0, top), Point::new(200, top), Point::new(200, bottom), Point::new(0, bottom)]
+}
+
+#[test]
+fn vertical_span_covers_polygon_outline() {
+    let upper = rect(0, 40);
+    assert_eq!(vertical_span_at(&upper, 50), Some((0, 40)));
+    assert_eq!(vertical_span_at(&upper, 0), Some((0, 40)));
+    assert_eq!(vertical_span_at(&upper, 250), None);
+    let triangle = [Point::new(0, 0), Point::new(100, 100), Point::new(0, 100)];
+    assert_eq!(vertical_span_at(&triangle, 50), Some((50, 100)));
+}
+
+#[test]
+fn closed_lids_meet_and_open_lids_are_unchanged() {
+    let (upper, lower) = (rect(0, 40), rect(100, 120));
+    let meeting = lid_meeting_y(&upper, &lower, 100).unwrap();
+    assert_eq!(meeting, 40 + 60 * UPPER_LID_BLINK_SHARE_PCT / 100);
+
+    let open = close_lid_vertices(&upper, true, &upper, &lower, MorphFraction::START).unwrap();
+    assert_eq!(open.as_slice(), &upper);
+
+    let closed_upper = close_lid_vertices(&upper, true, &upper, &lower, MorphFraction::END).unwrap();
+    let closed_lower = close_lid_vertices(&lower, false, &upper, &lower, MorphFraction::END).unwrap();
+    // the outer edges stay put, the inner edges meet
+    assert_eq!(closed_upper.as_slice(), &rect(0, meeting));
+    assert_eq!(closed_lower.as_slice(), &rect(meeting, 120));
+
+    let half_upper = close_lid_vertices(&upper, true, &upper, &lower, MorphFraction::HALF).unwrap();
+    assert_eq!(half_upper.as_slice(), &rect(0, 40 + (meeting - 40) / 2));
+}
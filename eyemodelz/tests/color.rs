@@ -0,0 +1,43 @@
+use embedded_graphics::pixelcolor::{Rgb565, Rgb888, RgbColor};
+use eyemodelz::*;
+
+#[test]
+fn unity_factor_is_identity() {
+    for color in [Rgb565::BLACK, Rgb565::WHITE, Rgb565::new(3, 17, 29), Rgb565::new(9, 0, 16)] {
+        assert_eq!(adjust_lightness_rgb565(color, 256), color);
+    }
+}
+
+#[test]
+fn darken_scales_each_channel() {
+    let color = Rgb565::new(20, 40, 10);
+    assert_eq!(adjust_lightness_rgb565(color, FACTOR_DARKEN_50), Rgb565::new(10, 20, 5));
+    assert_eq!(adjust_lightness_rgb565(color, FACTOR_DARKEN_10), Rgb565::new(17, 35, 8));
+}
+
+#[test]
+fn brighten_clamps_to_channel_max() {
+    assert_eq!(adjust_lightness_rgb565(Rgb565::WHITE, FACTOR_BRIGHTEN_50), Rgb565::WHITE);
+    assert_eq!(adjust_lightness_rgb565(Rgb565::new(30, 60, 4), FACTOR_BRIGHTEN_20), Rgb565::new(31, 63, 4));
+}
+
+#[test]
+fn black_stays_black() {
+    for factor in [FACTOR_DARKEN_30, FACTOR_BRIGHTEN_40, 0, 1024] {
+        assert_eq!(adjust_lightness_rgb565(Rgb565::BLACK, factor), Rgb565::BLACK);
+    }
+}
+
+#[test]
+fn negative_factor_clamps_to_black() {
+    assert_eq!(adjust_lightness_rgb565(Rgb565::WHITE, -256), Rgb565::BLACK);
+}
+
+#[test]
+fn mix_runs_from_start_to_end() {
+    let start = Rgb888::new(0, 100, 255);
+    let end = Rgb888::new(200, 100, 55);
+    assert_eq!(mix_rgb888(start, end, MorphFraction::START), start);
+    assert_eq!(mix_rgb888(start, end, MorphFraction::END), end);
+    assert_eq!(mix_rgb888(start, end, MorphFraction::HALF), Rgb888::new(100, 100, 155));
+}
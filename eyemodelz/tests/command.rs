@@ -0,0 +1,94 @@
+//!
+//! Parsing the live control command protocol, and formatting its status reply.
+//!
+
+use embedded_graphics::pixelcolor::Rgb565;
+use eyemodelz::command::*;
+use eyemodelz::pupil::PupilSize;
+use eyemodelz::*;
+
+const MODE_NAMES: [&str; 3] = ["ClockStar", "HStep", "Meander"];
+
+fn parse(line: &str) -> Result<Command, CommandError> {
+    parse_command(line, &MODE_NAMES)
+}
+
+#[test]
+fn modes_by_name_or_index() {
+    assert_eq!(parse("mode meander"), Ok(Command::SetMode(2)));
+    assert_eq!(parse("MODE HStep"), Ok(Command::SetMode(1)));
+    assert_eq!(parse("mode 0"), Ok(Command::SetMode(0)));
+    assert_eq!(parse("mode 3"), Err(CommandError::BadArgument));
+    assert_eq!(parse("mode Sideways"), Err(CommandError::BadArgument));
+    assert_eq!(parse("mode"), Err(CommandError::MissingArgument));
+}
+
+#[test]
+fn gaze_by_direction_vector_or_auto() {
+    assert_eq!(parse("gaze NorthWest"), Ok(Command::SetGaze(Some(GazeDirection::NorthWest.into()))));
+    assert_eq!(parse("gaze 12"), Ok(Command::SetGaze(Some(GazeDirection::East.into()))));
+    assert_eq!(parse("gaze -1 0.5"), Ok(Command::SetGaze(Some(GazeVector::from_f32(-1.0, 0.5)))));
+    assert_eq!(parse("gaze auto"), Ok(Command::SetGaze(None)));
+    assert_eq!(parse("gaze 33"), Err(CommandError::BadArgument));
+    assert_eq!(parse("gaze 1.5 0"), Err(CommandError::BadArgument));
+    assert_eq!(parse("gaze 0 0 0"), Err(CommandError::ExtraArgument));
+}
+
+#[test]
+fn emotion_colors_and_brightness() {
+    assert_eq!(parse("emotion surprise"), Ok(Command::SetEmotion(Some(EmotionExpression::Surprise))));
+    assert_eq!(parse("emotion 0"), Ok(Command::SetEmotion(Some(EmotionExpression::Neutral))));
+    assert_eq!(parse("emotion Auto"), Ok(Command::SetEmotion(None)));
+    assert_eq!(parse("iris #FF0000"), Ok(Command::SetIrisColor(Some(Rgb565::new(31, 0, 0)))));
+    assert_eq!(parse("skin 8eb34e"), Ok(Command::SetSkinColor(Some(Rgb565::new(0x8e >> 3, 0xb3 >> 2, 0x4e >> 3)))));
+    assert_eq!(parse("skin auto"), Ok(Command::SetSkinColor(None)));
+    assert_eq!(parse("iris #FF00"), Err(CommandError::BadArgument));
+    assert_eq!(parse("iris teal"), Err(CommandError::BadArgument));
+    assert_eq!(parse("brightness 100"), Ok(Command::SetBrightness(Some(100))));
+    assert_eq!(parse("brightness auto"), Ok(Command::SetBrightness(None)));
+    assert_eq!(parse("brightness 101"), Err(CommandError::BadArgument));
+    assert_eq!(parse("pupil 140"), Ok(Command::SetPupil(Some(PupilSize::from_pct(140)))));
+    assert_eq!(parse("pupil auto"), Ok(Command::SetPupil(None)));
+    assert_eq!(parse("pupil 59"), Err(CommandError::BadArgument));
+    assert_eq!(parse("pupil 151"), Err(CommandError::BadArgument));
+}
+
+#[test]
+fn framing_errors() {
+    assert_eq!(parse("  status  "), Ok(Command::Status));
+    assert_eq!(parse("Help"), Ok(Command::Help));
+    assert_eq!(parse(""), Err(CommandError::Empty));
+    assert_eq!(parse(" \t "), Err(CommandError::Empty));
+    assert_eq!(parse("blink"), Err(CommandError::UnknownCommand));
+    assert_eq!(parse("status now"), Err(CommandError::ExtraArgument));
+    let long = "mode ".repeat(MAX_COMMAND_LEN);
+    assert_eq!(parse(&long), Err(CommandError::TooLong));
+}
+
+#[test]
+fn status_line() {
+    let status = EyeStatus {
+        mode_name: "Meander",
+        gaze: GazeVector::from_f32(-1.0, 0.5),
+        emotion: EmotionExpression::Surprise,
+        iris_color: Rgb565::new(31, 0, 31),
+        skin_color: Rgb565::new(0x8e >> 3, 0xb3 >> 2, 0x4e >> 3),
+        brightness_pct: 75,
+        pupil: PupilSize::from_pct(110),
+    };
+    assert_eq!(status.to_string(),
+        "mode=Meander gaze=-1.000,0.500 emotion=Surprise iris=#FF00FF skin=#8CB24A brightness=75 pupil=110");
+}
+
+#[test]
+fn names_round_trip() {
+    for idx in 0..EmotionExpression::MaxCount as u8 {
+        let emotion = EmotionExpression::try_from(idx).unwrap();
+        assert_eq!(parse(&format!("emotion {}", emotion.name())), Ok(Command::SetEmotion(Some(emotion))));
+    }
+    for idx in 0..GazeDirection::MaxCount as u8 {
+        let dir = GazeDirection::try_from(idx).unwrap();
+        assert_eq!(format!("{:?}", dir), dir.name());
+        assert_eq!(parse(&format!("gaze {}", dir.name())), Ok(Command::SetGaze(Some(dir.into()))));
+    }
+}
@@ -0,0 +1,80 @@
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use eyemodelz::dirty_rect::*;
+
+const FRAME: Size = Size::new(320, 240);
+
+#[test]
+fn empty_until_drawn() {
+    let mut dirty = DirtyRect::new();
+    assert!(dirty.is_empty());
+    assert_eq!(dirty.bounds(FRAME), None);
+    dirty.add_points(&[], 2);
+    assert!(dirty.is_empty());
+}
+
+#[test]
+fn grows_to_hold_every_shape() {
+    let mut dirty = DirtyRect::new();
+    dirty.add_points(&[Point::new(100, 50), Point::new(120, 80), Point::new(90, 60)], 0);
+    assert_eq!(dirty.bounds(FRAME), Some(Rectangle::with_corners(Point::new(90, 50), Point::new(120, 80))));
+    dirty.add_rect(Rectangle::new(Point::new(200, 10), Size::new(5, 5)));
+    assert_eq!(dirty.bounds(FRAME), Some(Rectangle::with_corners(Point::new(90, 10), Point::new(204, 80))));
+}
+
+#[test]
+fn margin_and_clipping() {
+    let mut dirty = DirtyRect::new();
+    dirty.add_points(&[Point::new(2, 100), Point::new(330, 239)], 3);
+    assert_eq!(dirty.bounds(FRAME), Some(Rectangle::with_corners(Point::new(0, 97), Point::new(319, 239))));
+
+    // entirely off screen
+    let mut dirty = DirtyRect::new();
+    dirty.add_points(&[Point::new(-20, -20), Point::new(-10, -5)], 1);
+    assert!(!dirty.is_empty());
+    assert_eq!(dirty.bounds(FRAME), None);
+}
+
+#[test]
+fn take_resets() {
+    let mut dirty = DirtyRect::new();
+    dirty.add_frame(FRAME);
+    assert_eq!(dirty.take(FRAME), Some(Rectangle::new(Point::zero(), FRAME)));
+    assert!(dirty.is_empty());
+    assert_eq!(dirty.take(FRAME), None);
+}
+
+#[test]
+fn narrow_regions_go_row_by_row() {
+    let iris = Rectangle::new(Point::new(100, 60), Size::new(120, 130));
+    assert_eq!(transfer_rect(iris, FRAME.width, 256), iris);
+
+    // nearly full width is cheaper sent as whole rows
+    let wide = Rectangle::new(Point::new(10, 60), Size::new(300, 130));
+    assert_eq!(transfer_rect(wide, FRAME.width, 256), Rectangle::new(Point::new(0, 60), Size::new(320, 130)));
+}
+
+#[test]
+fn bands_cover_the_region_in_order() {
+    let rect = Rectangle::new(Point::new(40, 30), Size::new(100, 95));
+    // 10 rows of 200 bytes fit in each band
+    let all: Vec<_> = bands(rect, 2000 + 199).collect();
+    assert_eq!(all.len(), 10);
+    assert_eq!(all[0], Rectangle::new(Point::new(40, 30), Size::new(100, 10)));
+    assert_eq!(all[9], Rectangle::new(Point::new(40, 120), Size::new(100, 5)));
+    assert!(all.windows(2).all(|pair| pair[1].top_left.y == pair[0].top_left.y + pair[0].size.height as i32));
+
+    // at least a row at a time, even if it doesn't fit
+    assert_eq!(bands(rect, 10).count(), 95);
+    assert_eq!(bands(rect, 1 << 20).collect::<Vec<_>>(), vec![rect]);
+}
+
+#[test]
+fn packing_copies_rows_without_gaps() {
+    let width = 8u32;
+    let frame: Vec<u8> = (0..width * 4 * 2).map(|idx| idx as u8).collect();
+    let rect = Rectangle::new(Point::new(2, 1), Size::new(3, 2));
+    let mut packed = [0u8; 16];
+    assert_eq!(pack_rect(&frame, width, rect, &mut packed), 12);
+    assert_eq!(&packed[..12], &[20, 21, 22, 23, 24, 25, 36, 37, 38, 39, 40, 41]);
+}
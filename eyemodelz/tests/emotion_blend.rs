@@ -0,0 +1,73 @@
+use eyemodelz::emotion_blend::*;
+use eyemodelz::{EmotionExpression, MorphFraction};
+
+const DURATION_MS: u32 = 400;
+
+#[test]
+fn holding_an_emotion_is_steady() {
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, DURATION_MS);
+    assert_eq!(blender.update(EmotionExpression::Neutral, 1000), EmotionBlend::steady(EmotionExpression::Neutral));
+    assert!(!blender.is_busy(1000));
+}
+
+#[test]
+fn change_blends_over_the_duration() {
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, DURATION_MS);
+    let start = blender.update(EmotionExpression::Happy, 1000);
+    assert_eq!((start.from, start.to, start.progress), (EmotionExpression::Neutral, EmotionExpression::Happy, MorphFraction::START));
+    assert!(blender.is_busy(1000));
+
+    // eased, so it's symmetric about the middle, slow at each end
+    let middle = blender.update(EmotionExpression::Happy, 1200);
+    assert_eq!(middle.progress, MorphFraction::HALF);
+    let early = blender.update(EmotionExpression::Happy, 1040);
+    assert!(early.progress < MorphFraction::from_ratio(1, 10));
+
+    let progress: Vec<_> = (1000..1400).step_by(20).map(|ms| blender.update(EmotionExpression::Happy, ms).progress).collect();
+    assert!(progress.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", progress);
+
+    assert_eq!(blender.update(EmotionExpression::Happy, 1400), EmotionBlend::steady(EmotionExpression::Happy));
+    assert!(!blender.is_busy(1400));
+}
+
+#[test]
+fn reversing_midway_keeps_the_shape() {
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, DURATION_MS);
+    blender.update(EmotionExpression::Shy, 0);
+    let before = blender.update(EmotionExpression::Shy, 100);
+    let reversed = blender.update(EmotionExpression::Neutral, 100);
+    assert_eq!((reversed.from, reversed.to), (EmotionExpression::Shy, EmotionExpression::Neutral));
+    let diff = before.progress.raw().abs_diff(reversed.progress.inverse().raw());
+    assert!(diff <= 2, "{:?} vs {:?}", before, reversed);
+    // and the way back only takes as long as we'd come
+    assert_eq!(blender.update(EmotionExpression::Neutral, 200), EmotionBlend::steady(EmotionExpression::Neutral));
+}
+
+#[test]
+fn new_target_midway_starts_from_the_nearer_emotion() {
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, DURATION_MS);
+    blender.update(EmotionExpression::Love, 0);
+    let retarget = blender.update(EmotionExpression::Confused, 300);
+    assert_eq!((retarget.from, retarget.to, retarget.progress),
+        (EmotionExpression::Love, EmotionExpression::Confused, MorphFraction::START));
+
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, DURATION_MS);
+    blender.update(EmotionExpression::Love, 0);
+    let retarget = blender.update(EmotionExpression::Confused, 100);
+    assert_eq!(retarget.from, EmotionExpression::Neutral);
+}
+
+#[test]
+fn zero_duration_snaps() {
+    let mut blender = EmotionBlender::new(EmotionExpression::Neutral, 0);
+    assert_eq!(blender.update(EmotionExpression::Surprise, 5), EmotionBlend::steady(EmotionExpression::Surprise));
+}
+
+#[test]
+fn blends_pack_into_bits() {
+    let blend = EmotionBlend { from: EmotionExpression::Trepidation, to: EmotionExpression::Curious, progress: MorphFraction::from_ratio(1, 3) };
+    assert_eq!(EmotionBlend::from_bits(blend.to_bits()), blend);
+    let steady = EmotionBlend::steady(EmotionExpression::Skeptical);
+    assert_eq!(EmotionBlend::from_bits(steady.to_bits()), steady);
+    assert!(steady.is_steady() && !blend.is_steady());
+}
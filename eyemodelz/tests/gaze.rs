@@ -0,0 +1,131 @@
+use eyemodelz::*;
+
+#[test]
+fn grid_constants_match_sparse_asset_layout() {
+    assert_eq!(NUM_LOOK_STEPS, 4);
+    assert_eq!(LAST_LOOK_STEP_IDX, 3);
+    assert_eq!(SPARSE_GRID_DIM, 7);
+    // center plus 8 arms of (tweens + endpoint)
+    assert_eq!(SPARSE_GRID_LEN, 25);
+    assert_eq!(SPARSE_CTR_IDX, 12);
+}
+
+#[test]
+fn row_col_and_digits_agree() {
+    for idx in 0..GazeDirection::MaxCount as u8 {
+        let dir = GazeDirection::try_from(idx).unwrap();
+        let (row, col) = dir.row_col();
+        assert_eq!(row * ORIGINAL_ASSET_GRID_DIM + col, idx);
+        let digits = dir.to_digits().as_bytes();
+        assert_eq!(digits, &[b'0' + row, b'0' + col]);
+    }
+}
+
+#[test]
+fn arm_order_round_trip_returns_to_center() {
+    let arm_order = GazeDirection::CARDINAL_CLOCK_EDGE_ORDER;
+    let steps: Vec<(GazeDirection, u8)> = (0..GazeDirection::RT_STEPS_PER_ARM)
+        .map(|count| GazeDirection::gaze_and_step_for_arm_order(count, &arm_order))
+        .collect();
+    assert_eq!(steps, vec![
+        (GazeDirection::StraightAhead, 0),
+        (GazeDirection::NorthWest, 1),
+        (GazeDirection::NorthWest, 2),
+        (GazeDirection::NorthWest, 3),
+        (GazeDirection::NorthWest, 2),
+        (GazeDirection::NorthWest, 1),
+        (GazeDirection::StraightAhead, 0),
+    ]);
+}
+
+#[test]
+fn arm_order_visits_every_arm_then_wraps() {
+    for arm_order in [
+        &GazeDirection::CARDINAL_H8_ORDER[..],
+        &GazeDirection::CARDINAL_CLOCK_EDGE_ORDER[..],
+        &GazeDirection::CARDINAL_ANTICLOCK_EDGE_ORDER[..],
+        &GazeDirection::CARDINAL_HSWEEP_ORDER[..],
+        &GazeDirection::CARDINAL_VSWEEP_ORDER[..],
+    ] {
+        let cycle_len = GazeDirection::RT_STEPS_PER_ARM * arm_order.len();
+        for (arm_idx, expected) in arm_order.iter().enumerate() {
+            let peak = arm_idx * GazeDirection::RT_STEPS_PER_ARM + 3;
+            assert_eq!(GazeDirection::gaze_and_step_for_arm_order(peak, arm_order), (*expected, LAST_LOOK_STEP_IDX));
+            assert_eq!(GazeDirection::gaze_and_step_for_arm_order(peak + cycle_len, arm_order), (*expected, LAST_LOOK_STEP_IDX));
+        }
+    }
+}
+
+#[test]
+fn look_steps_stay_in_range() {
+    for count in 0..1000 {
+        for (dir, step) in [
+            GazeDirection::gaze_and_look_for_meander(count),
+            GazeDirection::gaze_and_step_for_sparse_star(count),
+            GazeDirection::gaze_and_step_for_hsweep(count),
+            GazeDirection::gaze_and_step_for_vsweep(count),
+        ] {
+            assert!(step <= LAST_LOOK_STEP_IDX);
+            assert_ne!(dir, GazeDirection::MaxCount);
+            // only the center is ever reported with step zero
+            assert_eq!(step == 0, dir == GazeDirection::StraightAhead);
+        }
+    }
+}
+
+#[test]
+fn sweeps_stay_on_their_axis() {
+    for count in 0..100 {
+        let (hdir, _) = GazeDirection::gaze_and_step_for_hsweep(count);
+        assert!(matches!(hdir, GazeDirection::West | GazeDirection::East | GazeDirection::StraightAhead));
+        let (vdir, _) = GazeDirection::gaze_and_step_for_vsweep(count);
+        assert!(matches!(vdir, GazeDirection::North | GazeDirection::South | GazeDirection::StraightAhead));
+    }
+}
+
+#[test]
+fn emotion_digits() {
+    assert_eq!(EmotionExpression::Neutral.as_digit(), b'0');
+    assert_eq!(EmotionExpression::Surprise.as_digit(), b'1');
+}
+
+#[test]
+fn direct_transitions_chain_between_targets() {
+    let targets = [GazeDirection::NorthWest, GazeDirection::East, GazeDirection::South];
+    let steps_per_leg = 5;
+    let legs: Vec<GazeTransition> = (0..steps_per_leg * targets.len())
+        .map(|count| GazeDirection::gaze_transition_for_target_order(count, &targets, steps_per_leg))
+        .collect();
+    for (idx, gaze) in legs.iter().enumerate() {
+        let leg = idx / steps_per_leg;
+        assert_eq!(gaze.start, targets[leg]);
+        assert_eq!(gaze.end, targets[(leg + 1) % targets.len()]);
+        assert_ne!(gaze.progress, MorphFraction::END);
+        // never passes back through center
+        assert_ne!(gaze.keyframe(), Some(GazeDirection::StraightAhead));
+    }
+    // arriving at one target would duplicate step 0 of the next leg, so it is skipped
+    assert_eq!(legs[steps_per_leg].keyframe(), Some(GazeDirection::East));
+    assert_eq!(legs[1].progress, MorphFraction::from_ratio(1, 5));
+}
+
+#[test]
+fn reversed_transition_lands_on_same_pose() {
+    let gaze = GazeTransition::from_center(GazeDirection::NorthEast, 1);
+    let reversed = gaze.reversed();
+    assert_eq!(reversed.start, GazeDirection::NorthEast);
+    assert_eq!(reversed.end, GazeDirection::StraightAhead);
+    assert_eq!(reversed.look_step(), Some(LAST_LOOK_STEP_IDX - 1));
+    assert_eq!(reversed.reversed(), gaze);
+    assert_eq!(GazeTransition::from_center(GazeDirection::NorthEast, 0).keyframe(), Some(GazeDirection::StraightAhead));
+    assert_eq!(gaze.keyframe(), None);
+}
+
+#[test]
+fn look_steps_are_recovered_from_progress() {
+    for step in 0..NUM_LOOK_STEPS {
+        assert_eq!(GazeTransition::from_center(GazeDirection::West, step).look_step(), Some(step));
+    }
+    let between = GazeTransition { progress: MorphFraction::from_ratio(1, 2), ..GazeTransition::from_center(GazeDirection::West, 0) };
+    assert_eq!(between.look_step(), None);
+}
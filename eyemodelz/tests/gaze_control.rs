@@ -0,0 +1,146 @@
+//!
+//! The gaze controller's timing: fixations, saccades, microsaccades and smooth pursuit.
+//!
+
+use eyemodelz::gaze_control::*;
+use eyemodelz::*;
+
+/// Timing with no microsaccades or pursuit, so fixations are steady and every move is a saccade
+const SACCADES_ONLY: GazeTiming = GazeTiming {
+    pursuit_one_in: 0,
+    min_microsaccade_interval_ms: u32::MAX / 2,
+    max_microsaccade_interval_ms: u32::MAX / 2,
+    ..GazeTiming::HUMAN
+};
+
+fn is_at_rest(gaze: GazeTransition) -> bool {
+    gaze.start == gaze.end && gaze.progress == MorphFraction::START
+}
+
+#[test]
+fn starts_fixating_straight_ahead() {
+    let mut controller = GazeController::new(GazeTiming::HUMAN, GazeTargets::Random, 7, 1000);
+    assert_eq!(controller.motion(), GazeMotion::Fixation);
+    assert_eq!(controller.update(1000),
+        GazeTransition { start: GazeDirection::StraightAhead, end: GazeDirection::StraightAhead, progress: MorphFraction::START });
+}
+
+#[test]
+fn sequence_targets_are_visited_in_order() {
+    let mut controller = GazeController::new(SACCADES_ONLY, GazeTargets::Sequence(&GazeDirection::CARDINAL_H8_ORDER), 3, 0);
+    let mut visited = Vec::new();
+    for now_ms in (0..60_000).step_by(5) {
+        let gaze = controller.update(now_ms);
+        if is_at_rest(gaze) && visited.last() != Some(&gaze.end) {
+            visited.push(gaze.end);
+        }
+    }
+    assert!(visited.len() > 10, "only visited {:?}", visited);
+    let expected = core::iter::once(GazeDirection::StraightAhead)
+        .chain(GazeDirection::CARDINAL_H8_ORDER.iter().copied().cycle());
+    assert!(visited.iter().copied().eq(expected.take(visited.len())), "visited {:?}", visited);
+}
+
+#[test]
+fn fixations_dwell_within_timing_range() {
+    let timing = SACCADES_ONLY;
+    let mut controller = GazeController::new(timing, GazeTargets::Random, 11, 0);
+    let mut fixation_start: Option<u64> = Some(0);
+    let mut dwells = Vec::new();
+    for now_ms in 0..30_000 {
+        controller.update(now_ms);
+        match (controller.motion(), fixation_start) {
+            (GazeMotion::Fixation, None) => fixation_start = Some(now_ms),
+            (GazeMotion::Saccade, Some(start_ms)) => {
+                dwells.push(now_ms - start_ms);
+                fixation_start = None;
+            }
+            _ => {}
+        }
+    }
+    assert!(dwells.len() > 10);
+    for dwell in dwells {
+        assert!((timing.min_fixation_ms as u64..=timing.max_fixation_ms as u64).contains(&dwell), "dwell {}", dwell);
+    }
+}
+
+#[test]
+fn saccades_are_fast_and_scale_with_distance() {
+    let timing = SACCADES_ONLY;
+    let mut controller = GazeController::new(timing, GazeTargets::Random, 5, 0);
+    for (target, cells) in [(GazeDirection::East, 1), (GazeDirection::West, 2)] {
+        let start_ms = 10_000 * cells as u64;
+        controller.update(start_ms);
+        controller.saccade_to(target, start_ms);
+        let expected_ms = (timing.saccade_base_ms + timing.saccade_ms_per_cell * cells) as u64;
+        let mid = controller.update(start_ms + expected_ms / 2);
+        assert_eq!(mid.end, target);
+        assert!(mid.progress > MorphFraction::START && mid.progress < MorphFraction::END);
+        assert_eq!(controller.motion(), GazeMotion::Saccade);
+        let landed = controller.update(start_ms + expected_ms);
+        assert_eq!(controller.motion(), GazeMotion::Fixation);
+        assert_eq!(landed, GazeTransition { start: target, end: target, progress: MorphFraction::START });
+    }
+}
+
+#[test]
+fn pursuit_moves_steadily() {
+    let timing = SACCADES_ONLY;
+    let mut controller = GazeController::new(timing, GazeTargets::Random, 5, 0);
+    controller.pursue(GazeDirection::North, 0);
+    assert_eq!(controller.motion(), GazeMotion::Pursuit);
+    let mut last = MorphFraction::START;
+    for now_ms in (100..timing.pursuit_ms_per_cell as u64).step_by(100) {
+        let gaze = controller.update(now_ms);
+        assert_eq!((gaze.start, gaze.end), (GazeDirection::StraightAhead, GazeDirection::North));
+        assert!(gaze.progress > last);
+        assert_eq!(gaze.progress, MorphFraction::from_ratio(now_ms as u32, timing.pursuit_ms_per_cell));
+        last = gaze.progress;
+    }
+    controller.update(timing.pursuit_ms_per_cell as u64);
+    assert_eq!(controller.motion(), GazeMotion::Fixation);
+}
+
+#[test]
+fn microsaccades_stay_near_the_fixation_point() {
+    let timing = GazeTiming { min_fixation_ms: 100_000, max_fixation_ms: 100_001, ..GazeTiming::HUMAN };
+    let mut controller = GazeController::new(timing, GazeTargets::Random, 9, 0);
+    controller.saccade_to(GazeDirection::NorthWest, 0);
+    let mut offsets = 0;
+    for now_ms in (1000..20_000).step_by(10) {
+        let gaze = controller.update(now_ms);
+        assert_eq!(gaze.start, GazeDirection::NorthWest);
+        if gaze.end != gaze.start {
+            offsets += 1;
+            assert_eq!(gaze.progress, timing.microsaccade_amplitude);
+            let ((row, col), (end_row, end_col)) = (gaze.start.row_col(), gaze.end.row_col());
+            assert!(row.abs_diff(end_row) <= 1 && col.abs_diff(end_col) <= 1, "{:?}", gaze);
+        }
+    }
+    assert!(offsets > 0, "no microsaccades");
+}
+
+#[test]
+fn slow_frames_catch_up() {
+    // sampling once a minute still passes through whole movements, without getting stuck
+    let mut controller = GazeController::new(GazeTiming::HUMAN, GazeTargets::Random, 13, 0);
+    let mut fixations = Vec::new();
+    for minute in 1..50u64 {
+        let gaze = controller.update(minute * 60_000);
+        if !fixations.contains(&gaze.start) {
+            fixations.push(gaze.start);
+        }
+    }
+    assert!(fixations.len() > 3, "{:?}", fixations);
+}
+
+#[test]
+fn grid_positions_round_trip() {
+    for idx in 0..GazeDirection::MaxCount as u8 {
+        let dir = GazeDirection::try_from(idx).unwrap();
+        let (row, col) = dir.row_col();
+        assert_eq!(GazeDirection::from_row_col(row as i8, col as i8), Some(dir));
+    }
+    assert_eq!(GazeDirection::from_row_col(-1, 0), None);
+    assert_eq!(GazeDirection::from_row_col(1, 3), None);
+}
@@ -0,0 +1,84 @@
+//!
+//! Continuous gaze vectors, and how they resolve onto the sparse grid of authored assets.
+//!
+
+use eyemodelz::*;
+
+fn all_directions() -> impl Iterator<Item = GazeDirection> {
+    (0..GazeDirection::MaxCount as u8).map(|idx| GazeDirection::try_from(idx).unwrap())
+}
+
+#[test]
+fn directions_resolve_to_their_keyframes() {
+    for dir in all_directions() {
+        let blend = GazeVector::from(dir).resolve();
+        let resolved = if blend.weight == MorphFraction::END { blend.secondary } else { blend.primary };
+        assert!(blend.weight == MorphFraction::START || blend.weight == MorphFraction::END, "{:?}: {:?}", dir, blend);
+        assert_eq!(resolved.keyframe(), Some(dir), "{:?}: {:?}", dir, blend);
+        assert_eq!(GazeVector::from(dir).nearest_direction(), dir);
+    }
+}
+
+#[test]
+fn sparse_grid_control_points_resolve_to_authored_steps() {
+    let ctr = SPARSE_GRID_ARM_LEN as i8;
+    for dir in all_directions().filter(|dir| *dir != GazeDirection::StraightAhead) {
+        let (row, col) = dir.row_col();
+        let (d_row, d_col) = (row as i8 - 1, col as i8 - 1);
+        for look_step in 0..NUM_LOOK_STEPS {
+            let (grid_row, grid_col) = (ctr + d_row * look_step as i8, ctr + d_col * look_step as i8);
+            let blend = GazeVector::from_sparse_grid(grid_row as u8, grid_col as u8).unwrap().resolve();
+            let resolved = if blend.weight == MorphFraction::END { blend.secondary } else { blend.primary };
+            assert!(blend.weight == MorphFraction::START || blend.weight == MorphFraction::END, "{:?}: {:?}", dir, blend);
+            assert_eq!(resolved.look_step(), Some(look_step), "{:?} step {}: {:?}", dir, look_step, blend);
+            if look_step > 0 {
+                assert_eq!(resolved.end, dir);
+            }
+        }
+    }
+    assert_eq!(GazeVector::from_sparse_grid(SPARSE_GRID_DIM, 0), None);
+}
+
+#[test]
+fn transitions_at_look_steps_resolve_to_authored_steps() {
+    for dir in all_directions().filter(|dir| *dir != GazeDirection::StraightAhead) {
+        for look_step in 1..NUM_LOOK_STEPS {
+            let blend = GazeVector::from(GazeTransition::from_center(dir, look_step)).resolve();
+            let resolved = if blend.weight == MorphFraction::END { blend.secondary } else { blend.primary };
+            assert_eq!((resolved.end, resolved.look_step()), (dir, Some(look_step)), "{:?}", blend);
+        }
+    }
+}
+
+#[test]
+fn between_arms_blends_neighbors() {
+    // up and to the left, closer to West than to NorthWest
+    let blend = GazeVector::from_f32(-0.9, -0.3).resolve();
+    assert_eq!(blend.primary.end, GazeDirection::West);
+    assert_eq!(blend.secondary.end, GazeDirection::NorthWest);
+    assert!((blend.primary.progress.to_f32() - 0.9).abs() < 0.001, "{:?}", blend.primary.progress);
+    assert_eq!(blend.secondary.progress, blend.primary.progress);
+    assert!((blend.weight.to_f32() - 1.0 / 3.0).abs() < 0.001, "{:?}", blend.weight);
+
+    // closer to North than to NorthEast
+    let blend = GazeVector::from_f32(0.2, -0.8).resolve();
+    assert_eq!((blend.primary.end, blend.secondary.end), (GazeDirection::North, GazeDirection::NorthEast));
+    assert!((blend.weight.to_f32() - 0.25).abs() < 0.001, "{:?}", blend.weight);
+}
+
+#[test]
+fn transitions_move_in_straight_lines() {
+    let gaze = GazeTransition { start: GazeDirection::NorthWest, end: GazeDirection::East, progress: MorphFraction::HALF };
+    let (x, y) = GazeVector::from(gaze).to_f32();
+    assert!(x.abs() < 0.001 && (y + 0.5).abs() < 0.001, "({}, {})", x, y);
+    assert_eq!(GazeVector::from(gaze.reversed()), GazeVector::from(gaze));
+}
+
+#[test]
+fn clamps_and_packs() {
+    assert_eq!(GazeVector::from_f32(3.0, f32::NAN), GazeVector::new(GazeVector::SCALE, 0));
+    assert_eq!(GazeVector::new(i16::MIN, i16::MAX), GazeVector::new(-GazeVector::SCALE, GazeVector::SCALE));
+    for gaze in [GazeVector::STRAIGHT_AHEAD, GazeVector::new(-5, 7), GazeVector::new(-GazeVector::SCALE, 1234)] {
+        assert_eq!(GazeVector::from_bits(gaze.to_bits()), gaze);
+    }
+}
@@ -0,0 +1,112 @@
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use embedded_graphics::geometry::AnchorPoint;
+use embedded_graphics::prelude::{Point, PointsIter, Size, Transform};
+use embedded_graphics::primitives::Rectangle;
+use eyemodelz::iris_texture::*;
+
+const IRIS: Rectangle = Rectangle::new(Point::new(92, 90), Size::new(135, 139));
+const PUPIL: Rectangle = Rectangle::new(Point::new(132, 131), Size::new(57, 59));
+const TEXTURE: IrisTexture = IrisTexture::new(Rgb565::new(8, 23, 16), 0x1234_5678);
+
+/// The point `fraction` (out of 256) of the way from the iris's center to its edge, straight right
+fn right_of_center(iris: Rectangle, fraction: i32) -> Point {
+    iris.center() + Point::new(iris.size.width as i32 * fraction / 512, 0)
+}
+
+/// The lightness of a color, as the sum of its channels at 8 bits each
+fn brightness(color: Rgb565) -> u32 {
+    (color.r() as u32 * 255 / 31) + (color.g() as u32 * 255 / 63) + (color.b() as u32 * 255 / 31)
+}
+
+/// The average brightness around the ring at `depth`, sampling every pixel of the iris near it
+fn ring_brightness(shader: &IrisShader, depth: u32) -> u32 {
+    let (total, count) = IRIS.points()
+        .filter(|&point| shader.coords(point).depth.abs_diff(depth) <= 2)
+        .fold((0, 0), |(total, count), point| (total + brightness(shader.shade(point)), count + 1));
+    assert!(count > 0, "nothing at depth {}", depth);
+    total / count
+}
+
+#[test]
+fn coords_run_from_the_pupil_to_the_limbus() {
+    let shader = TEXTURE.shader(IRIS, PUPIL);
+    assert_eq!(shader.coords(IRIS.center()).depth, 0);
+    assert_eq!(shader.coords(right_of_center(IRIS, 100)).depth, 0);
+    // halfway from the pupil's rim to the limbus
+    let halfway = shader.coords(right_of_center(IRIS, 182)).depth;
+    assert!((112..144).contains(&halfway), "{}", halfway);
+    assert!(shader.coords(right_of_center(IRIS, 256)).depth >= 245);
+    assert_eq!(shader.coords(Point::new(400, 0)).depth, FULL_DEPTH);
+}
+
+#[test]
+fn angles_go_counterclockwise_from_the_right() {
+    // a round iris, since an oval one squashes the angles with it
+    let iris = Rectangle::new(IRIS.top_left, Size::new(135, 135));
+    let shader = TEXTURE.shader(iris, PUPIL);
+    let center = iris.center();
+    let angle_to = |dx: i32, dy: i32| shader.coords(center + Point::new(dx, dy)).angle as i32;
+    let quarter = ANGLE_STEPS as i32 / 4;
+    let expected = [((40, 0), 0), ((40, -40), quarter / 2), ((0, -40), quarter), ((-40, 0), 2 * quarter),
+        ((0, 40), 3 * quarter), ((40, 20), 4 * quarter - 76)];
+    for ((dx, dy), angle) in expected {
+        let found = angle_to(dx, dy);
+        let error = (found - angle).rem_euclid(ANGLE_STEPS as i32);
+        assert!(error <= 3 || error >= ANGLE_STEPS as i32 - 3, "{},{}: {} for {}", dx, dy, found, angle);
+    }
+}
+
+#[test]
+fn pattern_depends_only_on_the_seed() {
+    let shader = TEXTURE.shader(IRIS, PUPIL);
+    let again = IrisTexture::new(TEXTURE.color, TEXTURE.seed).shader(IRIS, PUPIL);
+    let other = IrisTexture::new(TEXTURE.color, 0x8765_4321).shader(IRIS, PUPIL);
+    let points: Vec<Point> = IRIS.points().collect();
+    assert!(points.iter().all(|&point| shader.shade(point) == again.shade(point)));
+    let num_differing = points.iter().filter(|&&point| shader.shade(point) != other.shade(point)).count();
+    assert!(num_differing > points.len() / 4, "{} of {}", num_differing, points.len());
+}
+
+#[test]
+fn fibers_vary_around_the_iris() {
+    let shader = TEXTURE.shader(IRIS, PUPIL);
+    let around: Vec<u32> = (0..ANGLE_STEPS as i32).step_by(4)
+        .map(|step| {
+            let turn = step as f32 / ANGLE_STEPS as f32 * core::f32::consts::TAU;
+            let at = IRIS.center() + Point::new((45.0 * turn.cos()) as i32, (45.0 * turn.sin()) as i32);
+            brightness(shader.shade(at))
+        })
+        .collect();
+    let (darkest, lightest) = (*around.iter().min().unwrap(), *around.iter().max().unwrap());
+    assert!(lightest > darkest + 40, "{}..{}", darkest, lightest);
+}
+
+#[test]
+fn rings_lighten_the_collarette_and_darken_the_edges() {
+    let shader = TEXTURE.shader(IRIS, PUPIL);
+    let ruff = ring_brightness(&shader, 2);
+    let collarette = ring_brightness(&shader, 80);
+    let middle = ring_brightness(&shader, 170);
+    let limbus = ring_brightness(&shader, 250);
+    assert!(collarette > middle && middle > limbus && collarette > ruff, "{} {} {} {}", ruff, collarette, middle, limbus);
+    assert!(limbus < middle * 2 / 3, "{} {}", middle, limbus);
+}
+
+#[test]
+fn pattern_moves_with_the_iris() {
+    let shader = TEXTURE.shader(IRIS, PUPIL);
+    let offset = Point::new(-37, 12);
+    let moved = TEXTURE.shader(IRIS.translate(offset), PUPIL.translate(offset));
+    assert!(IRIS.points().all(|point| shader.shade(point) == moved.shade(point + offset)));
+}
+
+#[test]
+fn fibers_stretch_as_the_pupil_narrows() {
+    let dilated = TEXTURE.shader(IRIS, PUPIL.resized(Size::new(85, 88), AnchorPoint::Center));
+    let constricted = TEXTURE.shader(IRIS, PUPIL.resized(Size::new(34, 35), AnchorPoint::Center));
+    // the pupil's rim is further out for the dilated pupil, the limbus is where it was
+    assert_eq!(dilated.coords(right_of_center(IRIS, 135)).depth, 0);
+    assert!(constricted.coords(right_of_center(IRIS, 135)).depth > 40);
+    assert!(dilated.coords(right_of_center(IRIS, 256)).depth >= 240);
+    assert!(constricted.coords(right_of_center(IRIS, 256)).depth >= 240);
+}
@@ -0,0 +1,70 @@
+use embedded_graphics::prelude::Point;
+use eyemodelz::morph::*;
+
+#[test]
+fn interpolation_endpoints_are_exact() {
+    let start = [Point::new(0, 0), Point::new(10, -4), Point::new(3, 7)];
+    let end = [Point::new(6, 9), Point::new(-2, 4), Point::new(3, 1)];
+    assert_eq!(interpolate_vertices(&start, &end, 0, 3).unwrap().as_slice(), &start);
+    assert_eq!(interpolate_vertices(&start, &end, 3, 3).unwrap().as_slice(), &end);
+}
+
+#[test]
+fn interpolation_rounds_symmetrically() {
+    let start = [Point::new(0, 0)];
+    let end = [Point::new(3, -3)];
+    assert_eq!(interpolate_vertices(&start, &end, 1, 2).unwrap().as_slice(), &[Point::new(2, -2)]);
+    assert_eq!(interpolate_vertices(&end, &start, 1, 2).unwrap().as_slice(), &[Point::new(1, -1)]);
+    assert_eq!(lerp_point(Point::new(0, 0), Point::new(9, 9), 1, 3), Point::new(3, 3));
+}
+
+#[test]
+fn mismatched_keyframes_are_rejected() {
+    let three = [Point::zero(); 3];
+    let four = [Point::zero(); 4];
+    assert!(interpolate_vertices(&three, &four, 1, 2).is_none());
+    let too_many = [Point::zero(); MAX_MORPH_VERTICES + 1];
+    assert!(interpolate_vertices(&too_many, &too_many, 1, 2).is_none());
+}
+
+#[test]
+fn translation_moves_every_vertex() {
+    let poly = [Point::new(0, 0), Point::new(4, 0), Point::new(2, 3)];
+    let moved = translate_vertices(&poly, Point::new(-1, 2)).unwrap();
+    assert_eq!(moved.as_slice(), &[Point::new(-1, 2), Point::new(3, 2), Point::new(1, 5)]);
+    assert!(translate_vertices(&[Point::zero(); MAX_MORPH_VERTICES + 1], Point::zero()).is_none());
+}
+
+#[test]
+fn fraction_conversions_clamp_and_round() {
+    assert_eq!(MorphFraction::from_ratio(0, 7), MorphFraction::START);
+    assert_eq!(MorphFraction::from_ratio(7, 7), MorphFraction::END);
+    assert_eq!(MorphFraction::from_ratio(9, 7), MorphFraction::END);
+    assert_eq!(MorphFraction::from_f32(0.5), MorphFraction::HALF);
+    assert_eq!(MorphFraction::from_f32(-1.0), MorphFraction::START);
+    assert_eq!(MorphFraction::from_f32(f32::NAN), MorphFraction::START);
+    assert_eq!(MorphFraction::from_f32(2.0), MorphFraction::END);
+    assert_eq!(MorphFraction::from_raw(u16::MAX), MorphFraction::END);
+    assert_eq!(MorphFraction::HALF.to_f32(), 0.5);
+    assert_eq!(MorphFraction::from_ratio(1, 4).inverse(), MorphFraction::from_ratio(3, 4));
+}
+
+#[test]
+fn fractions_locate_evenly_spaced_steps() {
+    assert_eq!(MorphFraction::from_ratio(2, 3).exact_step(3), Some(2));
+    assert_eq!(MorphFraction::from_ratio(1, 2).exact_step(3), None);
+    assert_eq!(MorphFraction::END.exact_step(3), Some(3));
+
+    assert_eq!(MorphFraction::from_ratio(1, 2).between_steps(3), (1, 2, MorphFraction::HALF));
+    assert_eq!(MorphFraction::START.between_steps(3), (0, 1, MorphFraction::START));
+    assert_eq!(MorphFraction::END.between_steps(3), (2, 3, MorphFraction::END));
+}
+
+#[test]
+fn morph_follows_continuous_fraction() {
+    let start = [Point::new(0, 100), Point::new(40, 0)];
+    let end = [Point::new(100, 0), Point::new(40, 80)];
+    let quarter = morph_vertices(&start, &end, MorphFraction::from_f32(0.25)).unwrap();
+    assert_eq!(quarter.as_slice(), &[Point::new(25, 75), Point::new(40, 20)]);
+    assert_eq!(morph_vertices(&start, &end, MorphFraction::END).unwrap().as_slice(), &end);
+}
@@ -0,0 +1,50 @@
+use eyemodelz::*;
+
+#[test]
+fn center_asset_has_no_step_tag() {
+    for step in 0..NUM_LOOK_STEPS {
+        assert_eq!(stepped_asset_name("iris", GazeDirection::StraightAhead, step).as_str(), "iris_11");
+    }
+}
+
+#[test]
+fn transition_endpoints_use_plain_grid_digits() {
+    assert_eq!(stepped_asset_name("pupil", GazeDirection::NorthWest, 0).as_str(), "pupil_11");
+    assert_eq!(stepped_asset_name("pupil", GazeDirection::NorthWest, LAST_LOOK_STEP_IDX).as_str(), "pupil_00");
+}
+
+#[test]
+fn tween_steps_are_tagged_with_start_and_end() {
+    assert_eq!(stepped_asset_name("iris", GazeDirection::SouthEast, 1).as_str(), "iris_11_0_22");
+    assert_eq!(stepped_asset_name("iris", GazeDirection::SouthEast, 2).as_str(), "iris_11_1_22");
+    assert_eq!(
+        stepped_asset_name_full("upper_lid_bulge", GazeDirection::West, GazeDirection::North, 2).as_str(),
+        "upper_lid_bulge_10_1_01");
+}
+
+#[test]
+fn longest_prefix_fits() {
+    // the longest prefix used by the renderer, with a full tween tag
+    let name = stepped_asset_name("iris_shadow_top", GazeDirection::SouthWest, 1);
+    assert_eq!(name.as_str(), "iris_shadow_top_11_0_20");
+}
+
+#[test]
+fn neutral_assets_have_no_emotion_key() {
+    assert_eq!(emotion_asset_prefix("eyebrow", EmotionExpression::Neutral).as_str(), "eyebrow");
+    assert_eq!(emotion_asset_prefix("eyebrow", EmotionExpression::Happy).as_str(), "eyebrow_happy");
+}
+
+#[test]
+fn longest_emotion_prefix_fits() {
+    let prefix = emotion_asset_prefix("upper_lid_shadow", EmotionExpression::Trepidation);
+    let name = stepped_asset_name_full(&prefix, GazeDirection::StraightAhead, GazeDirection::North, 2);
+    assert_eq!(name.as_str(), "upper_lid_shadow_trepidation_11_1_01");
+}
+
+#[test]
+fn emotion_asset_keys_are_lowercase_names() {
+    for idx in 1..EmotionExpression::MaxCount as usize {
+        assert_eq!(EmotionExpression::ASSET_KEYS[idx], EmotionExpression::NAMES[idx].to_lowercase());
+    }
+}
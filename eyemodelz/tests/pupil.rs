@@ -0,0 +1,88 @@
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use eyemodelz::pupil::*;
+use eyemodelz::EmotionExpression;
+
+#[test]
+fn sizes_are_kept_in_range() {
+    assert_eq!(PupilSize::from_pct(0), PupilSize::MIN);
+    assert_eq!(PupilSize::from_pct(255), PupilSize::MAX);
+    assert_eq!(PupilSize::from_pct(120).pct(), 120);
+    assert_eq!(PupilSize::default(), PupilSize::AUTHORED);
+}
+
+#[test]
+fn target_follows_light_and_emotion() {
+    let neutral = EmotionExpression::Neutral;
+    assert_eq!(PupilSize::target(0, neutral).pct(), 125);
+    assert_eq!(PupilSize::target(100, neutral).pct(), 80);
+    assert!(PupilSize::target(30, neutral) > PupilSize::target(70, neutral));
+    assert!(PupilSize::target(50, EmotionExpression::Surprise) > PupilSize::target(50, neutral));
+    assert!(PupilSize::target(50, EmotionExpression::Love) > PupilSize::target(50, neutral));
+    assert_eq!(PupilSize::target(0, EmotionExpression::Surprise), PupilSize::MAX);
+}
+
+#[test]
+fn pupil_scales_about_the_iris_center() {
+    let center = Point::new(160, 160);
+    let pupil = [Point::new(132, 160), Point::new(160, 140), Point::new(188, 161)];
+    assert_eq!(PupilSize::AUTHORED.scale_about(&pupil, center).unwrap().as_slice(), &pupil);
+    let dilated = PupilSize::from_pct(150).scale_about(&pupil, center).unwrap();
+    assert_eq!(dilated.as_slice(), &[Point::new(118, 160), Point::new(160, 130), Point::new(202, 162)]);
+    let constricted = PupilSize::from_pct(60).scale_about(&pupil, center).unwrap();
+    assert_eq!(constricted.as_slice(), &[Point::new(143, 160), Point::new(160, 148), Point::new(177, 161)]);
+}
+
+#[test]
+fn glints_move_with_the_rim() {
+    let center = Point::new(160, 160);
+    let glint = Point::new(178, 151);
+    assert_eq!(PupilSize::AUTHORED.rim_shift(glint, center), Point::zero());
+    assert_eq!(PupilSize::from_pct(150).rim_shift(glint, center), Point::new(9, -5));
+    assert_eq!(PupilSize::from_pct(60).rim_shift(glint, center), Point::new(-7, 4));
+}
+
+#[test]
+fn glints_stay_within_the_iris() {
+    let iris = Rectangle::new(Point::new(92, 92), Size::new(135, 135));
+    let glint = Rectangle::new(Point::new(170, 140), Size::new(17, 23));
+    // with room to spare it's just the rim shift
+    let dilated = PupilSize::from_pct(150);
+    assert_eq!(dilated.glint_shift(glint, iris), dilated.rim_shift(glint.center(), iris.center()));
+    // looking aside, the iris is narrow and the glint near its edge
+    let narrow_iris = Rectangle::new(Point::new(182, 96), Size::new(91, 119));
+    let edge_glint = Rectangle::new(Point::new(238, 128), Size::new(27, 28));
+    assert_eq!(PupilSize::from_pct(150).glint_shift(edge_glint, narrow_iris), Point::new(8, -7));
+    assert_eq!(PupilSize::from_pct(60).glint_shift(edge_glint, narrow_iris).x, -10);
+    // one authored outside the iris stays where it is, rather than going further out
+    let outside = Rectangle::new(Point::new(268, 120), Size::new(10, 10));
+    assert_eq!(PupilSize::from_pct(150).glint_shift(outside, narrow_iris).x, 0);
+}
+
+#[test]
+fn constricts_faster_than_it_dilates() {
+    let start = PupilSize::AUTHORED;
+    let mut constricting = PupilAnimator::new(PupilTiming::HUMAN, start, 0);
+    let mut dilating = PupilAnimator::new(PupilTiming::HUMAN, start, 0);
+    let mut last = (start, start);
+    for tick in 1..=6u64 {
+        let sizes = (constricting.update(PupilSize::MIN, tick * 50), dilating.update(PupilSize::from_pct(140), tick * 50));
+        assert!(sizes.0 <= last.0 && sizes.1 >= last.1, "{:?} after {:?}", sizes, last);
+        last = sizes;
+    }
+    // after 300ms, most of the way down but well short of the way up
+    let constricted = start.pct() - last.0.pct();
+    let dilated = last.1.pct() - start.pct();
+    assert!(constricted > 20 && dilated < 15, "{:?}", last);
+}
+
+#[test]
+fn arrives_at_the_target() {
+    let mut animator = PupilAnimator::new(PupilTiming::HUMAN, PupilSize::from_pct(80), 0);
+    let target = PupilSize::from_pct(125);
+    let sizes: Vec<PupilSize> = (1..=200u64).map(|tick| animator.update(target, tick * 50)).collect();
+    assert!(sizes.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(animator.size(), target);
+    // and stays there
+    assert_eq!(animator.update(target, 20_000), target);
+}
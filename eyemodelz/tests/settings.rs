@@ -0,0 +1,261 @@
+//!
+//! Settings records, and the wear-leveled store, against an in-memory NOR flash.
+//!
+
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash};
+use eyemodelz::settings::*;
+
+const SECTOR_SIZE: usize = 4096;
+const SLOTS_PER_SECTOR: usize = SECTOR_SIZE / RECORD_SIZE;
+// Put the region somewhere other than the start of flash
+const REGION_OFFSET: u32 = SECTOR_SIZE as u32;
+
+/// NOR flash in memory: writes can only clear bits, erases set a whole sector back to 0xFF
+struct MockFlash {
+    bytes: Vec<u8>,
+    erase_counts: Vec<u32>,
+}
+
+impl MockFlash {
+    fn new(num_sectors: usize) -> Self {
+        Self { bytes: vec![0xFF; num_sectors * SECTOR_SIZE], erase_counts: vec![0; num_sectors] }
+    }
+}
+
+impl ErrorType for MockFlash {
+    type Error = NorFlashErrorKind;
+}
+
+impl ReadNorFlash for MockFlash {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let start = offset as usize;
+        let src = self.bytes.get(start..start + bytes.len()).ok_or(NorFlashErrorKind::OutOfBounds)?;
+        bytes.copy_from_slice(src);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl NorFlash for MockFlash {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from % SECTOR_SIZE != 0 || to % SECTOR_SIZE != 0 {
+            return Err(NorFlashErrorKind::NotAligned);
+        }
+        self.bytes.get_mut(from..to).ok_or(NorFlashErrorKind::OutOfBounds)?.fill(0xFF);
+        for sector in from / SECTOR_SIZE..to / SECTOR_SIZE {
+            self.erase_counts[sector] += 1;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let start = offset as usize;
+        let dest = self.bytes.get_mut(start..start + bytes.len()).ok_or(NorFlashErrorKind::OutOfBounds)?;
+        for (dest, src) in dest.iter_mut().zip(bytes) {
+            *dest &= *src;
+        }
+        Ok(())
+    }
+}
+
+fn custom_settings(mode_a: u8) -> Settings {
+    Settings {
+        mode_a,
+        iris_color: Some(Rgb565::new(31, 0, 31)),
+        skin_color: None,
+        brightness_pct: Some(40),
+        brightness_curve: BrightnessCurve { min_pct: 10, max_pct: 80, ramp_ms: 3000 },
+        behavior: BehaviorParams { min_fixation_ms: 400, ..BehaviorParams::DEFAULT },
+    }
+}
+
+/// A store over flash with one unused sector in front of `num_region_sectors` for settings
+fn new_store(num_region_sectors: usize) -> SettingsStore<MockFlash> {
+    let flash = MockFlash::new(1 + num_region_sectors);
+    SettingsStore::new(flash, REGION_OFFSET, (num_region_sectors * SECTOR_SIZE) as u32)
+}
+
+/// Drop the store and load a fresh one from the same flash, as after a reset
+fn reload(store: SettingsStore<MockFlash>, num_region_sectors: usize) -> (SettingsStore<MockFlash>, Option<Settings>) {
+    let mut store = SettingsStore::new(store.release(), REGION_OFFSET, (num_region_sectors * SECTOR_SIZE) as u32);
+    let settings = store.load().unwrap();
+    (store, settings)
+}
+
+#[test]
+fn records_round_trip() {
+    for settings in [Settings::defaults(6), custom_settings(3)] {
+        let record = encode_record(&settings, 1234);
+        assert_eq!(decode_record(&record), Ok((settings, 1234)));
+    }
+}
+
+#[test]
+fn bad_records_are_rejected() {
+    let record = encode_record(&custom_settings(2), 7);
+    assert_eq!(decode_record(&[0xFF; RECORD_SIZE]), Err(RecordError::Blank));
+
+    let mut corrupt = record;
+    corrupt[10] ^= 0x01;
+    assert_eq!(decode_record(&corrupt), Err(RecordError::BadCrc));
+
+    let mut bad_magic = record;
+    bad_magic[0] = 0;
+    assert_eq!(decode_record(&bad_magic), Err(RecordError::BadMagic));
+
+    // a record from some other format version, with a CRC that matches
+    let mut future = record;
+    future[2] = SETTINGS_VERSION + 1;
+    let crc = crc32_of(&future[..RECORD_SIZE - 4]);
+    future[RECORD_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+    assert_eq!(decode_record(&future), Err(RecordError::UnsupportedVersion(SETTINGS_VERSION + 1)));
+}
+
+/// Reference CRC-32 (IEEE), to forge records the store didn't write
+fn crc32_of(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn blank_flash_has_no_settings() {
+    let mut store = new_store(2);
+    assert_eq!(store.load(), Ok(None));
+}
+
+#[test]
+fn latest_save_survives_reset() {
+    let mut store = new_store(2);
+    store.load().unwrap();
+    for mode_a in 0..5 {
+        store.save(&custom_settings(mode_a)).unwrap();
+    }
+    let (_, settings) = reload(store, 2);
+    assert_eq!(settings, Some(custom_settings(4)));
+}
+
+#[test]
+fn saves_fill_every_slot_before_erasing() {
+    const NUM_SECTORS: usize = 2;
+    let mut store = new_store(NUM_SECTORS);
+    store.load().unwrap();
+    let num_slots = NUM_SECTORS * SLOTS_PER_SECTOR;
+    for idx in 0..num_slots {
+        store.save(&custom_settings(idx as u8)).unwrap();
+    }
+    let flash = store.release();
+    assert_eq!(flash.erase_counts, [0, 0, 0], "nothing erased until the region is full");
+
+    // wrapping around erases the oldest sector only, and the latest record survives resets
+    let mut store = SettingsStore::new(flash, REGION_OFFSET, (NUM_SECTORS * SECTOR_SIZE) as u32);
+    assert_eq!(store.load().unwrap(), Some(custom_settings((num_slots - 1) as u8)));
+    store.save(&custom_settings(200)).unwrap();
+    let (mut store, settings) = reload(store, NUM_SECTORS);
+    assert_eq!(settings, Some(custom_settings(200)));
+
+    // keep going for a while: erases stay even across the region
+    for idx in 0..(10 * num_slots) {
+        store.save(&custom_settings(idx as u8)).unwrap();
+    }
+    let flash = store.release();
+    let region_erases = &flash.erase_counts[1..];
+    assert!(region_erases.iter().max().unwrap() - region_erases.iter().min().unwrap() <= 1, "{:?}", region_erases);
+    assert!(region_erases.iter().all(|count| *count >= 10), "{:?}", region_erases);
+}
+
+#[test]
+fn torn_write_falls_back_to_previous_record() {
+    let mut store = new_store(2);
+    store.load().unwrap();
+    store.save(&custom_settings(1)).unwrap();
+    store.save(&custom_settings(2)).unwrap();
+    let mut flash = store.release();
+
+    // a reset partway through writing the third record leaves only some of its bytes
+    let torn = encode_record(&custom_settings(3), 2);
+    let third_slot = REGION_OFFSET + 2 * RECORD_SIZE as u32;
+    flash.write(third_slot, &torn[..RECORD_SIZE / 2]).unwrap();
+
+    let mut store = SettingsStore::new(flash, REGION_OFFSET, 2 * SECTOR_SIZE as u32);
+    assert_eq!(store.load().unwrap(), Some(custom_settings(2)));
+
+    // the next save skips past the torn slot rather than writing over it
+    store.save(&custom_settings(4)).unwrap();
+    let (_, settings) = reload(store, 2);
+    assert_eq!(settings, Some(custom_settings(4)));
+}
+
+#[test]
+fn erase_without_write_keeps_the_latest_record() {
+    const NUM_SECTORS: usize = 2;
+    let mut store = new_store(NUM_SECTORS);
+    store.load().unwrap();
+    let num_slots = NUM_SECTORS * SLOTS_PER_SECTOR;
+    for idx in 0..num_slots {
+        store.save(&custom_settings(idx as u8)).unwrap();
+    }
+    let mut flash = store.release();
+
+    // the next save wraps around and erases the first sector, then a reset comes before the write
+    flash.erase(REGION_OFFSET, REGION_OFFSET + SECTOR_SIZE as u32).unwrap();
+    let mut store = SettingsStore::new(flash, REGION_OFFSET, (NUM_SECTORS * SECTOR_SIZE) as u32);
+    assert_eq!(store.load().unwrap(), Some(custom_settings((num_slots - 1) as u8)));
+
+    // and saving carries on into the erased sector
+    store.save(&custom_settings(200)).unwrap();
+    let (store, settings) = reload(store, NUM_SECTORS);
+    assert_eq!(settings, Some(custom_settings(200)));
+    assert_eq!(store.release().erase_counts, [0, 1, 0]);
+}
+
+#[test]
+#[should_panic(expected = "at least two sectors")]
+fn region_must_be_at_least_two_sectors() {
+    new_store(1);
+}
+
+#[test]
+fn out_of_range_settings_are_invalid() {
+    assert!(Settings::defaults(0).is_valid(1));
+    assert!(custom_settings(3).is_valid(4));
+    assert!(!custom_settings(4).is_valid(4));
+
+    let base = custom_settings(3);
+    let invalid = [
+        Settings { brightness_pct: Some(101), ..base },
+        Settings { brightness_curve: BrightnessCurve { min_pct: 60, max_pct: 50, ..base.brightness_curve }, ..base },
+        Settings { brightness_curve: BrightnessCurve { max_pct: 120, ..base.brightness_curve }, ..base },
+        Settings { behavior: BehaviorParams { min_blink_interval_ms: 5000, max_blink_interval_ms: 4000, ..base.behavior }, ..base },
+        Settings { behavior: BehaviorParams { min_fixation_ms: 900, max_fixation_ms: 800, ..base.behavior }, ..base },
+    ];
+    for settings in invalid {
+        assert!(!settings.is_valid(4), "{:?}", settings);
+        // they're stored as they are, it's up to the loader to reject them
+        assert_eq!(decode_record(&encode_record(&settings, 1)), Ok((settings, 1)));
+    }
+}
+
+#[test]
+fn brightness_curve_steps() {
+    let curve = BrightnessCurve::DEFAULT;
+    assert_eq!(curve.step_pct(50), 1);
+    assert_eq!(curve.step_pct(500), 9);
+    let flat = BrightnessCurve { min_pct: 50, max_pct: 50, ramp_ms: 0 };
+    assert_eq!(flat.step_pct(50), 1);
+}
@@ -0,0 +1,137 @@
+use core::convert::Infallible;
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::StrokeAlignment;
+use eyemodelz::thick_stroke::*;
+
+/// Pixels from -OFFSET to GRID - OFFSET on each axis, so outlines can spill past the origin
+const GRID: usize = 64;
+const OFFSET: i32 = 20;
+
+struct Grid {
+    pixels: [[bool; GRID]; GRID],
+}
+
+impl Grid {
+    fn drawn(vertices: &[Point], stroke_width: u32, alignment: StrokeAlignment) -> Self {
+        let mut grid = Grid { pixels: [[false; GRID]; GRID] };
+        draw_thick_outline(vertices, stroke_width, alignment, BinaryColor::On, &mut grid).unwrap();
+        grid
+    }
+
+    fn get(&self, x: i32, y: i32) -> bool {
+        self.pixels[(y + OFFSET) as usize][(x + OFFSET) as usize]
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..GRID).flat_map(move |row| (0..GRID).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.pixels[row][col])
+            .map(|(row, col)| Point::new(col as i32 - OFFSET, row as i32 - OFFSET))
+    }
+}
+
+impl OriginDimensions for Grid {
+    fn size(&self) -> Size {
+        Size::new(GRID as u32, GRID as u32)
+    }
+}
+
+impl DrawTarget for Grid {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I: IntoIterator<Item = Pixel<BinaryColor>>>(&mut self, pixels: I) -> Result<(), Infallible> {
+        for Pixel(point, _) in pixels {
+            let (col, row) = (point.x + OFFSET, point.y + OFFSET);
+            assert!((0..GRID as i32).contains(&col) && (0..GRID as i32).contains(&row), "{:?} off the grid", point);
+            self.pixels[row as usize][col as usize] = true;
+        }
+        Ok(())
+    }
+}
+
+/// A square whose fill covers pixels 0..20 on each axis
+const SQUARE: [Point; 4] = [Point::new(0, 0), Point::new(20, 0), Point::new(20, 20), Point::new(0, 20)];
+
+fn in_square_fill(point: Point) -> bool {
+    (0..20).contains(&point.x) && (0..20).contains(&point.y)
+}
+
+#[test]
+fn nothing_to_draw() {
+    assert_eq!(Grid::drawn(&[], 3, StrokeAlignment::Center).points().count(), 0);
+    assert_eq!(Grid::drawn(&SQUARE, 0, StrokeAlignment::Center).points().count(), 0);
+}
+
+#[test]
+fn inside_outline_stays_in_the_fill() {
+    for width in 1..=5 {
+        let grid = Grid::drawn(&SQUARE, width, StrokeAlignment::Inside);
+        assert!(grid.points().all(in_square_fill), "width {}", width);
+        let hole = 20 - 2 * width as usize;
+        assert_eq!(grid.points().count(), 400 - hole * hole, "width {}", width);
+    }
+}
+
+#[test]
+fn outside_outline_never_touches_the_fill() {
+    for width in 1..=5 {
+        let grid = Grid::drawn(&SQUARE, width, StrokeAlignment::Outside);
+        let width = width as i32;
+        assert!(!grid.points().any(in_square_fill), "width {}", width);
+        for (x, y) in [(-width, 10), (19 + width, 10), (10, -width), (10, 19 + width), (-1, -1), (20, 20)] {
+            assert!(grid.get(x, y), "width {} missing {},{}", width, x, y);
+        }
+        for (x, y) in [(-width - 1, 10), (20 + width, 10), (10, -width - 1), (10, 20 + width)] {
+            assert!(!grid.get(x, y), "width {} spilled to {},{}", width, x, y);
+        }
+    }
+}
+
+#[test]
+fn center_outline_is_as_wide_as_asked() {
+    for width in 1..=6 {
+        let grid = Grid::drawn(&SQUARE, width, StrokeAlignment::Center);
+        // across each side, midway along it
+        let left = (-OFFSET..10).filter(|&x| grid.get(x, 10)).count();
+        let right = (10..GRID as i32 - OFFSET).filter(|&x| grid.get(x, 10)).count();
+        let top = (-OFFSET..10).filter(|&y| grid.get(10, y)).count();
+        assert_eq!((left, right, top), (width as usize, width as usize, width as usize), "width {}", width);
+    }
+}
+
+#[test]
+fn slanted_center_outlines_are_symmetric() {
+    // a diamond is its own mirror image, so its outline should be too: about its middle column
+    // when centered on the pixels, or half a pixel left of it when following the fill's edge
+    let diamond = [Point::new(10, -10), Point::new(30, 10), Point::new(10, 30), Point::new(-10, 10)];
+    for (width, mirror_x) in [(3, 20), (4, 19)] {
+        let grid = Grid::drawn(&diamond, width, StrokeAlignment::Center);
+        assert!(grid.points().count() > 0);
+        for point in grid.points() {
+            assert!(grid.get(mirror_x - point.x, point.y), "width {} not mirrored at {:?}", width, point);
+        }
+    }
+}
+
+#[test]
+fn inside_and_outside_split_a_center_outline_twice_as_wide() {
+    let shapes: [&[Point]; 3] = [
+        &[Point::new(10, -10), Point::new(30, 10), Point::new(10, 30), Point::new(-10, 10)],
+        &[Point::new(-5, 0), Point::new(35, 7), Point::new(12, 33)],
+        &[Point::new(0, 0), Point::new(30, 2), Point::new(14, 12), Point::new(28, 28), Point::new(-3, 25)],
+    ];
+    for vertices in shapes {
+        for width in 1..=4 {
+            let inside = Grid::drawn(vertices, width, StrokeAlignment::Inside);
+            let outside = Grid::drawn(vertices, width, StrokeAlignment::Outside);
+            let center = Grid::drawn(vertices, 2 * width, StrokeAlignment::Center);
+            assert!(inside.points().count() > 0 && outside.points().count() > 0);
+            assert!(!inside.points().any(|point| outside.get(point.x, point.y)), "width {} overlaps", width);
+            let union = inside.points().count() + outside.points().count();
+            assert_eq!(union, center.points().count(), "width {}", width);
+            assert!(inside.points().chain(outside.points()).all(|point| center.get(point.x, point.y)));
+        }
+    }
+}
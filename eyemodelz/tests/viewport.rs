@@ -0,0 +1,50 @@
+use embedded_graphics::prelude::{Point, Size};
+use eyemodelz::viewport::*;
+
+const ART: Size = Size::new(320, 240);
+const ROUND_PANEL: Size = Size::new(240, 240);
+
+#[test]
+fn art_sized_panel_is_identity() {
+    let viewport = Viewport::new(ART, ART);
+    assert!(viewport.is_identity());
+    for point in [Point::new(0, 0), Point::new(137, 91), Point::new(319, 239), Point::new(-5, 250)] {
+        assert_eq!(viewport.to_panel(point), point);
+    }
+    let pixels: Vec<u32> = ViewportPixels::new(viewport, 0..ART.width * ART.height).collect();
+    assert_eq!(pixels, (0..ART.width * ART.height).collect::<Vec<_>>());
+}
+
+#[test]
+fn round_panel_fits_the_art_width() {
+    let viewport = Viewport::new(ART, ROUND_PANEL);
+    assert!(!viewport.is_identity());
+    // scaled by 3/4, with 30 rows above and below
+    assert_eq!(viewport.to_panel(Point::new(0, 0)), Point::new(0, 30));
+    assert_eq!(viewport.to_panel(Point::new(160, 120)), Point::new(120, 120));
+    assert_eq!(viewport.to_panel(Point::new(320, 240)), Point::new(240, 210));
+    assert_eq!(viewport.to_panel(Point::new(26, 150)), Point::new(20, 143));
+    let vertices = [Point::new(0, 0), Point::new(320, 240)];
+    assert_eq!(viewport.to_panel_vertices(&vertices).unwrap().as_slice(), &[Point::new(0, 30), Point::new(240, 210)]);
+}
+
+#[test]
+fn round_panel_stretches_the_edge_rows() {
+    let viewport = Viewport::new(ART, ROUND_PANEL);
+    assert_eq!((viewport.art_row(0), viewport.art_row(29)), (0, 0));
+    assert_eq!((viewport.art_row(30), viewport.art_row(31), viewport.art_row(32)), (0, 2, 3));
+    assert_eq!((viewport.art_row(209), viewport.art_row(210), viewport.art_row(239)), (239, 239, 239));
+    assert_eq!((viewport.art_column(0), viewport.art_column(120), viewport.art_column(239)), (0, 160, 319));
+}
+
+#[test]
+fn round_panel_pixels_come_from_the_nearest_art_pixel() {
+    let viewport = Viewport::new(ART, ROUND_PANEL);
+    // each art pixel is its own index
+    let pixels: Vec<u32> = ViewportPixels::new(viewport, 0..ART.width * ART.height).collect();
+    assert_eq!(pixels.len(), (ROUND_PANEL.width * ROUND_PANEL.height) as usize);
+    for (idx, &pixel) in pixels.iter().enumerate() {
+        let (x, y) = ((idx as u32 % ROUND_PANEL.width) as i32, (idx as u32 / ROUND_PANEL.width) as i32);
+        assert_eq!(pixel, viewport.art_row(y) * ART.width + viewport.art_column(x), "at {},{}", x, y);
+    }
+}
@@ -0,0 +1,70 @@
+//!
+//! Compare drawing thick polygon outlines with embedded-graphics, both as `ClosedPolygon` strokes them
+//! and as a closed `Polyline` (which joins its segments), against the scanline rasterizer
+//! in `eyemodelz::thick_stroke`.
+//!
+//! Run with `cargo bench --bench stroke`.
+//!
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Polyline, PrimitiveStyleBuilder, StrokeAlignment};
+use lcd_async::raw_framebuf::RawFrameBuf;
+
+use closed_svg_path::ClosedPolygon;
+use eyemodelz::thick_stroke::draw_thick_outline;
+use eyesim::eyerender::*;
+use eyesim::new_frame_buf;
+
+const RUNS: u32 = 200;
+
+/// Average microseconds per call of `draw`
+fn time_micros(mut draw: impl FnMut()) -> f64 {
+    draw(); // warm up
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        draw();
+    }
+    start.elapsed().as_secs_f64() * 1e6 / RUNS as f64
+}
+
+fn main() {
+    let mut frame_buf = new_frame_buf();
+    let mut raw_fb = RawFrameBuf::<Rgb565, &mut [u8]>::new(frame_buf.as_mut_slice(),
+        DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize);
+
+    println!("{:<20} {:>5} {:>8} {:>12} {:>12} {:>12} {:>8} {:>8}", "shape", "width", "align",
+        "polygon µs", "polyline µs", "scanline µs", "vs poly", "vs line");
+    for path_id in ["upper_lid_shine_11", "iris_11", "lower_lid_bulge_11"] {
+        let Some(cpoly) = get_svg_path_by_id(SvgFileId::EyeLeft, path_id) else {
+            println!("{:<20} missing", path_id);
+            continue;
+        };
+        let vertices = cpoly.vertices();
+        let closed: Vec<Point> = vertices.iter().chain(vertices.first()).copied().collect();
+        for width in 1..=4 {
+            for alignment in [StrokeAlignment::Center, StrokeAlignment::Inside, StrokeAlignment::Outside] {
+                let style = PrimitiveStyleBuilder::new()
+                    .stroke_color(Rgb565::WHITE)
+                    .stroke_width(width)
+                    .stroke_alignment(alignment)
+                    .build();
+                let polygon_micros = time_micros(|| {
+                    let _ = black_box(ClosedPolygon::new(vertices).into_styled(style).draw(&mut raw_fb));
+                });
+                let polyline_micros = time_micros(|| {
+                    let _ = black_box(Polyline::new(&closed).into_styled(style).draw(&mut raw_fb));
+                });
+                let scanline_micros = time_micros(|| {
+                    let _ = black_box(draw_thick_outline(vertices, width, alignment, Rgb565::WHITE, &mut raw_fb));
+                });
+                println!("{:<20} {:>5} {:>8} {:>12.1} {:>12.1} {:>12.1} {:>7.1}x {:>7.1}x", path_id, width,
+                    format!("{:?}", alignment), polygon_micros, polyline_micros, scanline_micros,
+                    polygon_micros / scanline_micros, polyline_micros / scanline_micros);
+            }
+        }
+    }
+}
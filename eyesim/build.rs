@@ -0,0 +1,32 @@
+//! The eye assets live in the firmware crate's `img` directory
+
+use std::path::{Path, PathBuf};
+
+#[path = "../build_support/morph_check.rs"]
+mod morph_check;
+#[path = "../build_support/bg_assets.rs"]
+mod bg_assets;
+// just the generated buffer sizes, since the host has RAM to spare
+#[allow(dead_code)]
+#[path = "../build_support/ram_budget.rs"]
+mod ram_budget;
+
+fn main() {
+    // SVG files need special handling because of the proc_macro
+    println!("cargo:rerun-if-changed=../img/eyestack-left-gen.svg");
+    println!("cargo:rerun-if-changed=../img/eyestack-right-gen.svg");
+    morph_check::check_morph_pairs_or_fail(&[
+        Path::new("../img/eyestack-left-gen.svg"),
+        Path::new("../img/eyestack-right-gen.svg"),
+    ]);
+    println!("cargo:rerun-if-changed=../build_support/morph_check.rs");
+
+    let out = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    bg_assets::generate_or_fail(Path::new("../img/eye_backgrounds.txt"), &out);
+    println!("cargo:rerun-if-changed=../build_support/bg_assets.rs");
+
+    ram_budget::generate(&out);
+    println!("cargo:rerun-if-changed=../build_support/ram_budget.rs");
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,92 @@
+//!
+//! Conversion of RGB565 frame buffers into image files
+//!
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::eyerender::{FullFrameBuf, DISPLAY_HEIGHT, DISPLAY_WIDTH, PIXEL_SIZE};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+/// Read one RGB565 pixel from the frame buffer.
+/// `RawFrameBuf` stores pixels big-endian, the order the display expects on the wire.
+pub fn rgb565_at(frame_buf: &[u8], idx: usize) -> u16 {
+    let offset = idx * PIXEL_SIZE as usize;
+    u16::from_be_bytes([frame_buf[offset], frame_buf[offset + 1]])
+}
+
+/// Expand an RGB565 pixel to RGB888, replicating high bits so that full-scale stays full-scale
+pub fn rgb565_to_rgb888(pixel: u16) -> [u8; 3] {
+    let r5 = ((pixel >> 11) & 0x1f) as u8;
+    let g6 = ((pixel >> 5) & 0x3f) as u8;
+    let b5 = (pixel & 0x1f) as u8;
+    [(r5 << 3) | (r5 >> 2), (g6 << 2) | (g6 >> 4), (b5 << 3) | (b5 >> 2)]
+}
+
+/// Convert a whole frame buffer into packed RGB888 rows
+pub fn frame_to_rgb888(frame_buf: &FullFrameBuf) -> Vec<u8> {
+    let num_pixels = DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize;
+    let mut rgb = Vec::with_capacity(num_pixels * 3);
+    for idx in 0..num_pixels {
+        rgb.extend_from_slice(&rgb565_to_rgb888(rgb565_at(frame_buf, idx)));
+    }
+    rgb
+}
+
+/// Write packed RGB888 rows as an image file
+pub fn write_rgb888(path: &Path, format: ImageFormat, width: u32, height: u32, rgb: &[u8]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Png => {
+            let mut encoder = png::Encoder::new(writer, width, height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut png_writer = encoder.write_header().map_err(io::Error::other)?;
+            png_writer.write_image_data(rgb).map_err(io::Error::other)?;
+        }
+        ImageFormat::Ppm => {
+            write!(writer, "P6\n{} {}\n255\n", width, height)?;
+            writer.write_all(rgb)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a full frame buffer as an image file
+pub fn write_frame(path: &Path, format: ImageFormat, frame_buf: &FullFrameBuf) -> io::Result<()> {
+    write_rgb888(path, format, DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32, &frame_to_rgb888(frame_buf))
+}
+
+/// Pack an RGB888 pixel back into RGB565.
+/// This is the exact inverse of `rgb565_to_rgb888`, so RGB565 frames round-trip losslessly through PNG.
+pub fn rgb888_to_rgb565(rgb: &[u8]) -> u16 {
+    ((rgb[0] as u16 >> 3) << 11) | ((rgb[1] as u16 >> 2) << 5) | (rgb[2] as u16 >> 3)
+}
+
+/// Read an 8-bit RGB PNG, as written by `write_rgb888`, returning (width, height, packed rows)
+pub fn read_png_rgb888(path: &Path) -> io::Result<(u32, u32, Vec<u8>)> {
+    let decoder = png::Decoder::new(std::io::BufReader::new(File::open(path)?));
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut rgb = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgb).map_err(io::Error::other)?;
+    if info.color_type != png::ColorType::Rgb || info.bit_depth != png::BitDepth::Eight {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "expected 8-bit RGB png"));
+    }
+    rgb.truncate(info.buffer_size());
+    Ok((info.width, info.height, rgb))
+}
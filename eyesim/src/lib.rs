@@ -0,0 +1,157 @@
+//!
+//! Host-side eye renderer.
+//!
+//! This reuses the firmware's `eyerender` module verbatim,
+//! so that frames rendered here match what the displays show.
+//!
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::Point;
+use embedded_graphics::primitives::Rectangle;
+
+use closed_svg_path_proc::import_svg_paths;
+
+#[allow(dead_code)]
+#[path = "../../src/eyerender/mod.rs"]
+pub mod eyerender;
+
+pub mod image_out;
+
+use eyemodelz::*;
+use eyemodelz::dirty_rect::bands;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyemodelz::pupil::PupilSize;
+use crate::eyerender::*;
+
+/// Wraps a log argument so that defmt-style `{}` placeholders can be printed with `Debug`
+pub struct LogArg<'a, T: core::fmt::Debug>(pub &'a T);
+
+impl<T: core::fmt::Debug> core::fmt::Display for LogArg<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// Logging is noisy during batch renders, so it's only enabled by setting `EYESIM_LOG`
+pub fn log_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var_os("EYESIM_LOG").is_some())
+}
+
+macro_rules! log_info {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {{
+        if $crate::log_enabled() {
+            std::eprintln!(concat!("INFO  ", $fmt) $(, $crate::LogArg(&$arg))*);
+        }
+    }};
+}
+pub(crate) use log_info as info;
+
+macro_rules! log_warn {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {{
+        std::eprintln!(concat!("WARN  ", $fmt) $(, $crate::LogArg(&$arg))*);
+    }};
+}
+pub(crate) use log_warn as warn;
+
+/// Timestamp source for rendering benchmarks
+pub fn now_micros() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_micros() as u64
+}
+
+import_svg_paths!(EyeLeft, "../img/eyestack-left-gen.svg");
+import_svg_paths!(EyeRight, "../img/eyestack-right-gen.svg");
+
+/// Everything needed to render a single frame for one eye
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EyeFrameParams {
+    pub is_left: bool,
+    pub gaze: GazeVector,
+    /// The emotion, or a change from one emotion to another
+    pub emotion: EmotionBlend,
+    /// How far the lids are closed by a blink
+    pub lid_closure: MorphFraction,
+    /// How far the pupil is dilated or constricted
+    pub pupil_size: PupilSize,
+    pub iris_color: Rgb565,
+    pub skin_color: Rgb565,
+}
+
+impl EyeFrameParams {
+    /// A steady Neutral eye with open lids, the pupil as drawn, and a blue iris on green skin.
+    /// Change the rest with struct update syntax.
+    pub fn neutral(is_left: bool, gaze: GazeVector) -> Self {
+        Self {
+            is_left,
+            gaze,
+            emotion: EmotionBlend::steady(EmotionExpression::Neutral),
+            lid_closure: MorphFraction::START,
+            pupil_size: PupilSize::AUTHORED,
+            iris_color: hex_to_rgb565(0x405D80),
+            skin_color: Rgb565::new(17, 45, 9),
+        }
+    }
+}
+
+/// Allocate a zeroed frame buffer on the heap (it's too big to pass around on the stack)
+pub fn new_frame_buf() -> Box<FullFrameBuf> {
+    vec![0u8; FRAME_SIZE_BYTES].into_boxed_slice().try_into().unwrap()
+}
+
+/// Render a complete frame for one eye, in the same layer order as the firmware redraw loop
+pub fn render_eye_frame(params: &EyeFrameParams, frame_buf: &mut FullFrameBuf) {
+    render_eye_frame_with_cache(params, &mut LayerCache::new(), frame_buf);
+}
+
+/// Render a complete frame for one eye, with static layers from (and cached into) `layers`,
+/// as the firmware keeps them from one frame to the next
+pub fn render_eye_frame_with_cache(params: &EyeFrameParams, layers: &mut LayerCache, frame_buf: &mut FullFrameBuf) {
+    render_full_frame(params, layers, ANTI_ALIASED_LAYERS, frame_buf);
+}
+
+/// Render a complete frame for one eye with anti-aliased fills on just the `anti_aliased` layers,
+/// rather than the firmware's `ANTI_ALIASED_LAYERS`
+pub fn render_eye_frame_anti_aliased(params: &EyeFrameParams, anti_aliased: EyeLayers, frame_buf: &mut FullFrameBuf) {
+    render_full_frame(params, &mut LayerCache::new(), anti_aliased, frame_buf);
+}
+
+fn render_full_frame(params: &EyeFrameParams, layers: &mut LayerCache, anti_aliased: EyeLayers,
+    frame_buf: &mut FullFrameBuf)
+{
+    let backgrounds = EmotionBackgrounds::for_blend(params.emotion, params.is_left);
+    let mut frame = FrameRows::full(frame_buf);
+    frame.anti_aliased = anti_aliased;
+
+    render_background_layer(params.is_left, &backgrounds, params.gaze.nearest_direction(), params.emotion,
+        params.skin_color, &mut frame);
+    render_eyeball_layers(params.is_left, params.gaze, params.emotion, params.lid_closure,
+        params.pupil_size, params.iris_color, params.skin_color, layers, &mut frame);
+}
+
+/// Render a complete frame for one eye a band at a time through a strip of `strip_bytes`,
+/// as the firmware does with the `band-render` feature, copying each band into `frame_buf`
+pub fn render_eye_frame_in_bands(params: &EyeFrameParams, strip_bytes: usize, frame_buf: &mut FullFrameBuf) {
+    let backgrounds = EmotionBackgrounds::for_blend(params.emotion, params.is_left);
+    let mut layers = LayerCache::new();
+    let mut renderer = band_renderer(params.is_left, &backgrounds, params.gaze, params.emotion,
+        params.lid_closure, params.pupil_size, params.iris_color, params.skin_color, &mut layers);
+    let mut strip = vec![0u8; strip_bytes];
+    for band in bands(Rectangle::new(Point::zero(), FRAME_SIZE), strip_bytes) {
+        let len = renderer.render_band(&mut strip, band);
+        let start = band.top_left.y as usize * DISPLAY_WIDTH as usize * PIXEL_SIZE as usize;
+        frame_buf[start..start + len].copy_from_slice(&strip[..len]);
+    }
+}
+
+/// Redraw just the eyeball layers over the previous frame, as the firmware does when only
+/// the gaze, lids or iris color changed, returning the region that needs sending to the display
+pub fn render_eyeball_update(params: &EyeFrameParams, frame_buf: &mut FullFrameBuf) -> Option<Rectangle> {
+    let mut frame = FrameRows::full(frame_buf);
+    render_eyeball_layers(params.is_left, params.gaze, params.emotion, params.lid_closure,
+        params.pupil_size, params.iris_color, params.skin_color, &mut LayerCache::new(), &mut frame);
+    frame.dirty.take(FRAME_SIZE)
+}
@@ -0,0 +1,370 @@
+//!
+//! Render left and right eye frames to image files, without any hardware attached.
+//!
+//! Examples:
+//! - `eyesim --mode ClockStar --frames 56` renders the ClockStar gaze sequence
+//! - `eyesim --mode SlowRandMeander --frames 100` renders 5 seconds of saccades and fixations
+//! - `eyesim --gaze 22 --step all` renders every look step toward the southeast
+//! - `eyesim --from 00 --gaze 12` renders a direct transition from northwest to east
+//! - `eyesim --gaze 02 --divisions 12` renders a 12 step morph toward the northeast
+//! - `eyesim --gaze 10 --step 3 --blink WinkLeft` renders a left wink while looking west
+//! - `eyesim --vector -1,-0.58` renders a gaze about 30 degrees up from due west
+//! - `eyesim --gaze 11 --step 0 --emotion-from Neutral --emotion Happy` renders a change of emotion
+//!
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use eyemodelz::*;
+use eyemodelz::blink::{BlinkController, BlinkKind, BlinkTiming, LidClosure};
+use eyemodelz::emotion_blend::{EmotionBlend, EmotionBlender, DEFAULT_EMOTION_BLEND_MS};
+use eyemodelz::gaze_control::{GazeController, GazeTiming};
+use eyemodelz::pupil::PupilSize;
+use eyesim::eyerender::*;
+use eyesim::image_out::{write_frame, ImageFormat};
+use eyesim::{new_frame_buf, render_eye_frame, EyeFrameParams};
+
+const USAGE: &str = "\
+usage: eyesim [options]
+  --mode <name|index>   TestModeA providing colors and the gaze sequence (default: Meander)
+  --frames <count>      number of frames of the mode's gaze sequence (default: one full sweep)
+  --frame-ms <millis>   simulated time between frames, for modes driven by the gaze controller
+                        (Meander, SlowRandMeander, Randomize) (default: 50)
+  --gaze <dir|all>      render a fixed gaze instead of the mode sequence,
+                        as grid digits (00..22) or a name (NorthWest, StraightAhead, ...)
+  --step <idx|all>      look step for --gaze, 0..=divisions (default: all)
+  --divisions <count>   number of steps each --gaze move is divided into (default: 3, the authored steps)
+  --from <dir>          start direction of the --gaze transition (default: StraightAhead)
+  --vector <x,y>        render a single continuous gaze instead, each axis -1..1
+                        (x from west to east, y from north to south)
+  --emotion <name|index> override the mode's emotion
+  --emotion-from <name|index> render one change of emotion, from this one to --emotion, at 50 fps,
+                        over the first frame's gaze
+  --emotion-ms <millis> how long the change of emotion takes (default: 400)
+  --lid <0..1>          lid closure, from open (0) to shut (1) (default: 0)
+  --pupil <60..150>     pupil size, in percent of its authored size (default: 100)
+  --blink <kind>        render one blink (Single, Double, WinkLeft, WinkRight) at 50 fps,
+                        over the first frame's gaze
+  --format <png|ppm>    output image format (default: png)
+  --out <dir>           output directory (default: eyesim_out)";
+
+struct Options {
+    mode: TestModeA,
+    frames: usize,
+    frame_millis: u64,
+    gaze: Option<Vec<GazeDirection>>,
+    gaze_start: GazeDirection,
+    vector: Option<GazeVector>,
+    step: Option<u8>,
+    divisions: u8,
+    emotion: Option<EmotionExpression>,
+    emotion_from: Option<EmotionExpression>,
+    emotion_millis: u32,
+    lid: MorphFraction,
+    pupil_size: PupilSize,
+    blink: Option<BlinkKind>,
+    format: ImageFormat,
+    out_dir: PathBuf,
+}
+
+/// Match an enum value by (case-insensitive) debug name or by numeric index
+fn parse_enum_arg<T>(arg: &str, max_count: u8) -> Option<T>
+where T: TryFrom<u8> + core::fmt::Debug
+{
+    if let Ok(idx) = arg.parse::<u8>() {
+        return if idx < max_count { T::try_from(idx).ok() } else { None };
+    }
+    (0..max_count)
+        .filter_map(|idx| T::try_from(idx).ok())
+        .find(|val| format!("{:?}", val).eq_ignore_ascii_case(arg))
+}
+
+fn all_gaze_directions() -> Vec<GazeDirection> {
+    (0..GazeDirection::MaxCount as u8)
+        .filter_map(|idx| GazeDirection::try_from(idx).ok())
+        .collect()
+}
+
+fn parse_gaze_arg(arg: &str) -> Option<Vec<GazeDirection>> {
+    if arg == "all" {
+        return Some(all_gaze_directions());
+    }
+    if let Some(dir) = all_gaze_directions().into_iter().find(|dir| dir.to_digits() == arg) {
+        return Some(vec![dir]);
+    }
+    // note that bare digits are treated as grid coordinates above, not enum indices
+    parse_enum_arg::<GazeDirection>(arg, GazeDirection::MaxCount as u8).map(|dir| vec![dir])
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut opts = Options {
+        mode: TestModeA::Meander,
+        frames: GazeDirection::RT_STEPS_PER_ARM * GazeDirection::CARDINAL_H8_ORDER.len(),
+        frame_millis: 50,
+        gaze: None,
+        gaze_start: GazeDirection::StraightAhead,
+        vector: None,
+        step: None,
+        divisions: LAST_LOOK_STEP_IDX,
+        emotion: None,
+        emotion_from: None,
+        emotion_millis: DEFAULT_EMOTION_BLEND_MS,
+        lid: MorphFraction::START,
+        pupil_size: PupilSize::AUTHORED,
+        blink: None,
+        format: ImageFormat::Png,
+        out_dir: PathBuf::from("eyesim_out"),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(String::new());
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--mode" => {
+                opts.mode = parse_enum_arg(&value, TestModeA::MaxCount as u8)
+                    .ok_or_else(|| format!("unknown mode: {}", value))?;
+            }
+            "--frames" => {
+                opts.frames = value.parse().map_err(|_| format!("bad frame count: {}", value))?;
+            }
+            "--frame-ms" => {
+                opts.frame_millis = value.parse().ok().filter(|millis| *millis > 0)
+                    .ok_or_else(|| format!("bad frame interval: {}", value))?;
+            }
+            "--gaze" => {
+                opts.gaze = Some(parse_gaze_arg(&value).ok_or_else(|| format!("unknown gaze: {}", value))?);
+            }
+            "--from" => {
+                opts.gaze_start = match parse_gaze_arg(&value).as_deref() {
+                    Some([dir]) => *dir,
+                    _ => return Err(format!("unknown start gaze: {}", value)),
+                };
+            }
+            "--vector" => {
+                let axes: Vec<f32> = value.split(',').filter_map(|axis| axis.trim().parse().ok()).collect();
+                opts.vector = match axes.as_slice() {
+                    [x, y] => Some(GazeVector::from_f32(*x, *y)),
+                    _ => return Err(format!("bad gaze vector: {}", value)),
+                };
+            }
+            "--step" => {
+                opts.step = if value == "all" { None }
+                    else { Some(value.parse().map_err(|_| format!("bad look step: {}", value))?) };
+            }
+            "--divisions" => {
+                opts.divisions = value.parse().ok().filter(|count| *count > 0)
+                    .ok_or_else(|| format!("bad division count: {}", value))?;
+            }
+            "--emotion" => {
+                opts.emotion = Some(parse_enum_arg(&value, EmotionExpression::MaxCount as u8)
+                    .ok_or_else(|| format!("unknown emotion: {}", value))?);
+            }
+            "--emotion-from" => {
+                opts.emotion_from = Some(parse_enum_arg(&value, EmotionExpression::MaxCount as u8)
+                    .ok_or_else(|| format!("unknown emotion: {}", value))?);
+            }
+            "--emotion-ms" => {
+                opts.emotion_millis = value.parse().map_err(|_| format!("bad emotion duration: {}", value))?;
+            }
+            "--lid" => {
+                let closure: f32 = value.parse().map_err(|_| format!("bad lid closure: {}", value))?;
+                opts.lid = MorphFraction::from_f32(closure);
+            }
+            "--pupil" => {
+                opts.pupil_size = value.parse::<u8>().ok()
+                    .filter(|pct| (PupilSize::MIN.pct()..=PupilSize::MAX.pct()).contains(pct))
+                    .map(PupilSize::from_pct)
+                    .ok_or_else(|| format!("bad pupil size: {}", value))?;
+            }
+            "--blink" => {
+                let kinds = [BlinkKind::Single, BlinkKind::Double, BlinkKind::WinkLeft, BlinkKind::WinkRight];
+                opts.blink = Some(kinds.into_iter()
+                    .find(|kind| format!("{:?}", kind).eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("unknown blink: {}", value))?);
+            }
+            "--format" => {
+                opts.format = match value.as_str() {
+                    "png" => ImageFormat::Png,
+                    "ppm" => ImageFormat::Ppm,
+                    _ => return Err(format!("unknown format: {}", value)),
+                };
+            }
+            "--out" => {
+                opts.out_dir = PathBuf::from(value);
+            }
+            _ => return Err(format!("unknown option: {}", flag)),
+        }
+    }
+    if opts.step.is_some_and(|step| step > opts.divisions) {
+        return Err(format!("look step must be 0..={}", opts.divisions));
+    }
+    Ok(opts)
+}
+
+/// Lid closures for every frame of a single blink, sampled every `frame_millis`
+fn blink_closures(kind: BlinkKind, frame_millis: u64) -> Vec<LidClosure> {
+    let mut controller = BlinkController::new(BlinkTiming::HUMAN, 0, 0);
+    controller.set_spontaneous(false, 0);
+    controller.trigger(kind, 0);
+    let mut closures = Vec::new();
+    let mut now_ms = 0;
+    loop {
+        closures.push(controller.update(now_ms));
+        if !controller.is_busy() {
+            break;
+        }
+        now_ms += frame_millis;
+    }
+    closures
+}
+
+/// Emotion blends for every frame of a single change of emotion, sampled every `frame_millis`
+fn emotion_blends(from: EmotionExpression, to: EmotionExpression, duration_ms: u32, frame_millis: u64)
+    -> Vec<EmotionBlend>
+{
+    let mut blender = EmotionBlender::new(from, duration_ms);
+    let mut blends = Vec::new();
+    let mut now_ms = 0;
+    loop {
+        blends.push(blender.update(to, now_ms));
+        if !blender.is_busy(now_ms) {
+            break;
+        }
+        now_ms += frame_millis;
+    }
+    blends
+}
+
+/// The mode's gaze for each frame: from a gaze controller on a simulated clock
+/// (with a fixed seed, so runs are repeatable), or from the mode's counter-based sequence
+fn mode_gazes(mode: TestModeA, frames: usize, frame_millis: u64) -> Vec<GazeTransition> {
+    match mode.gaze_targets() {
+        Some(targets) => {
+            let mut controller = GazeController::new(GazeTiming::HUMAN, targets, 0, 0);
+            (0..frames).map(|counter| controller.update(counter as u64 * frame_millis)).collect()
+        }
+        None => (0..frames).map(|counter| mode.gaze_transition(counter)).collect(),
+    }
+}
+
+/// Fractions in file names are written in thousandths, eg 333 for one third of the way
+fn permille(fraction: f32) -> i32 {
+    (fraction * 1000.0).round() as i32
+}
+
+/// File name tag for a transition: start and end directions, then progress
+fn transition_tag(gaze: GazeTransition) -> String {
+    format!("{}_{}_{:04}", gaze.start.to_digits(), gaze.end.to_digits(), permille(gaze.progress.to_f32()))
+}
+
+/// File name tag for a continuous gaze: x and y, signed
+fn vector_tag(gaze: GazeVector) -> String {
+    let (x, y) = gaze.to_f32();
+    format!("v{:+05}_{:+05}", permille(x), permille(y))
+}
+
+/// A cheap, deterministic stand-in for the RoscRng used by the Randomize mode
+fn pseudo_rand_bytes(counter: usize) -> [u8; 3] {
+    let hash = (counter as u32).wrapping_mul(0x9E37_79B9).rotate_left(13);
+    let bytes = hash.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args() {
+        Ok(opts) => opts,
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("{}", msg);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Each frame is (counter, gaze, lid closure, any emotion blend), with a gaze tag for the file name
+    let steps: Vec<u8> = match opts.step {
+        Some(step) => vec![step],
+        None => (0..=opts.divisions).collect(),
+    };
+    let gazes: Vec<(String, GazeVector)> = match (&opts.vector, &opts.gaze) {
+        (Some(vector), _) => vec![(vector_tag(*vector), *vector)],
+        (None, Some(directions)) => directions.iter()
+            .flat_map(|dir| steps.iter().map(move |step| GazeTransition {
+                start: opts.gaze_start,
+                end: *dir,
+                progress: MorphFraction::from_ratio(*step as u32, opts.divisions as u32),
+            }))
+            .map(|gaze| (transition_tag(gaze), gaze.into()))
+            .collect(),
+        (None, None) => mode_gazes(opts.mode, opts.frames, opts.frame_millis).into_iter()
+            .map(|gaze| (transition_tag(gaze), gaze.into()))
+            .collect(),
+    };
+    let mut frames: Vec<(usize, String, GazeVector, LidClosure, Option<EmotionBlend>)> = gazes.into_iter()
+        .enumerate()
+        .map(|(counter, (tag, gaze))| (counter, tag, gaze, LidClosure { left: opts.lid, right: opts.lid }, None))
+        .collect();
+    if let (Some(kind), Some((_, tag, gaze, _, _))) = (opts.blink, frames.first().cloned()) {
+        frames = blink_closures(kind, 20).into_iter()
+            .enumerate()
+            .map(|(counter, closure)| (counter, tag.clone(), gaze, closure, None))
+            .collect();
+    }
+    if let (Some(from), Some((_, tag, gaze, closure, _))) = (opts.emotion_from, frames.first().cloned()) {
+        let to = opts.emotion.unwrap_or(opts.mode.appearance(0, pseudo_rand_bytes(0)).emotion);
+        frames = emotion_blends(from, to, opts.emotion_millis, 20).into_iter()
+            .enumerate()
+            .map(|(counter, blend)| (counter, tag.clone(), gaze, closure, Some(blend)))
+            .collect();
+    }
+
+    if let Err(err) = std::fs::create_dir_all(&opts.out_dir) {
+        eprintln!("can't create {}: {}", opts.out_dir.display(), err);
+        return ExitCode::FAILURE;
+    }
+
+    let mut frame_buf = new_frame_buf();
+    for (counter, gaze_tag, gaze, lid_closure, emotion_blend) in frames {
+        let appearance = opts.mode.appearance(counter, pseudo_rand_bytes(counter));
+        for is_left in [true, false] {
+            let params = EyeFrameParams {
+                is_left,
+                gaze,
+                emotion: emotion_blend
+                    .unwrap_or(EmotionBlend::steady(opts.emotion.unwrap_or(appearance.emotion))),
+                lid_closure: lid_closure.for_eye_side(is_left),
+                pupil_size: opts.pupil_size,
+                iris_color: appearance.iris_color,
+                skin_color: appearance.skin_color,
+            };
+            render_eye_frame(&params, &mut frame_buf);
+
+            let lid_tag = match params.lid_closure {
+                MorphFraction::START => String::new(),
+                closure => format!("_lid{:04}", permille(closure.to_f32())),
+            };
+            let pupil_tag = match params.pupil_size {
+                PupilSize::AUTHORED => String::new(),
+                size => format!("_pupil{}", size.pct()),
+            };
+            let emotion_tag = match emotion_blend {
+                Some(blend) => format!("_{:?}_{:?}_{:04}", blend.from, blend.to, permille(blend.progress.to_f32())),
+                None => String::new(),
+            };
+            let file_name = format!("{:?}_{:04}_{}_{}{}{}{}.{}",
+                opts.mode, counter, debug_tag_for_eye_side(is_left),
+                gaze_tag, lid_tag, pupil_tag, emotion_tag, opts.format.extension());
+            let path = opts.out_dir.join(file_name);
+            if let Err(err) = write_frame(&path, opts.format, &frame_buf) {
+                eprintln!("can't write {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,51 @@
+//!
+//! Anti-aliasing should only soften the edges of the layers it's asked for,
+//! leaving every other pixel as the hard-edged fill draws it.
+//!
+
+use eyemodelz::*;
+use eyesim::eyerender::*;
+use eyesim::image_out::rgb565_at;
+use eyesim::{new_frame_buf, render_eye_frame_anti_aliased, EyeFrameParams};
+
+/// Whether the pixel at `x`, `y` differs from any pixel up to two away. Slivers of shapes thinner
+/// than a pixel are broken up by the hard-edged fill, so anti-aliasing can join the pieces.
+fn near_an_edge(frame_buf: &FullFrameBuf, x: i32, y: i32) -> bool {
+    let pixel_at = |x: i32, y: i32| rgb565_at(frame_buf, y as usize * DISPLAY_WIDTH as usize + x as usize);
+    let pixel = pixel_at(x, y);
+    (-2..=2).flat_map(|dy| (-2..=2).map(move |dx| (x + dx, y + dy)))
+        .filter(|&(x, y)| (0..DISPLAY_WIDTH as i32).contains(&x) && (0..DISPLAY_HEIGHT as i32).contains(&y))
+        .any(|(x, y)| pixel_at(x, y) != pixel)
+}
+
+#[test]
+fn anti_aliasing_only_softens_edges() {
+    let frames = [
+        (GazeVector::STRAIGHT_AHEAD, MorphFraction::START),
+        (GazeVector::from(GazeDirection::NorthEast), MorphFraction::START),
+        (GazeVector::from_f32(-0.7, 0.4), MorphFraction::HALF),
+    ];
+    for is_left in [true, false] {
+        for &(gaze, lids) in &frames {
+            let params = EyeFrameParams { lid_closure: lids, ..EyeFrameParams::neutral(is_left, gaze) };
+            let mut hard = new_frame_buf();
+            render_eye_frame_anti_aliased(&params, EyeLayers::NONE, &mut hard);
+            let mut smooth = new_frame_buf();
+            render_eye_frame_anti_aliased(&params, ANTI_ALIASED_LAYERS, &mut smooth);
+
+            let mut num_changed = 0;
+            for y in 0..DISPLAY_HEIGHT as i32 {
+                for x in 0..DISPLAY_WIDTH as i32 {
+                    let idx = y as usize * DISPLAY_WIDTH as usize + x as usize;
+                    if rgb565_at(&hard[..], idx) != rgb565_at(&smooth[..], idx) {
+                        num_changed += 1;
+                        assert!(near_an_edge(&hard, x, y), "{} eye: {},{} changed away from any edge for {:?}",
+                            debug_tag_for_eye_side(is_left), x, y, params);
+                    }
+                }
+            }
+            assert!(num_changed > 0, "{} eye: nothing anti-aliased for {:?}", debug_tag_for_eye_side(is_left), params);
+        }
+    }
+}
+
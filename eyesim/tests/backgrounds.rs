@@ -0,0 +1,77 @@
+//!
+//! The generated background table must give each eye and emotion a full-frame image,
+//! shared rather than duplicated where emotions use the same one.
+//!
+
+use embedded_graphics::prelude::{OriginDimensions, Size};
+use eyemodelz::*;
+use eyesim::eyerender::*;
+use tinyqoi::Qoi;
+
+fn all_emotions() -> impl Iterator<Item = EmotionExpression> {
+    (0..EmotionExpression::MaxCount as u8).map(|idx| EmotionExpression::try_from(idx).unwrap())
+}
+
+#[test]
+fn every_background_fills_the_frame() {
+    for is_left in [true, false] {
+        for emotion in all_emotions() {
+            let Some(bytes) = get_emotion_bg_bytes(emotion, is_left) else { continue };
+            let qoi = Qoi::new(bytes).unwrap_or_else(|_| panic!("{:?} {} doesn't decode", emotion, is_left));
+            assert_eq!(qoi.size(), Size::new(320, 240), "{:?} {}", emotion, is_left);
+            assert_eq!(qoi.pixels().count(), 320 * 240, "{:?} {}", emotion, is_left);
+        }
+    }
+}
+
+#[test]
+fn both_eyes_have_neutral_and_surprise_backgrounds() {
+    for is_left in [true, false] {
+        let neutral = get_emotion_bg_bytes(EmotionExpression::Neutral, is_left).unwrap();
+        let surprise = get_emotion_bg_bytes(EmotionExpression::Surprise, is_left).unwrap();
+        assert!(!core::ptr::eq(neutral, surprise));
+        // emotions without a background of their own fall back to the neutral one
+        assert!(core::ptr::eq(get_emotion_bg_bytes(EmotionExpression::Curious, is_left).unwrap(), neutral));
+    }
+}
+
+#[test]
+fn shared_backgrounds_are_embedded_once() {
+    for is_left in [true, false] {
+        let happy = get_emotion_bg_bytes(EmotionExpression::Happy, is_left).unwrap();
+        assert!(core::ptr::eq(get_emotion_bg_bytes(EmotionExpression::Love, is_left).unwrap(), happy));
+    }
+    let mut distinct: Vec<&[u8]> = Vec::new();
+    for is_left in [true, false] {
+        for bytes in all_emotions().filter_map(|emotion| get_emotion_bg_bytes(emotion, is_left)) {
+            if !distinct.iter().any(|seen| core::ptr::eq(*seen, bytes)) {
+                distinct.push(bytes);
+            }
+        }
+    }
+    assert_eq!(distinct.iter().map(|bytes| bytes.len()).sum::<usize>(), EMOTION_BG_FLASH_BYTES);
+}
+
+/// The first row of the cheek below the eye, the only part of the background a blush may change
+const CHEEK_TOP_ROW: usize = 196;
+
+#[test]
+fn backgrounds_differ_from_neutral_only_in_the_blush() {
+    for is_left in [true, false] {
+        let neutral = Qoi::new(get_emotion_bg_bytes(EmotionExpression::Neutral, is_left).unwrap()).unwrap();
+        // Surprise has art of its own; every other emotion is the neutral image, blushing or not
+        for emotion in all_emotions().filter(|&emotion| emotion != EmotionExpression::Surprise) {
+            let qoi = Qoi::new(get_emotion_bg_bytes(emotion, is_left).unwrap()).unwrap();
+            let changed: Vec<usize> = neutral.pixels().zip(qoi.pixels()).enumerate()
+                .filter(|(_, (neutral, other))| neutral != other)
+                .map(|(idx, _)| idx)
+                .collect();
+            if let Some(&idx) = changed.iter().find(|&&idx| idx / 320 < CHEEK_TOP_ROW) {
+                panic!("{:?} {}: differs from neutral at {:?}, above the cheek", emotion, is_left, (idx % 320, idx / 320));
+            }
+            if matches!(emotion, EmotionExpression::Happy | EmotionExpression::Shy | EmotionExpression::Love) {
+                assert!(!changed.is_empty(), "{:?} {}: no blush", emotion, is_left);
+            }
+        }
+    }
+}
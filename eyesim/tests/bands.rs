@@ -0,0 +1,44 @@
+//!
+//! Rendering a frame a band at a time, as the `band-render` firmware does,
+//! must give exactly the same pixels as rendering it into a full frame buffer.
+//!
+
+use eyemodelz::*;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyesim::eyerender::*;
+use eyesim::{new_frame_buf, render_eye_frame, render_eye_frame_in_bands, EyeFrameParams};
+
+fn blend(from: EmotionExpression, to: EmotionExpression, progress: MorphFraction) -> EmotionBlend {
+    EmotionBlend { from, to, progress }
+}
+
+#[test]
+fn bands_match_the_full_frame() {
+    let frames = [
+        (GazeVector::STRAIGHT_AHEAD, EmotionBlend::steady(EmotionExpression::Neutral), MorphFraction::START),
+        (GazeVector::from(GazeDirection::NorthEast), EmotionBlend::steady(EmotionExpression::Surprise), MorphFraction::START),
+        (GazeVector::from_f32(-0.7, 0.4), EmotionBlend::steady(EmotionExpression::Happy), MorphFraction::HALF),
+        (GazeVector::from(GazeDirection::South),
+            blend(EmotionExpression::Neutral, EmotionExpression::Surprise, MorphFraction::from_ratio(1, 3)),
+            MorphFraction::START),
+        (GazeVector::from(GazeDirection::West),
+            blend(EmotionExpression::Surprise, EmotionExpression::Love, MorphFraction::HALF),
+            MorphFraction::from_ratio(4, 5)),
+        (GazeVector::STRAIGHT_AHEAD, EmotionBlend::steady(EmotionExpression::Neutral), MorphFraction::END),
+    ];
+    // a band per row, bands that split shapes partway, and a single band for the whole frame
+    let strip_sizes = [640, 32 * 1024, FRAME_SIZE_BYTES];
+    for is_left in [true, false] {
+        for &(gaze, emotion, lids) in &frames {
+            let params = EyeFrameParams { emotion, lid_closure: lids, ..EyeFrameParams::neutral(is_left, gaze) };
+            let mut full = new_frame_buf();
+            render_eye_frame(&params, &mut full);
+            for strip_bytes in strip_sizes {
+                let mut banded = new_frame_buf();
+                render_eye_frame_in_bands(&params, strip_bytes, &mut banded);
+                assert!(banded[..] == full[..], "{} eye, {} byte strips: bands differ for {:?}",
+                    debug_tag_for_eye_side(is_left), strip_bytes, params);
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//!
+//! Sending only the dirty region of each frame must leave the display showing
+//! exactly the frame buffer, pixel for pixel.
+//!
+
+use embedded_graphics::primitives::Rectangle;
+use eyemodelz::*;
+use eyemodelz::dirty_rect::transfer_rect;
+use eyesim::eyerender::*;
+use eyesim::{new_frame_buf, render_eye_frame, render_eyeball_update, EyeFrameParams};
+
+fn params(is_left: bool, gaze: GazeVector, lid_closure: MorphFraction) -> EyeFrameParams {
+    EyeFrameParams { lid_closure, ..EyeFrameParams::neutral(is_left, gaze) }
+}
+
+/// Copy one region of the frame buffer to the simulated display, as the SPI transfer does
+fn send_rect(frame_buf: &FullFrameBuf, display: &mut FullFrameBuf, rect: Rectangle) {
+    let row_bytes = DISPLAY_WIDTH as usize * PIXEL_SIZE as usize;
+    let x_bytes = rect.top_left.x as usize * PIXEL_SIZE as usize;
+    let width_bytes = rect.size.width as usize * PIXEL_SIZE as usize;
+    for row in rect.rows() {
+        let start = row as usize * row_bytes + x_bytes;
+        display[start..start + width_bytes].copy_from_slice(&frame_buf[start..start + width_bytes]);
+    }
+}
+
+#[test]
+fn partial_updates_leave_no_stale_pixels() {
+    let moves = [
+        (GazeVector::STRAIGHT_AHEAD, MorphFraction::START),
+        (GazeVector::from(GazeDirection::East), MorphFraction::START),
+        (GazeVector::from_f32(-1.0, -0.58), MorphFraction::START),
+        (GazeVector::from_f32(-1.0, -0.58), MorphFraction::HALF),
+        (GazeVector::from(GazeDirection::SouthWest), MorphFraction::END),
+        (GazeVector::from(GazeDirection::North), MorphFraction::from_ratio(1, 5)),
+    ];
+    for is_left in [true, false] {
+        let (gaze, lids) = moves[0];
+        let mut frame_buf = new_frame_buf();
+        render_eye_frame(&params(is_left, gaze, lids), &mut frame_buf);
+        let mut display = frame_buf.clone();
+
+        for &(gaze, lids) in &moves[1..] {
+            let params = params(is_left, gaze, lids);
+            if let Some(dirty) = render_eyeball_update(&params, &mut frame_buf) {
+                send_rect(&frame_buf, &mut display, transfer_rect(dirty, DISPLAY_WIDTH as u32, 256));
+            }
+            assert!(display[..] == frame_buf[..], "{} eye: stale pixels after {:?}", debug_tag_for_eye_side(is_left), params);
+        }
+    }
+}
+
+#[test]
+fn eyeball_updates_skip_most_of_the_frame() {
+    for is_left in [true, false] {
+        let mut frame_buf = new_frame_buf();
+        let params = params(is_left, GazeVector::from(GazeDirection::West), MorphFraction::START);
+        render_eye_frame(&params, &mut frame_buf);
+        let dirty = render_eyeball_update(&params, &mut frame_buf).unwrap();
+        let frame_area = DISPLAY_WIDTH as u32 * DISPLAY_HEIGHT as u32;
+        assert!(dirty.size.width * dirty.size.height < frame_area * 2 / 3, "{:?}", dirty);
+    }
+}
@@ -0,0 +1,67 @@
+//!
+//! Emotion changes must start and end exactly on each emotion's own frame,
+//! with brows, lids and backgrounds in between along the way.
+//!
+
+use eyemodelz::*;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyesim::eyerender::*;
+use eyesim::image_out::{rgb565_at, rgb565_to_rgb888};
+use eyesim::{new_frame_buf, render_eye_frame, EyeFrameParams};
+
+fn render(is_left: bool, emotion: EmotionBlend) -> Box<FullFrameBuf> {
+    let params = EyeFrameParams {
+        emotion,
+        skin_color: hex_to_rgb565(0x8EB34E),
+        ..EyeFrameParams::neutral(is_left, GazeVector::STRAIGHT_AHEAD)
+    };
+    let mut frame_buf = new_frame_buf();
+    render_eye_frame(&params, &mut frame_buf);
+    frame_buf
+}
+
+fn blend(from: EmotionExpression, to: EmotionExpression, progress: MorphFraction) -> EmotionBlend {
+    EmotionBlend { from, to, progress }
+}
+
+#[test]
+fn blend_endpoints_match_each_emotion() {
+    let (from, to) = (EmotionExpression::Neutral, EmotionExpression::Happy);
+    for is_left in [true, false] {
+        assert!(render(is_left, blend(from, to, MorphFraction::START))[..] == render(is_left, EmotionBlend::steady(from))[..]);
+        assert!(render(is_left, blend(from, to, MorphFraction::END))[..] == render(is_left, EmotionBlend::steady(to))[..]);
+    }
+}
+
+#[test]
+fn midway_is_distinct_from_both_emotions() {
+    for (from, to) in [
+        (EmotionExpression::Neutral, EmotionExpression::Happy),
+        (EmotionExpression::Skeptical, EmotionExpression::Surprise),
+    ] {
+        for is_left in [true, false] {
+            let midway = render(is_left, blend(from, to, MorphFraction::HALF));
+            assert!(midway[..] != render(is_left, EmotionBlend::steady(from))[..], "{:?} to {:?}", from, to);
+            assert!(midway[..] != render(is_left, EmotionBlend::steady(to))[..], "{:?} to {:?}", from, to);
+        }
+    }
+}
+
+fn pixel_at(frame_buf: &FullFrameBuf, x: usize, y: usize) -> [u8; 3] {
+    rgb565_to_rgb888(rgb565_at(frame_buf, y * DISPLAY_WIDTH as usize + x))
+}
+
+#[test]
+fn backgrounds_cross_fade() {
+    // the blush on the right eye's outer cheek, below the lids
+    let (x, y) = (230, 236);
+    let neutral = pixel_at(&render(false, EmotionBlend::steady(EmotionExpression::Neutral)), x, y);
+    let love = pixel_at(&render(false, EmotionBlend::steady(EmotionExpression::Love)), x, y);
+    let midway = pixel_at(&render(false, blend(EmotionExpression::Neutral, EmotionExpression::Love, MorphFraction::HALF)), x, y);
+    assert_ne!(neutral, love);
+    let between = |channel: usize| {
+        neutral[channel].min(love[channel]) <= midway[channel] && midway[channel] <= neutral[channel].max(love[channel])
+    };
+    assert!((0..3).all(between), "{:?} {:?} {:?}", neutral, midway, love);
+    assert!(midway != neutral && midway != love, "{:?} {:?} {:?}", neutral, midway, love);
+}
@@ -0,0 +1,128 @@
+//!
+//! Golden-image regression tests: render every gaze direction, look step and emotion
+//! for both eyes, and compare pixel-for-pixel against the reference images in `tests/golden`.
+//!
+//! To (re)generate the reference images after an intentional art or rendering change:
+//! `EYESIM_BLESS=1 cargo test --target <host> --test golden`
+//!
+
+use std::path::{Path, PathBuf};
+
+use eyemodelz::*;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyesim::eyerender::*;
+use eyesim::image_out::*;
+use eyesim::{new_frame_buf, render_eye_frame, EyeFrameParams};
+
+/// Fixed colors, so that the reference images only change when shapes change
+const GOLDEN_IRIS_COLOR: u32 = 0x405D80;
+const GOLDEN_SKIN_COLOR: u32 = 0x8EB34E;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn diff_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden-diffs")
+}
+
+/// Every golden frame, with its reference image name
+fn all_golden_params() -> Vec<(String, EyeFrameParams)> {
+    let mut params = Vec::new();
+    for emotion_idx in 0..EmotionExpression::MaxCount as u8 {
+        let emotion = EmotionExpression::try_from(emotion_idx).unwrap();
+        for gaze_idx in 0..GazeDirection::MaxCount as u8 {
+            let gaze_dir = GazeDirection::try_from(gaze_idx).unwrap();
+            for look_step in 0..NUM_LOOK_STEPS {
+                for is_left in [true, false] {
+                    params.push((golden_name(is_left, emotion, gaze_dir, look_step), EyeFrameParams {
+                        emotion: EmotionBlend::steady(emotion),
+                        iris_color: hex_to_rgb565(GOLDEN_IRIS_COLOR),
+                        skin_color: hex_to_rgb565(GOLDEN_SKIN_COLOR),
+                        ..EyeFrameParams::neutral(is_left, GazeTransition::from_center(gaze_dir, look_step).into())
+                    }));
+                }
+            }
+        }
+    }
+    params
+}
+
+fn golden_name(is_left: bool, emotion: EmotionExpression, gaze_dir: GazeDirection, look_step: u8) -> String {
+    format!("{}_{:?}_{}_{}", debug_tag_for_eye_side(is_left), emotion, gaze_dir.to_digits(), look_step)
+}
+
+/// Build a diff image: matching pixels are a dimmed grayscale of the reference,
+/// mismatched pixels are bright magenta.
+fn diff_image(expected: &[u8], actual: &[u8]) -> Vec<u8> {
+    expected.chunks_exact(3).zip(actual.chunks_exact(3))
+        .flat_map(|(exp, act)| {
+            if exp == act {
+                let luma = ((exp[0] as u16 * 77 + exp[1] as u16 * 150 + exp[2] as u16 * 29) >> 8) as u8;
+                [luma / 3; 3]
+            } else {
+                [0xff, 0x00, 0xff]
+            }
+        })
+        .collect()
+}
+
+#[test]
+#[cfg_attr(feature = "gc9a01", ignore = "the reference frames are for the default panel")]
+fn golden_frames_match_reference() {
+    let bless = std::env::var_os("EYESIM_BLESS").is_some();
+    let width = DISPLAY_WIDTH as u32;
+    let height = DISPLAY_HEIGHT as u32;
+    let mut frame_buf = new_frame_buf();
+    let mut failures: Vec<String> = Vec::new();
+
+    if bless {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+    }
+    for (name, params) in all_golden_params() {
+        render_eye_frame(&params, &mut frame_buf);
+        let actual = frame_to_rgb888(&frame_buf);
+        let golden_path = golden_dir().join(format!("{}.png", name));
+
+        if bless {
+            write_rgb888(&golden_path, ImageFormat::Png, width, height, &actual).unwrap();
+            continue;
+        }
+
+        let expected = match read_png_rgb888(&golden_path) {
+            Ok((w, h, rgb)) if w == width && h == height => rgb,
+            Ok((w, h, _)) => {
+                failures.push(format!("{}: reference is {}x{}, expected {}x{}", name, w, h, width, height));
+                continue;
+            }
+            Err(err) => {
+                failures.push(format!("{}: can't read {}: {}", name, golden_path.display(), err));
+                continue;
+            }
+        };
+
+        let mismatched = expected.chunks_exact(3).zip(actual.chunks_exact(3))
+            .filter(|(exp, act)| rgb888_to_rgb565(exp) != rgb888_to_rgb565(act))
+            .count();
+        if mismatched > 0 {
+            std::fs::create_dir_all(diff_dir()).unwrap();
+            let diff_path = diff_dir().join(format!("{}-diff.png", name));
+            let actual_path = diff_dir().join(format!("{}-actual.png", name));
+            write_rgb888(&diff_path, ImageFormat::Png, width, height, &diff_image(&expected, &actual)).unwrap();
+            write_rgb888(&actual_path, ImageFormat::Png, width, height, &actual).unwrap();
+            failures.push(format!("{}: {} pixels differ, see {}", name, mismatched, diff_path.display()));
+        }
+    }
+
+    assert!(failures.is_empty(),
+        "{} golden frames differ (rerun with EYESIM_BLESS=1 to accept):\n{}",
+        failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn rgb565_round_trips_through_rgb888() {
+    for raw in [0x0000u16, 0xffff, 0xf800, 0x07e0, 0x001f, 0x18ff, 0x1234] {
+        let rgb = eyesim::image_out::rgb565_to_rgb888(raw);
+        assert_eq!(rgb888_to_rgb565(&rgb), raw);
+    }
+}
@@ -0,0 +1,42 @@
+//!
+//! Static layers composited from the layer cache must give exactly the same pixels
+//! as drawing their polygons, however the cache was filled along the way.
+//!
+
+use embedded_graphics::pixelcolor::{Rgb565, WebColors};
+use eyemodelz::*;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyesim::eyerender::*;
+use eyesim::{new_frame_buf, render_eye_frame_with_cache, EyeFrameParams};
+
+#[test]
+fn cached_layers_match_drawn_layers() {
+    let green_skin = Rgb565::new(17, 45, 9);
+    let orange_skin = Rgb565::CSS_ORANGE;
+    let surprise_to_happy = EmotionBlend {
+        from: EmotionExpression::Surprise, to: EmotionExpression::Happy, progress: MorphFraction::HALF,
+    };
+    // a sequence of frames as the firmware might draw them, reusing one cache
+    let frames = [
+        (GazeVector::STRAIGHT_AHEAD, EmotionBlend::steady(EmotionExpression::Neutral), MorphFraction::START, green_skin),
+        (GazeVector::from(GazeDirection::East), EmotionBlend::steady(EmotionExpression::Neutral), MorphFraction::START, green_skin),
+        (GazeVector::from(GazeDirection::East), EmotionBlend::steady(EmotionExpression::Neutral), MorphFraction::HALF, green_skin),
+        (GazeVector::from_f32(-0.6, 0.5), EmotionBlend::steady(EmotionExpression::Surprise), MorphFraction::START, orange_skin),
+        (GazeVector::from_f32(-0.6, 0.5), surprise_to_happy, MorphFraction::START, orange_skin),
+        (GazeVector::from(GazeDirection::North), EmotionBlend::steady(EmotionExpression::Happy), MorphFraction::START, orange_skin),
+        (GazeVector::from(GazeDirection::North), EmotionBlend::steady(EmotionExpression::Happy), MorphFraction::START, green_skin),
+    ];
+    for is_left in [true, false] {
+        let mut layers = LayerCache::new();
+        let mut uncached_layers = LayerCache::disabled();
+        for &(gaze, emotion, lids, skin_color) in &frames {
+            let params = EyeFrameParams { emotion, lid_closure: lids, skin_color, ..EyeFrameParams::neutral(is_left, gaze) };
+            let mut cached = new_frame_buf();
+            render_eye_frame_with_cache(&params, &mut layers, &mut cached);
+            let mut drawn = new_frame_buf();
+            render_eye_frame_with_cache(&params, &mut uncached_layers, &mut drawn);
+            assert!(cached[..] == drawn[..], "{} eye: cached layers differ for {:?}",
+                debug_tag_for_eye_side(is_left), params);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//!
+//! Mode names used by text commands must match the modes they select.
+//!
+
+use eyesim::eyerender::*;
+
+#[test]
+fn mode_names_match_modes() {
+    for idx in 0..TestModeA::MaxCount as u8 {
+        let mode = TestModeA::try_from(idx).unwrap();
+        assert_eq!(TestModeA::NAMES[idx as usize], format!("{:?}", mode));
+    }
+}
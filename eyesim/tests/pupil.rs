@@ -0,0 +1,99 @@
+//!
+//! The pupil must scale within the iris, carrying its glints along,
+//! and change nothing else in the frame.
+//!
+
+use embedded_graphics::prelude::{Point, PointsIter};
+use embedded_graphics::primitives::Rectangle;
+use eyemodelz::*;
+use eyemodelz::pupil::PupilSize;
+use eyesim::eyerender::*;
+use eyesim::image_out::rgb565_at;
+use eyesim::{new_frame_buf, render_eye_frame, render_eye_frame_in_bands, render_eyeball_update, EyeFrameParams};
+
+fn params(is_left: bool, gaze: GazeVector, pupil_size: PupilSize) -> EyeFrameParams {
+    EyeFrameParams { pupil_size, ..EyeFrameParams::neutral(is_left, gaze) }
+}
+
+fn render(params: &EyeFrameParams) -> Box<FullFrameBuf> {
+    let mut frame_buf = new_frame_buf();
+    render_eye_frame(params, &mut frame_buf);
+    frame_buf
+}
+
+/// The iris's bounds on the panel, with a pixel to spare for its outline
+fn iris_bounds(is_left: bool, gaze: GazeVector) -> Rectangle {
+    let iris = resolve_gaze_asset(SvgFileId::for_eye_side(is_left), "iris", gaze).unwrap();
+    let vertices = VIEWPORT.to_panel_vertices(iris.vertices()).unwrap();
+    let min = vertices.iter().fold(vertices[0], |min, point| min.component_min(*point));
+    let max = vertices.iter().fold(vertices[0], |max, point| max.component_max(*point));
+    Rectangle::with_corners(min - Point::new(1, 1), max + Point::new(1, 1))
+}
+
+fn pixel_points() -> impl Iterator<Item = Point> {
+    (0..DISPLAY_HEIGHT as i32).flat_map(|y| (0..DISPLAY_WIDTH as i32).map(move |x| Point::new(x, y)))
+}
+
+fn pixel_idx(point: Point) -> usize {
+    point.y as usize * DISPLAY_WIDTH as usize + point.x as usize
+}
+
+#[test]
+fn bigger_pupils_cover_more_of_the_iris() {
+    let sizes = [PupilSize::MIN, PupilSize::from_pct(80), PupilSize::AUTHORED, PupilSize::from_pct(125), PupilSize::MAX];
+    for is_left in [true, false] {
+        for gaze in [GazeVector::STRAIGHT_AHEAD, GazeVector::from_f32(-1.0, -0.58)] {
+            let bounds = iris_bounds(is_left, gaze);
+            let black_pixels: Vec<usize> = sizes.iter()
+                .map(|&size| {
+                    let frame_buf = render(&params(is_left, gaze, size));
+                    bounds.points().filter(|&point| rgb565_at(&frame_buf[..], pixel_idx(point)) == 0).count()
+                })
+                .collect();
+            assert!(black_pixels.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", black_pixels);
+        }
+    }
+}
+
+#[test]
+fn only_the_iris_changes() {
+    for is_left in [true, false] {
+        let gaze = GazeVector::from(GazeDirection::East);
+        let bounds = iris_bounds(is_left, gaze);
+        let authored = render(&params(is_left, gaze, PupilSize::AUTHORED));
+        for size in [PupilSize::MIN, PupilSize::MAX] {
+            let scaled = render(&params(is_left, gaze, size));
+            let changed: Vec<Point> = pixel_points()
+                .filter(|&point| rgb565_at(&scaled[..], pixel_idx(point)) != rgb565_at(&authored[..], pixel_idx(point)))
+                .collect();
+            assert!(!changed.is_empty(), "{:?}", size);
+            assert!(changed.iter().all(|&point| bounds.contains(point)),
+                "{:?} changed pixels outside {:?}: {:?}", size, bounds, changed.iter().find(|&&point| !bounds.contains(point)));
+        }
+    }
+}
+
+#[test]
+fn pupil_changes_render_the_same_every_way() {
+    for is_left in [true, false] {
+        let gaze = GazeVector::from_f32(0.4, 0.7);
+        let dilated = params(is_left, gaze, PupilSize::from_pct(140));
+        let full = render(&dilated);
+
+        let mut banded = new_frame_buf();
+        render_eye_frame_in_bands(&dilated, 8 * DISPLAY_WIDTH as usize * PIXEL_SIZE as usize, &mut banded);
+        assert!(banded[..] == full[..], "{} eye: bands differ", debug_tag_for_eye_side(is_left));
+
+        // constricting over the dilated frame leaves none of the old pupil behind,
+        // just as over the authored one (the iris's and lids' edges blend over what's below,
+        // and the iris's shading follows the pupil, so compare where the pupil is)
+        let constricted = params(is_left, gaze, PupilSize::from_pct(70));
+        let mut from_dilated = full.clone();
+        render_eyeball_update(&constricted, &mut from_dilated);
+        let mut from_authored = render(&params(is_left, gaze, PupilSize::AUTHORED));
+        render_eyeball_update(&constricted, &mut from_authored);
+        let stale = pixel_points()
+            .find(|&point| (rgb565_at(&from_dilated[..], pixel_idx(point)) == 0) != (rgb565_at(&from_authored[..], pixel_idx(point)) == 0));
+        assert!(stale.is_none(), "{} eye: stale pupil at {:?}", debug_tag_for_eye_side(is_left), stale);
+    }
+}
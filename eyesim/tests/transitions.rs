@@ -0,0 +1,81 @@
+//!
+//! Direct gaze-to-gaze transitions must land exactly on the keyframes at either end,
+//! and must not depend on which way an authored tween is traversed.
+//!
+
+use eyemodelz::*;
+use eyesim::eyerender::*;
+use eyesim::{new_frame_buf, render_eye_frame, EyeFrameParams};
+
+fn render(is_left: bool, gaze: impl Into<GazeVector>) -> Box<FullFrameBuf> {
+    let params = EyeFrameParams::neutral(is_left, gaze.into());
+    let mut frame_buf = new_frame_buf();
+    render_eye_frame(&params, &mut frame_buf);
+    frame_buf
+}
+
+fn assert_same_frame(is_left: bool, a: GazeTransition, b: GazeTransition) {
+    assert!(render(is_left, a)[..] == render(is_left, b)[..],
+        "{} eye: {:?} and {:?} render differently", debug_tag_for_eye_side(is_left), a, b);
+}
+
+#[test]
+fn direct_transition_endpoints_match_keyframes() {
+    let direct = GazeTransition::at_look_step(GazeDirection::NorthWest, GazeDirection::East, 0);
+    for is_left in [true, false] {
+        assert_same_frame(is_left, direct,
+            GazeTransition::from_center(GazeDirection::NorthWest, LAST_LOOK_STEP_IDX));
+        assert_same_frame(is_left, GazeTransition { progress: MorphFraction::END, ..direct },
+            GazeTransition::from_center(GazeDirection::East, LAST_LOOK_STEP_IDX));
+    }
+}
+
+#[test]
+fn return_to_center_reuses_authored_tweens() {
+    for look_step in 0..NUM_LOOK_STEPS {
+        let outward = GazeTransition::from_center(GazeDirection::SouthEast, look_step);
+        for is_left in [true, false] {
+            assert_same_frame(is_left, outward, outward.reversed());
+        }
+    }
+}
+
+#[test]
+fn authored_steps_are_used_exactly_at_their_fractions() {
+    // A 6-division move passes through each authored look step on every other frame
+    for step in 0..NUM_LOOK_STEPS {
+        let subdivided = GazeTransition {
+            progress: MorphFraction::from_ratio(step as u32 * 2, LAST_LOOK_STEP_IDX as u32 * 2),
+            ..GazeTransition::from_center(GazeDirection::NorthWest, 0)
+        };
+        for is_left in [true, false] {
+            assert_same_frame(is_left, subdivided, GazeTransition::from_center(GazeDirection::NorthWest, step));
+        }
+    }
+}
+
+#[test]
+fn sparse_grid_control_points_render_authored_steps() {
+    let ctr = SPARSE_GRID_ARM_LEN;
+    // the East arm of the 7x7 grid, and the NorthWest diagonal
+    for step in 1..NUM_LOOK_STEPS {
+        let east = GazeVector::from_sparse_grid(ctr, ctr + step).unwrap();
+        let north_west = GazeVector::from_sparse_grid(ctr - step, ctr - step).unwrap();
+        for is_left in [true, false] {
+            assert!(render(is_left, east)[..] == render(is_left, GazeTransition::from_center(GazeDirection::East, step))[..]);
+            assert!(render(is_left, north_west)[..]
+                == render(is_left, GazeTransition::from_center(GazeDirection::NorthWest, step))[..]);
+        }
+    }
+}
+
+#[test]
+fn gaze_between_arms_is_distinct_from_both() {
+    // about 30 degrees up from due west
+    let between = GazeVector::from_f32(-1.0, -0.58);
+    for is_left in [true, false] {
+        let frame = render(is_left, between);
+        assert!(frame[..] != render(is_left, GazeDirection::West)[..]);
+        assert!(frame[..] != render(is_left, GazeDirection::NorthWest)[..]);
+    }
+}
@@ -4,7 +4,12 @@ MEMORY {
      *
      * 2 MiB is a safe default here, although a Pico 2 has 4 MiB.
      */
-    FLASH : ORIGIN = 0x10000000, LENGTH = 2048K
+    FLASH : ORIGIN = 0x10000000, LENGTH = 2040K
+    /*
+     * The last two 4K sectors are kept out of the program image, for user settings
+     * that survive a reset. See src/settings_flash.rs
+     */
+    SETTINGS : ORIGIN = 0x101FE000, LENGTH = 8K
     /*
      * RAM consists of 8 banks, SRAM0-SRAM7, with a striped mapping.
      * This is usually good for performance, as it distributes load on
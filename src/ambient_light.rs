@@ -0,0 +1,62 @@
+//!
+//! Backlight brightness from the room's light (the `ambient-light` feature).
+//!
+//! A photoresistor on the board profile's `light_sensor` ADC pin is read a few times a second
+//! and smoothed into a brightness by `eyemodelz::ambient_light`. The main loop shows that
+//! brightness in place of the mode's own, kept within the user's brightness curve;
+//! a brightness set over USB still wins.
+//!
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use defmt::{unwrap, warn};
+use embassy_executor::Spawner;
+use embassy_rp::adc::{self, Adc, AdcPin, Channel};
+use embassy_rp::gpio::Pull;
+use embassy_rp::peripherals::ADC;
+use embassy_rp::{bind_interrupts, Peri};
+use embassy_time::{Instant, Timer};
+
+use eyemodelz::ambient_light::{AmbientBrightness, AmbientCurve, AmbientTiming};
+
+use crate::NO_OVERRIDE;
+
+bind_interrupts!(struct Irqs {
+    ADC_IRQ_FIFO => adc::InterruptHandler;
+});
+
+/// How often the light sensor is read
+const LIGHT_READ_INTERVAL_MS: u64 = 100;
+
+/// The brightness the room's light calls for, or NO_OVERRIDE until the sensor has been read
+static AMBIENT_BRIGHTNESS_PCT: AtomicU8 = AtomicU8::new(NO_OVERRIDE);
+
+/// The brightness the room's light calls for, once the sensor has been read
+pub fn ambient_brightness_pct() -> Option<u8> {
+    match AMBIENT_BRIGHTNESS_PCT.load(Ordering::Relaxed) {
+        NO_OVERRIDE => None,
+        pct => Some(pct),
+    }
+}
+
+#[embassy_executor::task]
+async fn light_sensor_task(mut adc: Adc<'static, adc::Async>, mut sensor: Channel<'static>) {
+    let mut ambient = AmbientBrightness::new(AmbientCurve::PHOTORESISTOR, AmbientTiming::DEFAULT);
+    loop {
+        match adc.read(&mut sensor).await {
+            Ok(level) => {
+                let pct = ambient.update(level, Instant::now().as_millis());
+                AMBIENT_BRIGHTNESS_PCT.store(pct, Ordering::Relaxed);
+            }
+            Err(err) => warn!("light sensor read failed: {}", err),
+        }
+        Timer::after_millis(LIGHT_READ_INTERVAL_MS).await;
+    }
+}
+
+/// Set up the ADC to read the photoresistor on `sensor_pin`, and spawn the task that reads it
+pub fn spawn_light_sensor_task(spawner: &Spawner, adc: Peri<'static, ADC>, sensor_pin: Peri<'static, impl AdcPin>) {
+    let adc = Adc::new(adc, Irqs, adc::Config::default());
+    let sensor = Channel::new_pin(sensor_pin, Pull::None);
+    unwrap!(spawner.spawn(light_sensor_task(adc, sensor)));
+}
@@ -0,0 +1,10 @@
+//!
+//! The pins the board is wired with, from the board profile picked at build time
+//! (see build_support/board_pins.rs and the profiles in `boards/`).
+//!
+
+use embassy_rp::peripherals;
+use embassy_rp::pwm::{self, Pwm};
+use embassy_rp::Peri;
+
+include!(concat!(env!("OUT_DIR"), "/board_pins.rs"));
@@ -0,0 +1,77 @@
+//!
+//! Per-eye display panel configuration.
+//!
+//! Everything that differs between panels, or between the left and right eye on the same panel,
+//! lives here: the controller model, its native resolution, how it's rotated onto the frame,
+//! and its color inversion and order. The default build drives a pair of 320x240 ST7789 panels,
+//! mounted on their sides in opposite directions. The `gc9a01` feature drives a pair of
+//! 240x240 GC9A01 round panels instead, with the eye art fitted to them (see `eyerender::VIEWPORT`).
+//!
+
+use lcd_async::options::{ColorInversion, ColorOrder, Orientation, Rotation};
+
+use crate::eyerender::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// The display controller both eyes are driven with
+#[cfg(not(feature = "gc9a01"))]
+pub type PanelModel = lcd_async::models::ST7789;
+#[cfg(feature = "gc9a01")]
+pub type PanelModel = lcd_async::models::GC9A01;
+
+#[cfg(not(feature = "gc9a01"))]
+pub const PANEL_MODEL: PanelModel = lcd_async::models::ST7789;
+#[cfg(feature = "gc9a01")]
+pub const PANEL_MODEL: PanelModel = lcd_async::models::GC9A01;
+
+/// How one eye's panel is set up
+#[derive(Clone, Copy)]
+pub struct DisplayConfig {
+    /// The panel's width and height before rotation
+    pub native_size: (u16, u16),
+    pub rotation: Rotation,
+    pub color_inversion: ColorInversion,
+    pub color_order: ColorOrder,
+}
+
+impl DisplayConfig {
+    pub fn orientation(&self) -> Orientation {
+        Orientation::new().rotate(self.rotation)
+    }
+
+    /// The panel's width and height once rotated
+    const fn rotated_size(&self) -> (u16, u16) {
+        match self.rotation {
+            Rotation::Deg90 | Rotation::Deg270 => (self.native_size.1, self.native_size.0),
+            Rotation::Deg0 | Rotation::Deg180 => self.native_size,
+        }
+    }
+}
+
+// ST7789: 240x320 panels on their sides, facing opposite ways
+#[cfg(not(feature = "gc9a01"))]
+pub const LEFT_DISPLAY: DisplayConfig = DisplayConfig {
+    native_size: (DISPLAY_HEIGHT, DISPLAY_WIDTH),
+    rotation: Rotation::Deg270,
+    color_inversion: ColorInversion::Inverted,
+    color_order: ColorOrder::Rgb,
+};
+#[cfg(not(feature = "gc9a01"))]
+pub const RIGHT_DISPLAY: DisplayConfig = DisplayConfig { rotation: Rotation::Deg90, ..LEFT_DISPLAY };
+
+// GC9A01: round panels, upright, with their red and blue swapped
+#[cfg(feature = "gc9a01")]
+pub const LEFT_DISPLAY: DisplayConfig = DisplayConfig {
+    native_size: (240, 240),
+    rotation: Rotation::Deg0,
+    color_inversion: ColorInversion::Inverted,
+    color_order: ColorOrder::Bgr,
+};
+#[cfg(feature = "gc9a01")]
+pub const RIGHT_DISPLAY: DisplayConfig = LEFT_DISPLAY;
+
+// both panels must show a whole frame
+const _: () = {
+    let (left, right) = (LEFT_DISPLAY.rotated_size(), RIGHT_DISPLAY.rotated_size());
+    assert!(left.0 == DISPLAY_WIDTH && left.1 == DISPLAY_HEIGHT);
+    assert!(right.0 == DISPLAY_WIDTH && right.1 == DISPLAY_HEIGHT);
+};
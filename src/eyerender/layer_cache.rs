@@ -0,0 +1,232 @@
+//!
+//! Pre-rasterized static layers of the eyeball.
+//!
+//! The sclera, the eye corners and the open lower lid don't move with the gaze, so rather than
+//! filling their polygons on every iris redraw, each is rasterized once (per emotion and skin color)
+//! into runs of same-colored pixels, which are replayed in the order they were drawn.
+//! Replaying in order keeps strokes over fills, so the result is pixel-identical to drawing the polygons.
+//!
+
+use core::convert::Infallible;
+
+use super::*;
+
+// LAYER_CACHE_SPANS and LAYER_CACHE_SPAN_BYTES, checked against the RAM in memory.x
+include!(concat!(env!("OUT_DIR"), "/frame_memory.rs"));
+
+// rows are kept in a byte
+const _: () = assert!(DISPLAY_HEIGHT <= 256);
+
+/// A run of same-colored pixels along a row, blended over what's below by `coverage`
+/// where the layer is anti-aliased
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Span {
+    x: u16,
+    y: u8,
+    coverage: u8,
+    len: u16,
+    color: Rgb565,
+}
+
+// the RAM budget counts spans at this size
+const _: () = assert!(core::mem::size_of::<Span>() == LAYER_CACHE_SPAN_BYTES);
+
+/// The parts of the eyeball that only change with the emotion or skin color
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum StaticLayer {
+    Sclera = 0,
+    /// The inner and outer corners
+    Corners = 1,
+    /// The lower lid bulge and shine, when the lids are fully open
+    LowerLid = 2,
+}
+
+const NUM_STATIC_LAYERS: usize = 3;
+
+/// What the cached layers were rasterized for
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CacheKey {
+    is_left: bool,
+    emotion: EmotionExpression,
+    skin_color: Rgb565,
+    anti_aliased: EyeLayers,
+}
+
+/// Where a layer's spans are, and the area they cover, or None if it didn't fit
+type LayerSpans = Option<(core::ops::Range<usize>, DirtyRect)>;
+
+/// The static layers of one eye, rasterized as runs of pixels
+pub struct LayerCache {
+    spans: heapless::Vec<Span, LAYER_CACHE_SPANS>,
+    layers: [LayerSpans; NUM_STATIC_LAYERS],
+    key: Option<CacheKey>,
+    enabled: bool,
+}
+
+impl Default for LayerCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LayerCache {
+    /// Empty, to be filled by the first `update`
+    pub const fn new() -> Self {
+        Self { spans: heapless::Vec::new(), layers: [None, None, None], key: None, enabled: true }
+    }
+
+    /// A cache that never holds anything, so every layer is drawn from its polygons,
+    /// for comparing against or benchmarking
+    pub const fn disabled() -> Self {
+        Self { spans: heapless::Vec::new(), layers: [None, None, None], key: None, enabled: false }
+    }
+
+    /// Re-rasterize the static layers if the eye, emotion, skin color or anti-aliased layers
+    /// differ from last time
+    pub fn update(&mut self, is_left: bool, emotion: EmotionExpression, skin_color: Rgb565, anti_aliased: EyeLayers) {
+        let key = CacheKey { is_left, emotion, skin_color, anti_aliased };
+        if !self.enabled || self.key == Some(key) {
+            return;
+        }
+        let start_micros = now_micros();
+        let file_id = SvgFileId::for_eye_side(is_left);
+        let steady = EmotionBlend::steady(emotion);
+        self.spans.clear();
+
+        self.layers[StaticLayer::Sclera as usize] = self.record(anti_aliased.contains(EyeLayer::Sclera), |rec| {
+            if let Some(cpoly) = get_svg_path_by_id_checked(file_id, "sclera") {
+                rec.draw_poly(cpoly.vertices(), &sclera_style());
+            }
+        });
+        self.layers[StaticLayer::Corners as usize] = self.record(anti_aliased.contains(EyeLayer::Corners), |rec| {
+            for corner_id in ["outer_corner_11", "inner_corner_11"] {
+                if let Some(cpoly) = get_svg_path_by_id_checked(file_id, corner_id) {
+                    rec.draw_poly(cpoly.vertices(), &corner_style());
+                }
+            }
+        });
+        self.layers[StaticLayer::LowerLid as usize] = self.record(anti_aliased.contains(EyeLayer::LowerLid), |rec| {
+            let (bulge_style, shine_style) = lower_lid_styles(skin_color);
+            if let Some(bulge) = resolve_fixed_emotion_asset(file_id, "lower_lid_bulge", steady, true) {
+                rec.draw_poly(bulge.vertices(), &bulge_style);
+            }
+            if let Some(shine) = resolve_fixed_emotion_asset(file_id, "lower_lid_shine", steady, true) {
+                rec.draw_poly(shine.vertices(), &shine_style);
+            }
+        });
+        self.key = Some(key);
+
+        let _elapsed_micros = now_micros() - start_micros;
+        info!("{} layer cache {} spans {}µs", debug_tag_for_eye_side(is_left), self.spans.len(), _elapsed_micros);
+        if self.layers.iter().any(|layer| layer.is_none()) {
+            warn!("{} layer cache full", debug_tag_for_eye_side(is_left));
+        }
+    }
+
+    /// Rasterize one layer after those already cached, with anti-aliased fills if `smoothing`
+    fn record<F: FnOnce(&mut SpanRecorder)>(&mut self, smoothing: bool, draw: F) -> LayerSpans {
+        let start = self.spans.len();
+        let mut recorder = SpanRecorder {
+            spans: &mut self.spans, first: start, smoothing, bounds: DirtyRect::new(), overflowed: false,
+        };
+        draw(&mut recorder);
+        let (overflowed, bounds) = (recorder.overflowed, recorder.bounds);
+        if overflowed {
+            self.spans.truncate(start);
+            return None;
+        }
+        Some((start..self.spans.len(), bounds))
+    }
+
+    /// Draw a cached layer into the frame, returning false if it isn't cached
+    /// (so the caller has to draw it from its polygons instead)
+    pub fn composite(&self, layer: StaticLayer, frame: &mut FrameRows) -> bool {
+        let Some((range, bounds)) = &self.layers[layer as usize] else { return false };
+        for span in &self.spans[range.clone()] {
+            frame.blend_span(Point::new(span.x as i32, span.y as i32), span.len as u32, span.color, span.coverage);
+        }
+        if let Some(rect) = bounds.bounds(FRAME_SIZE) {
+            frame.dirty.add_rect(rect.intersection(&frame.rows()));
+        }
+        true
+    }
+}
+
+/// Collects everything drawn into it as runs of pixels, clipped to the frame
+struct SpanRecorder<'a> {
+    spans: &'a mut heapless::Vec<Span, LAYER_CACHE_SPANS>,
+    /// Where this layer's spans start
+    first: usize,
+    /// Whether this layer's fills are anti-aliased
+    smoothing: bool,
+    bounds: DirtyRect,
+    overflowed: bool,
+}
+
+impl SpanRecorder<'_> {
+    /// Draw a polygon of the eye art
+    fn draw_poly(&mut self, vertices: &[Point], style: &PrimitiveStyle<Rgb565>) {
+        with_panel_vertices(vertices, |vertices| {
+            if self.smoothing {
+                fill_anti_aliased(vertices, |start, len, coverage| {
+                    if let Some(color) = smooth_fill_color(style, coverage) {
+                        self.push(start.x, start.y, len, color, coverage);
+                    }
+                });
+                draw_smooth_outline(vertices, style, self);
+            } else {
+                draw_styled_poly(vertices, style, self);
+            }
+        });
+    }
+
+    fn push(&mut self, x: i32, y: i32, len: u32, color: Rgb565, coverage: u8) {
+        // clip to the frame, as drawing into it would
+        let x_end = (x + len as i32).min(DISPLAY_WIDTH as i32);
+        let x = x.max(0);
+        if y < 0 || y >= DISPLAY_HEIGHT as i32 || x >= x_end {
+            return;
+        }
+        let len = (x_end - x) as u32;
+        self.bounds.add_rect(Rectangle::new(Point::new(x, y), Size::new(len, 1)));
+        // extend the layer's last run if this carries straight on from it
+        if let Some(last) = self.spans[self.first..].last_mut() {
+            if last.y as i32 == y && (last.x + last.len) as i32 == x && last.color == color && last.coverage == coverage {
+                last.len += len as u16;
+                return;
+            }
+        }
+        let span = Span { x: x as u16, y: y as u8, coverage, len: len as u16, color };
+        if self.spans.push(span).is_err() {
+            self.overflowed = true;
+        }
+    }
+}
+
+impl OriginDimensions for SpanRecorder<'_> {
+    fn size(&self) -> Size {
+        FRAME_SIZE
+    }
+}
+
+impl DrawTarget for SpanRecorder<'_> {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where I: IntoIterator<Item = Pixel<Self::Color>>
+    {
+        for Pixel(point, color) in pixels {
+            self.push(point.x, point.y, 1, color, FULL_COVERAGE);
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        for y in area.rows() {
+            self.push(area.top_left.x, y, area.size.width, color, FULL_COVERAGE);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1173 @@
+//!
+//! Rendering of eye layers into RGB565 frame buffers.
+//!
+//! This module is shared between the firmware and the host-side simulator (`eyesim`),
+//! so it must not depend on any particular HAL or executor. The including crate
+//! provides, at its root:
+//! - `info!` and `warn!` logging macros (defmt on target)
+//! - `now_micros()` for benchmarking
+//! - `get_svg_path_by_id_file_EyeLeft` and `get_svg_path_by_id_file_EyeRight`,
+//!   as generated by `import_svg_paths!`
+//!
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use embedded_graphics::{
+    prelude::*,
+    pixelcolor::{raw::RawU16, Rgb565, Rgb888},
+    primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StrokeAlignment},
+};
+use heapless::String;
+use lcd_async::raw_framebuf::RawFrameBuf;
+use num_enum::TryFromPrimitive;
+use tinyqoi::Qoi;
+
+use closed_svg_path::ClosedPolygon;
+
+use eyemodelz::*;
+use eyemodelz::anti_alias::{blend_rgb565, fill_anti_aliased, FULL_COVERAGE};
+use eyemodelz::blink::close_lid_vertices;
+use eyemodelz::dirty_rect::DirtyRect;
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyemodelz::gaze_control::GazeTargets;
+use eyemodelz::morph::{morph_vertices, translate_vertices, MorphVertices};
+use eyemodelz::iris_texture::IrisTexture;
+use eyemodelz::pupil::PupilSize;
+use eyemodelz::thick_stroke::draw_thick_outline;
+use eyemodelz::viewport::{Viewport, ViewportPixels};
+use crate::{info, warn, now_micros};
+use crate::{get_svg_path_by_id_file_EyeLeft, get_svg_path_by_id_file_EyeRight};
+
+mod layer_cache;
+pub use layer_cache::{LayerCache, StaticLayer, LAYER_CACHE_SPANS};
+
+
+pub const ORIGIN_POINT:Point = Point::new(0, 0);
+/// The size of frame the eye art is authored for, see `VIEWPORT`
+pub const ART_SIZE: Size = Size::new(320, 240);
+#[cfg(not(feature = "gc9a01"))]
+pub const DISPLAY_WIDTH: u16 =  320;
+#[cfg(not(feature = "gc9a01"))]
+pub const DISPLAY_HEIGHT: u16 = 240;
+// 240x240 round GC9A01 panels
+#[cfg(feature = "gc9a01")]
+pub const DISPLAY_WIDTH: u16 = 240;
+#[cfg(feature = "gc9a01")]
+pub const DISPLAY_HEIGHT: u16 = 240;
+pub const PIXEL_SIZE: u16 = 2; // RGB565 = 2 bytes per pixel
+pub const FRAME_SIZE_BYTES: usize = DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize * PIXEL_SIZE as usize;
+pub type FullFrameBuf = [u8; FRAME_SIZE_BYTES];
+pub const FRAME_SIZE: Size = Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32);
+/// How the eye art is fit onto the panel
+pub const VIEWPORT: Viewport = Viewport::new(ART_SIZE, FRAME_SIZE);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TestModeA {
+    ClockStar = 0,
+    HStep = 1,
+    VStep = 2,
+    HSweep = 3,
+    VSweep = 4,
+    SurpriseHSweep = 5,
+    Meander = 6,
+    SlowRandMeander = 7,
+    Randomize = 8,
+    MaxCount
+}
+
+pub const IRIS_PALETTE_PURPLE: [Rgb565; 8] = [
+    Rgb565::CSS_INDIGO,
+    Rgb565::CSS_REBECCAPURPLE,
+    Rgb565::CSS_DARK_ORCHID,
+    Rgb565::CSS_BLUE_VIOLET,
+    Rgb565::CSS_MEDIUM_PURPLE,
+    Rgb565::CSS_MEDIUM_ORCHID,
+    Rgb565::CSS_VIOLET,
+    Rgb565::CSS_PLUM,
+
+];
+
+/// The colors and expression used to render eyes in a particular test mode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModeAppearance {
+    pub iris_color: Rgb565,
+    pub skin_color: Rgb565,
+    pub emotion: EmotionExpression,
+}
+
+impl TestModeA {
+
+    /// Names for each mode, in index order, eg for text commands
+    pub const NAMES: [&'static str; TestModeA::MaxCount as usize] = [
+        "ClockStar", "HStep", "VStep", "HSweep", "VSweep", "SurpriseHSweep", "Meander", "SlowRandMeander", "Randomize",
+    ];
+
+    /// Provide the colors and expression for this mode.
+    /// - `counter` is a monotonically increasing frame counter, used for cycling palettes
+    /// - `rand_bytes` are only used by modes that randomize colors
+    pub fn appearance(self, counter: usize, rand_bytes: [u8; 3]) -> ModeAppearance {
+        let mut skin_color: Rgb565 =  hex_to_rgb565(0x8EB34E); //mid-lightness of CSS_DARK_OLIVE_GREEN
+        let mut emotion = EmotionExpression::Neutral;
+        let iris_color = match self {
+            TestModeA::HStep => Rgb565::CSS_SLATE_GRAY,
+            TestModeA::VStep => Rgb565::CSS_LIME_GREEN,
+            TestModeA::HSweep => Rgb565::CSS_DARK_TURQUOISE,
+            TestModeA::VSweep => Rgb565::CSS_GOLDENROD,
+            TestModeA::SurpriseHSweep => {
+                emotion = EmotionExpression::Surprise;
+                skin_color = Rgb565::CSS_ORANGE;
+                IRIS_PALETTE_PURPLE[counter % IRIS_PALETTE_PURPLE.len()]
+            }
+            TestModeA::Meander => hex_to_rgb565(0x405D80),
+            TestModeA::SlowRandMeander => Rgb565::CSS_CHOCOLATE,
+            TestModeA::ClockStar => Rgb565::CSS_DEEP_SKY_BLUE,
+            TestModeA::Randomize => Rgb565::new(rand_bytes[0], rand_bytes[1], rand_bytes[2]),
+            TestModeA::MaxCount => unreachable!(),
+        };
+        ModeAppearance { iris_color, skin_color, emotion }
+    }
+
+    /// Provide the gaze transition and look step for this mode, given a counter.
+    /// The random modes expect the caller to provide a random counter.
+    /// Modes with `gaze_targets` are normally driven by a `GazeController` instead.
+    pub fn gaze_transition(self, counter: usize) -> GazeTransition {
+        let from_center = |(dir, step): (GazeDirection, u8)| GazeTransition::from_center(dir, step);
+        match self {
+            TestModeA::HStep | TestModeA::HSweep | TestModeA::SurpriseHSweep => {
+                from_center(GazeDirection::gaze_and_step_for_hsweep(counter))
+            }
+            TestModeA::VStep | TestModeA::VSweep => {
+                from_center(GazeDirection::gaze_and_step_for_vsweep(counter))
+            }
+            TestModeA::ClockStar => {
+                from_center(GazeDirection::gaze_and_step_for_sparse_star(counter))
+            }
+            TestModeA::Meander => {
+                GazeDirection::gaze_transition_for_meander(counter)
+            }
+            TestModeA::SlowRandMeander | TestModeA::Randomize => {
+                from_center(GazeDirection::gaze_and_look_for_meander(counter))
+            }
+            TestModeA::MaxCount => unreachable!(),
+        }
+    }
+
+    /// The fixation targets for modes whose gaze is driven by a `GazeController` in real time,
+    /// or None for test pattern modes that step through `gaze_transition` by frame counter.
+    pub fn gaze_targets(self) -> Option<GazeTargets> {
+        match self {
+            TestModeA::Meander => Some(GazeTargets::Sequence(&GazeDirection::CARDINAL_H8_ORDER)),
+            TestModeA::SlowRandMeander | TestModeA::Randomize => Some(GazeTargets::Random),
+            _ => None,
+        }
+    }
+}
+
+
+// The background table, generated by build_support/bg_assets.rs from img/eye_backgrounds.txt
+include!(concat!(env!("OUT_DIR"), "/emotion_bg_assets.rs"));
+
+/// The background images at each end of an emotion blend, for one eye.
+/// None means the plain skin color.
+pub struct EmotionBackgrounds {
+    from: Option<Qoi<'static>>,
+    to: Option<Qoi<'static>>,
+    /// Both ends have the same background, so there's nothing to cross-fade
+    same: bool,
+}
+
+impl EmotionBackgrounds {
+    pub fn for_blend(emotion: EmotionBlend, is_left: bool) -> Self {
+        let from_bytes = get_emotion_bg_bytes(emotion.from, is_left);
+        let to_bytes = get_emotion_bg_bytes(emotion.to, is_left);
+        let same = match (from_bytes, to_bytes) {
+            (Some(from), Some(to)) => core::ptr::eq(from, to),
+            (from, to) => from.is_none() && to.is_none(),
+        };
+        let qoi = |bytes: Option<&'static [u8]>| bytes.and_then(|bytes| Qoi::new(bytes).ok());
+        Self { from: qoi(from_bytes), to: qoi(to_bytes), same }
+    }
+}
+
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+#[repr(u32)]
+pub enum SvgFileId {
+    EyeLeft,
+    EyeRight,
+    SvgFileIdCount
+}
+
+impl SvgFileId {
+    pub fn for_eye_side(is_left: bool) -> Self {
+        if is_left { SvgFileId::EyeLeft } else { SvgFileId::EyeRight }
+    }
+}
+
+
+pub fn get_svg_path_by_id(file_id: SvgFileId, path_id: &str) -> Option<&'static ClosedPolygon<'static>> {
+    match file_id {
+        SvgFileId::EyeLeft => get_svg_path_by_id_file_EyeLeft(path_id),
+        SvgFileId::EyeRight => get_svg_path_by_id_file_EyeRight(path_id),
+        _ => { warn!("Missing: {}", path_id); None }
+    }
+}
+
+pub fn get_svg_path_by_id_checked(file_id: SvgFileId, path_id: &str) -> Option<&'static ClosedPolygon<'static>> {
+    let check = get_svg_path_by_id(file_id, path_id);
+    if check.is_none() {
+        warn!("No path for  {}:{}", file_id, path_id);
+    }
+    check
+}
+
+/// Convert an RGB888 hex code (commonly used for defining colors) and convert to RGB565
+pub fn hex_to_rgb565(hex_color: u32) -> Rgb565 {
+    // Extract 8-bit R, G, B components
+    let r_8bit = ((hex_color >> 16) & 0xFF) as u8;
+    let g_8bit = ((hex_color >> 8) & 0xFF) as u8;
+    let b_8bit = (hex_color & 0xFF) as u8;
+
+    // Convert to 5-bit R, 6-bit G, 5-bit B for Rgb565
+    let r_5bit = r_8bit >> 3; // Take the most significant 5 bits
+    let g_6bit = g_8bit >> 2; // Take the most significant 6 bits
+    let b_5bit = b_8bit >> 3; // Take the most significant 5 bits
+
+    // Combine into a u16 and create Rgb565
+    let rgb565_value = ((r_5bit as u16) << 11) | ((g_6bit as u16) << 5) | (b_5bit as u16);
+
+    Rgb565::from(RawU16::new(rgb565_value))
+}
+
+/// The layers of an eye, in the order they're drawn, for choosing which get anti-aliased fills
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub enum EyeLayer {
+    /// The brow and other shapes over the background image
+    Background = 0,
+    Sclera = 1,
+    /// The iris, its shadow, the pupil and glints
+    Iris = 2,
+    Corners = 3,
+    LowerLid = 4,
+    /// The upper lid, its shadow and shine
+    UpperLid = 5,
+}
+
+/// A set of eye layers
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(target_os = "none", derive(defmt::Format))]
+pub struct EyeLayers(u8);
+
+impl EyeLayers {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self((1 << (EyeLayer::UpperLid as u8 + 1)) - 1);
+
+    pub const fn with(self, layer: EyeLayer) -> Self {
+        Self(self.0 | 1 << layer as u8)
+    }
+
+    pub const fn contains(self, layer: EyeLayer) -> bool {
+        self.0 & 1 << layer as u8 != 0
+    }
+}
+
+/// The layers whose fills are anti-aliased. Each edge pixel costs a read and blend,
+/// so only the curves where jagged edges show most are smoothed.
+pub const ANTI_ALIASED_LAYERS: EyeLayers =
+    EyeLayers::NONE.with(EyeLayer::Iris).with(EyeLayer::LowerLid).with(EyeLayer::UpperLid);
+
+/// The rows of a frame being drawn into: all of them, or a band of them for `BandRenderer`.
+/// Drawing uses frame coordinates, clipped to the rows held,
+/// and everything drawn is added to `dirty`.
+pub struct FrameRows<'a> {
+    pixels: &'a mut [u8],
+    first_row: i32,
+    num_rows: u32,
+    pub dirty: DirtyRect,
+    /// The layers drawn with anti-aliased fills, `ANTI_ALIASED_LAYERS` unless changed
+    pub anti_aliased: EyeLayers,
+    /// Whether the layer being drawn is anti-aliased
+    smoothing: bool,
+}
+
+impl<'a> FrameRows<'a> {
+    /// The whole frame
+    pub fn full(frame_buf: &'a mut FullFrameBuf) -> Self {
+        Self {
+            pixels: frame_buf.as_mut_slice(), first_row: 0, num_rows: DISPLAY_HEIGHT as u32, dirty: DirtyRect::new(),
+            anti_aliased: ANTI_ALIASED_LAYERS, smoothing: false,
+        }
+    }
+
+    /// The full width `band` of the frame, held in `pixels`, which must have room for it
+    pub fn band(pixels: &'a mut [u8], band: Rectangle) -> Self {
+        let len = band.size.height as usize * DISPLAY_WIDTH as usize * PIXEL_SIZE as usize;
+        Self {
+            pixels: &mut pixels[..len], first_row: band.top_left.y, num_rows: band.size.height, dirty: DirtyRect::new(),
+            anti_aliased: ANTI_ALIASED_LAYERS, smoothing: false,
+        }
+    }
+
+    /// The rows held, in frame coordinates
+    pub fn rows(&self) -> Rectangle {
+        Rectangle::new(Point::new(0, self.first_row), Size::new(DISPLAY_WIDTH as u32, self.num_rows))
+    }
+
+    /// Whether any of the rows from `min_y` to `max_y` are held
+    fn holds_any_of(&self, min_y: i32, max_y: i32) -> bool {
+        max_y >= self.first_row && min_y < self.first_row + self.num_rows as i32
+    }
+
+    fn raw_fb(&mut self) -> RawFrameBuf<Rgb565, &mut [u8]> {
+        RawFrameBuf::<Rgb565, &mut [u8]>::new(self.pixels, DISPLAY_WIDTH as usize, self.num_rows as usize)
+    }
+
+    /// Start drawing the shapes of `layer`, anti-aliased if it's one of `anti_aliased`
+    pub fn begin_layer(&mut self, layer: EyeLayer) {
+        self.smoothing = self.anti_aliased.contains(layer);
+    }
+
+    /// Draw a polygon in frame coordinates
+    fn draw_poly(&mut self, vertices: &[Point], style: &PrimitiveStyle<Rgb565>) {
+        let offset = Point::new(0, -self.first_row);
+        if self.smoothing {
+            fill_anti_aliased(vertices, |start, len, coverage| {
+                if let Some(color) = smooth_fill_color(style, coverage) {
+                    self.blend_span(start, len, color, coverage);
+                }
+            });
+            draw_smooth_outline(vertices, style, &mut self.raw_fb().translated(offset));
+        } else {
+            draw_styled_poly(vertices, style, &mut self.raw_fb().translated(offset));
+        }
+    }
+
+    /// Fill a run of `len` pixels along a row, in frame coordinates
+    fn fill_span(&mut self, start: Point, len: u32, color: Rgb565) {
+        if self.holds_any_of(start.y, start.y) {
+            let span = Rectangle::new(start - Point::new(0, self.first_row), Size::new(len, 1));
+            let _ = self.raw_fb().fill_solid(&span, color);
+        }
+    }
+
+    /// Blend `color` over a run of `len` pixels along a row, in frame coordinates,
+    /// by `coverage` (see `fill_anti_aliased`)
+    fn blend_span(&mut self, start: Point, len: u32, color: Rgb565, coverage: u8) {
+        if coverage >= FULL_COVERAGE {
+            return self.fill_span(start, len, color);
+        }
+        self.shade_span(start, len, coverage, |_| color);
+    }
+
+    /// Blend a color from `shade` for each pixel over a run of `len` pixels along a row, in frame coordinates,
+    /// by `coverage` (see `fill_anti_aliased`). `shade` is given each pixel's frame coordinates.
+    fn shade_span<S: Fn(Point) -> Rgb565>(&mut self, start: Point, len: u32, coverage: u8, shade: S) {
+        if !self.holds_any_of(start.y, start.y) {
+            return;
+        }
+        let row_start = (start.y - self.first_row) as usize * DISPLAY_WIDTH as usize;
+        for x in start.x.max(0)..(start.x + len as i32).min(DISPLAY_WIDTH as i32) {
+            let idx = (row_start + x as usize) * PIXEL_SIZE as usize;
+            let color = shade(Point::new(x, start.y));
+            let color = if coverage >= FULL_COVERAGE {
+                color
+            } else {
+                // stored big-endian, as `RawFrameBuf` does
+                let under = Rgb565::from(RawU16::new(u16::from_be_bytes([self.pixels[idx], self.pixels[idx + 1]])));
+                blend_rgb565(under, color, coverage)
+            };
+            self.pixels[idx..idx + 2].copy_from_slice(&RawU16::from(color).into_inner().to_be_bytes());
+        }
+    }
+
+    /// Fill a polygon in frame coordinates with a color from `shade` for each pixel, with no outline
+    fn shade_poly<S: Fn(Point) -> Rgb565>(&mut self, vertices: &[Point], shade: S) {
+        let smoothing = self.smoothing;
+        fill_anti_aliased(vertices, |start, len, coverage| {
+            if smoothing {
+                self.shade_span(start, len, coverage, &shade);
+            } else if coverage >= FULL_COVERAGE / 2 {
+                // without smoothing, each pixel is in or out
+                self.shade_span(start, len, FULL_COVERAGE, &shade);
+            }
+        });
+    }
+
+    /// Fill every row held with the next pixels from `colors`
+    fn fill_rows<I: Iterator<Item = Rgb565>>(&mut self, colors: &mut I) {
+        let size = Size::new(DISPLAY_WIDTH as u32, self.num_rows);
+        let num_pixels = (size.width * size.height) as usize;
+        let _ = self.raw_fb().fill_contiguous(&Rectangle::new(Point::zero(), size), colors.take(num_pixels));
+        self.dirty.add_rect(self.rows());
+    }
+}
+
+/// The ID prefix of an emotion's variant of an asset, or the neutral prefix
+/// if that emotion doesn't change the asset in this file.
+/// Stepped assets are probed by their center keyframe, eg `upper_lid_bulge_happy_11`.
+fn emotion_variant_prefix(file_id: SvgFileId, prefix: &str, emotion: EmotionExpression, is_stepped: bool)
+    -> String<MAX_ASSET_NAME_LEN>
+{
+    let keyed = emotion_asset_prefix(prefix, emotion);
+    let authored = if is_stepped {
+        get_svg_path_by_id(file_id, &stepped_asset_name(&keyed, GazeDirection::StraightAhead, 0)).is_some()
+    } else {
+        get_svg_path_by_id(file_id, &keyed).is_some()
+    };
+    if authored { keyed } else { emotion_asset_prefix(prefix, EmotionExpression::Neutral) }
+}
+
+/// Find the keyframe asset for a gaze direction, falling back to the center asset
+/// for prefixes that don't vary in that direction.
+fn find_keyframe_asset(file_id: SvgFileId, id_prefix: &str, direction: GazeDirection)
+    -> Option<&'static ClosedPolygon<'static>>
+{
+    get_svg_path_by_id(file_id, &stepped_asset_name(id_prefix, direction, LAST_LOOK_STEP_IDX))
+        .or_else(|| get_svg_path_by_id(file_id, &stepped_asset_name(id_prefix, GazeDirection::StraightAhead, 0)))
+}
+
+/// Find the authored asset for a look step of a transition, traversed in either direction
+fn find_authored_step_asset(file_id: SvgFileId, id_prefix: &str,
+    start_direction: GazeDirection, end_direction: GazeDirection, look_step: u8)
+    -> Option<&'static ClosedPolygon<'static>>
+{
+    get_svg_path_by_id(file_id, &stepped_asset_name_full(id_prefix, start_direction, end_direction, look_step))
+        .or_else(|| get_svg_path_by_id(file_id,
+            &stepped_asset_name_full(id_prefix, end_direction, start_direction, LAST_LOOK_STEP_IDX - look_step)))
+}
+
+/// The outline of an asset at some point in a transition:
+/// either an authored path, or one synthesized by morphing authored paths.
+#[allow(clippy::large_enum_variant)] // morphed vertices live on the stack, there's no allocator
+pub enum AssetShape {
+    Authored(&'static ClosedPolygon<'static>),
+    Morphed(MorphVertices),
+}
+
+impl AssetShape {
+    pub fn vertices(&self) -> &[Point] {
+        match self {
+            AssetShape::Authored(cpoly) => cpoly.vertices(),
+            AssetShape::Morphed(vertices) => vertices,
+        }
+    }
+}
+
+/// Interpolate between two keyframe polygons.
+/// If the keyframes can't be morphed into each other, snap to the nearest one.
+fn morph_polys(start_cpoly: &'static ClosedPolygon<'static>, end_cpoly: &'static ClosedPolygon<'static>,
+    t: MorphFraction) -> AssetShape
+{
+    match t {
+        MorphFraction::START => AssetShape::Authored(start_cpoly),
+        MorphFraction::END => AssetShape::Authored(end_cpoly),
+        _ => match morph_vertices(start_cpoly.vertices(), end_cpoly.vertices(), t) {
+            Some(vertices) => AssetShape::Morphed(vertices),
+            None => AssetShape::Authored(if t <= MorphFraction::HALF { start_cpoly } else { end_cpoly }),
+        }
+    }
+}
+
+/// Interpolate between two resolved shapes.
+/// If they can't be morphed into each other, snap to the nearest one.
+fn morph_shapes(start: AssetShape, end: AssetShape, t: MorphFraction) -> AssetShape {
+    match morph_vertices(start.vertices(), end.vertices(), t) {
+        Some(vertices) => AssetShape::Morphed(vertices),
+        None => if t <= MorphFraction::HALF { start } else { end },
+    }
+}
+
+/// Fill and outline a polygon. Outlines wider than a pixel are drawn by `draw_thick_outline`,
+/// since embedded-graphics is very slow at those.
+fn draw_styled_poly<D: DrawTarget<Color = Rgb565>>(vertices: &[Point], style: &PrimitiveStyle<Rgb565>, target: &mut D) {
+    match style.stroke_color {
+        Some(stroke_color) if style.stroke_width > 1 => {
+            if let Some(fill_color) = style.fill_color {
+                let _ = ClosedPolygon::new(vertices).into_styled(PrimitiveStyle::with_fill(fill_color)).draw(target);
+            }
+            let _ = draw_thick_outline(vertices, style.stroke_width, style.stroke_alignment, stroke_color, target);
+        }
+        _ => {
+            let _ = ClosedPolygon::new(vertices).into_styled(*style).draw(target);
+        }
+    }
+}
+
+/// The outline color, if the style has an outline
+fn outline_color(style: &PrimitiveStyle<Rgb565>) -> Option<Rgb565> {
+    style.stroke_color.filter(|_| style.stroke_width > 0)
+}
+
+/// The color of an anti-aliased polygon's pixels with `coverage`: the fill color wholly inside it,
+/// and around its edge the outline color, if it has one, to blend into what's below.
+/// The outline is then drawn just inside the edge (see `draw_smooth_outline`) so the edge stays smooth.
+fn smooth_fill_color(style: &PrimitiveStyle<Rgb565>, coverage: u8) -> Option<Rgb565> {
+    match outline_color(style) {
+        Some(outline) if coverage < FULL_COVERAGE => Some(outline),
+        _ => style.fill_color,
+    }
+}
+
+/// Draw the outline of an anti-aliased polygon, inside its edge whatever the style's alignment
+fn draw_smooth_outline<D: DrawTarget<Color = Rgb565>>(vertices: &[Point], style: &PrimitiveStyle<Rgb565>, target: &mut D) {
+    if let Some(outline) = outline_color(style) {
+        let _ = draw_thick_outline(vertices, style.stroke_width, StrokeAlignment::Inside, outline, target);
+    }
+}
+
+/// Call `draw` with the vertices of a shape in the eye art where they land on the panel (see `VIEWPORT`)
+fn with_panel_vertices<F: FnOnce(&[Point])>(vertices: &[Point], draw: F) {
+    if VIEWPORT.is_identity() {
+        return draw(vertices);
+    }
+    match VIEWPORT.to_panel_vertices(vertices) {
+        Some(panel_vertices) => draw(&panel_vertices),
+        None => warn!("too many vertices to fit to the panel: {}", vertices.len()),
+    }
+}
+
+/// The box bounding a shape's vertices
+fn vertex_bounds(vertices: &[Point]) -> Rectangle {
+    let first = vertices.first().copied().unwrap_or_default();
+    let min = vertices.iter().fold(first, |min, point| min.component_min(*point));
+    let max = vertices.iter().fold(first, |max, point| max.component_max(*point));
+    Rectangle::with_corners(min, max)
+}
+
+/// The box bounding a shape's vertices where they land on the panel (see `VIEWPORT`)
+fn panel_bounds(vertices: &[Point]) -> Rectangle {
+    let bounds = vertex_bounds(vertices);
+    let bottom_right = bounds.bottom_right().unwrap_or(bounds.top_left);
+    Rectangle::with_corners(VIEWPORT.to_panel(bounds.top_left), VIEWPORT.to_panel(bottom_right))
+}
+
+/// Draw a polygon of the eye art into the frame with `draw`, given its vertices on the panel,
+/// adding the area it covers, and `margin` around it, to the frame's `dirty`.
+/// Shapes entirely outside the rows being drawn are skipped.
+fn draw_panel_poly<F: FnOnce(&mut FrameRows, &[Point])>(frame: &mut FrameRows, vertices: &[Point], margin: i32, draw: F) {
+    with_panel_vertices(vertices, |vertices| {
+        let min_y = vertices.iter().map(|point| point.y).min().unwrap_or(0) - margin;
+        let max_y = vertices.iter().map(|point| point.y).max().unwrap_or(0) + margin;
+        if !frame.holds_any_of(min_y, max_y) {
+            return;
+        }
+        draw(frame, vertices);
+        frame.dirty.add_points(vertices, margin as u32);
+    });
+}
+
+/// Draw a polygon of the eye art with the given outline into the frame, adding the area it covers to its `dirty`.
+/// Shapes entirely outside the rows being drawn are skipped.
+pub fn draw_vertices(frame: &mut FrameRows, vertices: &[Point], style: &PrimitiveStyle<Rgb565>) {
+    // whatever the stroke alignment, twice the stroke width leaves room for mitered corners
+    let margin = 2 * style.stroke_width as i32;
+    draw_panel_poly(frame, vertices, margin, |frame, vertices| frame.draw_poly(vertices, style));
+}
+
+/// Fill a polygon of the eye art with a color from `shade` for each pixel, given the pixel's place on the panel,
+/// adding the area it covers to the frame's `dirty`. Shapes entirely outside the rows being drawn are skipped.
+pub fn draw_shaded_vertices<S: Fn(Point) -> Rgb565>(frame: &mut FrameRows, vertices: &[Point], shade: S) {
+    draw_panel_poly(frame, vertices, 0, |frame, vertices| frame.shade_poly(vertices, shade));
+}
+
+/// Draw a shape morphed between any two assets (usually from the same prefix), with t from start to end.
+pub fn draw_morphed_asset(frame: &mut FrameRows, file_id: SvgFileId,
+    start_id: &str, end_id: &str, t: MorphFraction, style: &PrimitiveStyle<Rgb565>)
+{
+    match (get_svg_path_by_id_checked(file_id, start_id), get_svg_path_by_id_checked(file_id, end_id)) {
+        (Some(start_cpoly), Some(end_cpoly)) => draw_vertices(frame, morph_polys(start_cpoly, end_cpoly, t).vertices(), style),
+        _ => warn!("can't morph {} to {}", start_id, end_id),
+    }
+}
+
+/// Find the outline of the asset defined by the id and gaze transition.
+/// Authored assets are used where the transition lands exactly on a look step that has one.
+/// Between look steps, we morph between the authored steps on either side;
+/// for transitions without authored tweens, we morph between the start and end keyframes.
+pub fn resolve_stepped_asset(file_id: SvgFileId, id_prefix: &str, gaze: GazeTransition) -> Option<AssetShape> {
+    let (lower_step, upper_step, t) = match gaze.look_step() {
+        Some(step) => (step, step, MorphFraction::START),
+        None => gaze.progress.between_steps(LAST_LOOK_STEP_IDX),
+    };
+    let lower_opt = find_authored_step_asset(file_id, id_prefix, gaze.start, gaze.end, lower_step);
+    let upper_opt = find_authored_step_asset(file_id, id_prefix, gaze.start, gaze.end, upper_step);
+    if let (Some(lower_cpoly), Some(upper_cpoly)) = (lower_opt, upper_opt) {
+        return Some(morph_polys(lower_cpoly, upper_cpoly, t));
+    }
+
+    let start_opt = find_keyframe_asset(file_id, id_prefix, gaze.start);
+    let end_opt = find_keyframe_asset(file_id, id_prefix, gaze.end);
+    match (start_opt, end_opt) {
+        (Some(start_cpoly), Some(end_cpoly)) => Some(morph_polys(start_cpoly, end_cpoly, gaze.progress)),
+        (Some(cpoly), None) | (None, Some(cpoly)) => Some(AssetShape::Authored(cpoly)),
+        (None, None) => {
+            warn!("no asset for file {} prefix {} gaze {}", file_id, id_prefix, gaze);
+            None
+        }
+    }
+}
+
+/// Find the outline of the asset defined by the id, for any gaze.
+/// The gaze resolves to one or two arms of authored steps (see `GazeVector::resolve`),
+/// and where it falls between two arms, we morph between the shapes found on each.
+pub fn resolve_gaze_asset(file_id: SvgFileId, id_prefix: &str, gaze: GazeVector) -> Option<AssetShape> {
+    let blend = gaze.resolve();
+    match blend.weight {
+        MorphFraction::START => resolve_stepped_asset(file_id, id_prefix, blend.primary),
+        MorphFraction::END => resolve_stepped_asset(file_id, id_prefix, blend.secondary),
+        weight => match (resolve_stepped_asset(file_id, id_prefix, blend.primary),
+            resolve_stepped_asset(file_id, id_prefix, blend.secondary))
+        {
+            (Some(primary), Some(secondary)) => Some(morph_shapes(primary, secondary, weight)),
+            (primary, secondary) => primary.or(secondary),
+        },
+    }
+}
+
+/// Find the outline of an emotion-keyed asset, morphed along an emotion blend.
+/// `resolve` finds the shape for the variant prefix of one emotion (see `emotion_variant_prefix`),
+/// and the shapes for each end of the blend are morphed together.
+fn resolve_emotion_asset<F>(file_id: SvgFileId, id_prefix: &str, emotion: EmotionBlend, is_stepped: bool,
+    resolve: F) -> Option<AssetShape>
+    where F: Fn(&str) -> Option<AssetShape>
+{
+    let from_prefix = emotion_variant_prefix(file_id, id_prefix, emotion.from, is_stepped);
+    let to_prefix = emotion_variant_prefix(file_id, id_prefix, emotion.to, is_stepped);
+    if from_prefix == to_prefix || emotion.progress == MorphFraction::END {
+        return resolve(&to_prefix);
+    }
+    if emotion.progress == MorphFraction::START {
+        return resolve(&from_prefix);
+    }
+    match (resolve(&from_prefix), resolve(&to_prefix)) {
+        (Some(from), Some(to)) => Some(morph_shapes(from, to, emotion.progress)),
+        (from, to) => from.or(to),
+    }
+}
+
+/// Find the outline of an emotion-keyed asset that doesn't move with gaze, eg `eyebrow` or `lower_lid_bulge`
+fn resolve_fixed_emotion_asset(file_id: SvgFileId, id_prefix: &str, emotion: EmotionBlend, is_stepped: bool)
+    -> Option<AssetShape>
+{
+    resolve_emotion_asset(file_id, id_prefix, emotion, is_stepped, |prefix| {
+        let cpoly = if is_stepped {
+            get_svg_path_by_id_checked(file_id, &stepped_asset_name(prefix, GazeDirection::StraightAhead, 0))
+        } else {
+            get_svg_path_by_id_checked(file_id, prefix)
+        };
+        cpoly.map(AssetShape::Authored)
+    })
+}
+
+/// Find the outline of an emotion-keyed asset for any gaze, see `resolve_gaze_asset`
+fn resolve_emotion_gaze_asset(file_id: SvgFileId, id_prefix: &str, emotion: EmotionBlend, gaze: GazeVector)
+    -> Option<AssetShape>
+{
+    resolve_emotion_asset(file_id, id_prefix, emotion, true, |prefix| resolve_gaze_asset(file_id, prefix, gaze))
+}
+
+/// Draw the asset defined by the id and gaze, see `resolve_gaze_asset`
+pub fn draw_gaze_asset(frame: &mut FrameRows,
+    file_id: SvgFileId,
+    id_prefix: &str,
+    gaze: GazeVector,
+    style: &PrimitiveStyle<Rgb565>)
+{
+    if let Some(shape) = resolve_gaze_asset(file_id, id_prefix, gaze) {
+        draw_vertices(frame, shape.vertices(), style);
+    }
+}
+
+/// Draw the asset defined by the id and gaze transition, see `resolve_stepped_asset`
+pub fn draw_stepped_asset(frame: &mut FrameRows,
+    file_id: SvgFileId,
+    id_prefix: &str,
+    gaze: GazeTransition,
+    style: &PrimitiveStyle<Rgb565>)
+{
+    if let Some(shape) = resolve_stepped_asset(file_id, id_prefix, gaze) {
+        draw_vertices(frame, shape.vertices(), style);
+    }
+}
+
+/// Lookup the preloaded ClosedPolygon and then draw it into the buffer with the style provided.
+pub fn draw_closed_poly(frame: &mut FrameRows, file_id: SvgFileId, path_id: &str, style: &PrimitiveStyle<Rgb565>) {
+    if let Some(cpoly) = get_svg_path_by_id_checked(file_id,path_id) {
+        draw_vertices(frame, cpoly.vertices(), style);
+    }
+}
+
+/// convenience for logging / debuggin
+pub fn debug_tag_for_eye_side(is_left: bool) -> &'static str {
+    if is_left {"left"} else {"right"}
+}
+
+/// Running average of how long one part of the redraw takes per frame, logged every thousand frames.
+/// Only the right eye is timed, since both take about as long.
+/// A part drawn a band at a time adds up its time over every band, until `end_frame`.
+pub struct RedrawBench {
+    name: &'static str,
+    run_count: AtomicUsize,
+    total_elapsed_micros: AtomicUsize,
+}
+
+impl RedrawBench {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, run_count: AtomicUsize::new(0), total_elapsed_micros: AtomicUsize::new(0) }
+    }
+
+    /// Add the time since `start_micros` to this frame's, returning when it finished (to start timing the next part)
+    pub fn record(&self, is_left: bool, start_micros: u64) -> u64 {
+        let end_micros = now_micros();
+        if !is_left {
+            self.total_elapsed_micros.fetch_add((end_micros - start_micros) as usize, Ordering::Relaxed);
+        }
+        end_micros
+    }
+
+    /// Count the frame's time as one run, once every part of the frame has been drawn
+    pub fn end_frame(&self, is_left: bool) {
+        if is_left {
+            return;
+        }
+        let total_runs = self.run_count.fetch_add(1, Ordering::Relaxed) + 1;
+        if total_runs >= 1000 {
+            let _avg_elapsed_micros = self.total_elapsed_micros.swap(0, Ordering::Relaxed) / total_runs;
+            info!("{} {} redraw {}µs", debug_tag_for_eye_side(is_left), self.name, _avg_elapsed_micros);
+            // reset benchmarker
+            self.run_count.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+static BACKGROUND_SHAPES_BENCH: RedrawBench = RedrawBench::new("bg shapes");
+static INNER_BENCH: RedrawBench = RedrawBench::new("inner");
+static SCLERA_BENCH: RedrawBench = RedrawBench::new("sclera");
+static IRIS_BENCH: RedrawBench = RedrawBench::new("iris");
+static OVERLAY_BENCH: RedrawBench = RedrawBench::new("overlay");
+static CORNERS_BENCH: RedrawBench = RedrawBench::new("corners");
+static LOWER_LID_BENCH: RedrawBench = RedrawBench::new("lower lid");
+static UPPER_LID_BENCH: RedrawBench = RedrawBench::new("upper lid");
+static RESOLVE_BENCH: RedrawBench = RedrawBench::new("eyeball shapes");
+/// Every part of `render_eyeball_layers` that's timed
+static EYEBALL_BENCHES: [&RedrawBench; 8] = [&RESOLVE_BENCH,
+    &INNER_BENCH, &SCLERA_BENCH, &IRIS_BENCH, &OVERLAY_BENCH, &CORNERS_BENCH, &LOWER_LID_BENCH, &UPPER_LID_BENCH];
+
+/// Every pixel of a background image, or endless skin color if there's none
+fn background_pixels<'a>(qoi: Option<&'a Qoi<'a>>, skin_color: Rgb888) -> impl Iterator<Item = Rgb888> + 'a {
+    qoi.map(|qoi| qoi.pixels()).into_iter().flatten().chain(core::iter::repeat(skin_color))
+}
+
+/// Every pixel of the frame's background, in order: the emotion's background image (if any) or the skin color,
+/// cross-faded between the backgrounds at each end of a blend, and fit to the panel (see `VIEWPORT`).
+/// Both images are decoded in step, a pixel at a time, since there's no room for a second frame.
+fn background_stream<'a>(backgrounds: &'a EmotionBackgrounds, emotion: EmotionBlend, skin_color: Rgb565)
+    -> impl Iterator<Item = Rgb565> + 'a
+{
+    let (from_qoi, to_qoi, t) =
+        if backgrounds.same || emotion.progress == MorphFraction::END {
+            (None, backgrounds.to.as_ref(), MorphFraction::END)
+        }
+        else if emotion.progress == MorphFraction::START {
+            (None, backgrounds.from.as_ref(), MorphFraction::END)
+        }
+        else {
+            (backgrounds.from.as_ref(), backgrounds.to.as_ref(), emotion.progress)
+        };
+    let skin_color = Rgb888::from(skin_color);
+    let art_pixels = background_pixels(from_qoi, skin_color).zip(background_pixels(to_qoi, skin_color))
+        .map(move |(from, to)| {
+            if t == MorphFraction::END { Rgb565::from(to) } else { Rgb565::from(mix_rgb888(from, to, t)) }
+        });
+    ViewportPixels::new(VIEWPORT, art_pixels)
+}
+
+/**
+ * Fill the frame with the emotion background image (if any) or the skin color,
+ * cross-fading between the backgrounds of a blend, then draw the background shapes (brow etc) on top.
+ */
+pub fn render_background_layer(is_left: bool, backgrounds: &EmotionBackgrounds, _gaze_dir: GazeDirection,
+    emotion: EmotionBlend, skin_color: Rgb565, frame: &mut FrameRows)
+{
+    frame.fill_rows(&mut background_stream(backgrounds, emotion, skin_color));
+    draw_background_shapes(is_left, &resolve_background_shapes(is_left, emotion), frame);
+    BACKGROUND_SHAPES_BENCH.end_frame(is_left);
+}
+
+/**
+ * Draw the eyeball (sclera, iris &c) and then everything that overlays it (lids &c)
+ */
+#[allow(clippy::too_many_arguments)] // the same per-frame parameters as the other layers
+pub fn render_eyeball_layers(is_left: bool, gaze: GazeVector, emotion: EmotionBlend, lid_closure: MorphFraction,
+    pupil_size: PupilSize, iris_color: Rgb565, skin_color: Rgb565, layers: &mut LayerCache, frame: &mut FrameRows)
+{
+    let shapes = resolve_eyeball_shapes(is_left, gaze, emotion, lid_closure, pupil_size);
+    draw_eyeball_layers(is_left, &shapes, emotion, iris_color, skin_color, layers, frame);
+    EYEBALL_BENCHES.iter().for_each(|bench| bench.end_frame(is_left));
+}
+
+/// `render_eyeball_layers` for one band or the whole frame, leaving the frame's benchmarks to the caller
+fn draw_eyeball_layers(is_left: bool, shapes: &EyeballShapes, emotion: EmotionBlend,
+    iris_color: Rgb565, skin_color: Rgb565, layers: &mut LayerCache, frame: &mut FrameRows)
+{
+    // cached for the emotion being blended to; the lower lid is drawn from polygons until the blend ends
+    layers.update(is_left, emotion.to, skin_color, frame.anti_aliased);
+    draw_inner_eye_shapes(is_left, shapes, iris_color, layers, frame);
+    draw_eyeball_overlay_shapes(is_left, shapes, skin_color, layers, frame);
+}
+
+/// Renders a whole frame for one eye a band of rows at a time, top to bottom, for when there's
+/// no room for a full frame buffer. Each band gets exactly the pixels that `render_background_layer`
+/// then `render_eyeball_layers` would draw into those rows of a full frame.
+/// The shapes are resolved once for the frame, so each band only rasterizes them.
+pub struct BandRenderer<'a, B> {
+    is_left: bool,
+    emotion: EmotionBlend,
+    iris_color: Rgb565,
+    skin_color: Rgb565,
+    background_shapes: BackgroundShapes,
+    eyeball_shapes: EyeballShapes,
+    layers: &'a mut LayerCache,
+    /// The background, decoded as far as the next band
+    background: B,
+}
+
+/// Start rendering a frame in bands, with the same parameters as the full frame layers
+#[allow(clippy::too_many_arguments)] // the same per-frame parameters as the layers
+pub fn band_renderer<'a>(is_left: bool, backgrounds: &'a EmotionBackgrounds, gaze: GazeVector, emotion: EmotionBlend,
+    lid_closure: MorphFraction, pupil_size: PupilSize, iris_color: Rgb565, skin_color: Rgb565, layers: &'a mut LayerCache)
+    -> BandRenderer<'a, impl Iterator<Item = Rgb565> + 'a>
+{
+    BandRenderer {
+        is_left, emotion, iris_color, skin_color, layers,
+        background_shapes: resolve_background_shapes(is_left, emotion),
+        eyeball_shapes: resolve_eyeball_shapes(is_left, gaze, emotion, lid_closure, pupil_size),
+        background: background_stream(backgrounds, emotion, skin_color),
+    }
+}
+
+impl<B: Iterator<Item = Rgb565>> BandRenderer<'_, B> {
+    /// Render the next full width `band` of the frame into `pixels`, returning the bytes used.
+    /// Bands must follow on from each other, starting from the top row.
+    pub fn render_band(&mut self, pixels: &mut [u8], band: Rectangle) -> usize {
+        let mut frame = FrameRows::band(pixels, band);
+        frame.fill_rows(&mut self.background);
+        draw_background_shapes(self.is_left, &self.background_shapes, &mut frame);
+        draw_eyeball_layers(self.is_left, &self.eyeball_shapes, self.emotion,
+            self.iris_color, self.skin_color, self.layers, &mut frame);
+        // time the whole frame, not each band
+        if band.top_left.y + band.size.height as i32 >= DISPLAY_HEIGHT as i32 {
+            BACKGROUND_SHAPES_BENCH.end_frame(self.is_left);
+            EYEBALL_BENCHES.iter().for_each(|bench| bench.end_frame(self.is_left));
+        }
+        frame.pixels.len()
+    }
+}
+
+/// The shapes drawn over the background image for one frame, see `resolve_background_shapes`
+pub struct BackgroundShapes {
+    test_ellipse: Option<&'static ClosedPolygon<'static>>,
+    brow: Option<AssetShape>,
+}
+
+/// Find the shapes drawn over the background for the frame's emotion
+pub fn resolve_background_shapes(is_left: bool, emotion: EmotionBlend) -> BackgroundShapes {
+    let start_micros = now_micros();
+    let file_id = SvgFileId::for_eye_side(is_left);
+    // TODO ensure that this ellipse is also reflected correctly on right eye
+    let test_ellipse = if is_left { get_svg_path_by_id_checked(file_id, "grande_ellipse") } else { None };
+    let brow = resolve_fixed_emotion_asset(file_id, "eyebrow", emotion, false);
+    BACKGROUND_SHAPES_BENCH.record(is_left, start_micros);
+    BackgroundShapes { test_ellipse, brow }
+}
+
+pub fn draw_background_shapes(is_left: bool, shapes: &BackgroundShapes, frame: &mut FrameRows)
+{
+    let start_micros = now_micros();
+
+    let brow_style = PrimitiveStyleBuilder::new()
+        .fill_color( Rgb565::CSS_BLACK )
+        .stroke_color(Rgb565::BLACK)
+        .stroke_width(1)
+        .stroke_alignment(StrokeAlignment::Center)
+        .build();
+    let test_ellipse_style  = PrimitiveStyleBuilder::new()
+        .fill_color( Rgb565::CSS_LIGHT_GREEN )
+        .stroke_color(Rgb565::BLACK)
+        .build();
+
+    frame.begin_layer(EyeLayer::Background);
+    if let Some(test_ellipse) = shapes.test_ellipse {
+        draw_vertices(frame, test_ellipse.vertices(), &test_ellipse_style);
+    }
+
+    // The eyebrow covers a lot of area, so we don't want to redraw too often
+    if let Some(brow) = &shapes.brow {
+        draw_vertices(frame, brow.vertices(), &brow_style);
+    }
+
+    BACKGROUND_SHAPES_BENCH.record(is_left, start_micros);
+}
+
+/// Seeds for each eye's iris texture, so the two eyes' patterns differ, as real eyes' do
+const LEFT_IRIS_SEED: u32 = 0x6C1F_4E27;
+const RIGHT_IRIS_SEED: u32 = 0x3B9D_A851;
+
+/// The sclera never changes in our model
+fn sclera_style() -> PrimitiveStyle<Rgb565> {
+    PrimitiveStyle::with_fill(hex_to_rgb565(0xf4eed7))
+}
+
+fn corner_style() -> PrimitiveStyle<Rgb565> {
+    PrimitiveStyle::with_fill(hex_to_rgb565(0x24102f)) // TODO
+}
+
+/// The lower lid bulge and shine styles
+fn lower_lid_styles(skin_color: Rgb565) -> (PrimitiveStyle<Rgb565>, PrimitiveStyle<Rgb565>) {
+    let slightly_brighter_skin = adjust_lightness_rgb565(skin_color, FACTOR_BRIGHTEN_20);
+    let slightly_darker_skin = adjust_lightness_rgb565(skin_color, FACTOR_DARKEN_20);
+
+    let lower_lid_bulge_style = PrimitiveStyleBuilder::new()
+        .fill_color(slightly_darker_skin)
+        .stroke_color(skin_color)
+        .stroke_width(1)
+        .build();
+
+    let lower_lid_shine_style = PrimitiveStyleBuilder::new()
+        .fill_color(slightly_brighter_skin)
+        .stroke_color(Rgb565::CSS_BLACK)
+        .stroke_width(1)
+        .stroke_alignment(StrokeAlignment::Center)
+        .build();
+
+    (lower_lid_bulge_style, lower_lid_shine_style)
+}
+
+/// Every shape of the eyeball and its lids for one frame, found (and morphed, scaled, shifted and closed)
+/// for its gaze, emotion, pupil size and blink, ready to draw. See `resolve_eyeball_shapes`.
+pub struct EyeballShapes {
+    sclera: Option<&'static ClosedPolygon<'static>>,
+    iris: Option<AssetShape>,
+    iris_shadow: Option<AssetShape>,
+    pupil: Option<AssetShape>,
+    glints: [Option<AssetShape>; 2],
+    corners: [Option<&'static ClosedPolygon<'static>>; 2],
+    lower_lid: Option<AssetShape>,
+    lower_lid_shine: Option<AssetShape>,
+    upper_lid_shadow: Option<AssetShape>,
+    upper_lid_shine: Option<AssetShape>,
+    upper_lid: Option<AssetShape>,
+    /// Whether the lower lid is away from where it's cached, by a blink or an emotion blend
+    lower_lid_moved: bool,
+}
+
+/// Close part of an upper or lower eyelid by `lid_closure` (START is fully open),
+/// or None if it stays as it is. `lids` are the main upper and lower lid shapes, which define how the parts move.
+fn close_lid_part(part: &[Point], is_upper: bool, lids: Option<(&[Point], &[Point])>, lid_closure: MorphFraction)
+    -> Option<AssetShape>
+{
+    match (lids, lid_closure != MorphFraction::START) {
+        (Some((upper_lid, lower_lid)), true) =>
+            close_lid_vertices(part, is_upper, upper_lid, lower_lid, lid_closure).map(AssetShape::Morphed),
+        _ => None,
+    }
+}
+
+/// Find every shape of the eyeball and its lids for one frame.
+/// The pupil is scaled about the center of the iris, the glints on its rim move with it,
+/// and the lids and their parts are drawn closed by `lid_closure`, on top of their shape for the gaze.
+pub fn resolve_eyeball_shapes(is_left: bool, gaze: GazeVector, emotion: EmotionBlend, lid_closure: MorphFraction,
+    pupil_size: PupilSize) -> EyeballShapes
+{
+    let start_micros = now_micros();
+    let file_id = SvgFileId::for_eye_side(is_left);
+
+    let iris = resolve_gaze_asset(file_id, "iris", gaze);
+    let iris_bounds = iris.as_ref().map(|iris| vertex_bounds(iris.vertices()));
+    let pupil = resolve_gaze_asset(file_id, "pupil", gaze).map(|pupil| match iris_bounds {
+        Some(iris_bounds) if pupil_size != PupilSize::AUTHORED => {
+            match pupil_size.scale_about(pupil.vertices(), iris_bounds.center()) {
+                Some(scaled) => AssetShape::Morphed(scaled),
+                None => {
+                    warn!("too many pupil vertices to scale: {}", pupil.vertices().len());
+                    pupil
+                }
+            }
+        }
+        _ => pupil,
+    });
+    let glints = ["glint_lg", "glint_sm"].map(|glint_prefix| {
+        let glint = resolve_gaze_asset(file_id, glint_prefix, gaze)?;
+        let shift = iris_bounds.map_or(Point::zero(),
+            |iris_bounds| pupil_size.glint_shift(vertex_bounds(glint.vertices()), iris_bounds));
+        if shift == Point::zero() {
+            Some(glint)
+        } else {
+            translate_vertices(glint.vertices(), shift).map(AssetShape::Morphed)
+        }
+    });
+
+    // The main shape of each lid defines the band that stretches closed when blinking
+    // Each emotion may have its own lids, made of the same parts as neutral, which morph between emotions
+    let lower_lid = resolve_fixed_emotion_asset(file_id, "lower_lid_bulge", emotion, true);
+    let upper_lid = resolve_emotion_gaze_asset(file_id, "upper_lid_bulge", emotion, gaze);
+    let lids = match (&upper_lid, &lower_lid) {
+        (Some(upper_lid), Some(lower_lid)) => Some((upper_lid.vertices(), lower_lid.vertices())),
+        _ => None,
+    };
+    let close = |part: Option<AssetShape>, is_upper: bool| part.map(|part| {
+        close_lid_part(part.vertices(), is_upper, lids, lid_closure).unwrap_or(part)
+    });
+    let lower_lid_shine = close(resolve_fixed_emotion_asset(file_id, "lower_lid_shine", emotion, true), false);
+    let upper_lid_shadow = close(resolve_emotion_gaze_asset(file_id, "upper_lid_shadow", emotion, gaze), true);
+    let upper_lid_shine = close(resolve_emotion_gaze_asset(file_id, "upper_lid_shine", emotion, gaze), true);
+    let closed_lower_lid = lower_lid.as_ref().and_then(|lid| close_lid_part(lid.vertices(), false, lids, lid_closure));
+    let closed_upper_lid = upper_lid.as_ref().and_then(|lid| close_lid_part(lid.vertices(), true, lids, lid_closure));
+
+    let settled = emotion.from == emotion.to || emotion.progress == MorphFraction::END;
+    let shapes = EyeballShapes {
+        sclera: get_svg_path_by_id_checked(file_id, "sclera"),
+        iris_shadow: iris.as_ref().and_then(|_| resolve_gaze_asset(file_id, "iris_shadow_top", gaze)),
+        iris,
+        pupil,
+        glints,
+        corners: [get_svg_path_by_id_checked(file_id, "outer_corner_11"),
+            get_svg_path_by_id_checked(file_id, "inner_corner_11")],
+        lower_lid: closed_lower_lid.or(lower_lid),
+        lower_lid_shine,
+        upper_lid_shadow,
+        upper_lid_shine,
+        upper_lid: closed_upper_lid.or(upper_lid),
+        lower_lid_moved: lid_closure != MorphFraction::START || !settled,
+    };
+    RESOLVE_BENCH.record(is_left, start_micros);
+    shapes
+}
+
+pub fn draw_inner_eye_shapes(is_left:bool, shapes: &EyeballShapes, iris_color: Rgb565, layers: &LayerCache,
+    frame: &mut FrameRows)
+{
+    let start_micros = now_micros();
+
+    // In our model, the sclera never changes. Other things draw over this.
+    frame.begin_layer(EyeLayer::Sclera);
+    if !layers.composite(StaticLayer::Sclera, frame) {
+        if let Some(sclera) = shapes.sclera {
+            draw_vertices(frame, sclera.vertices(), &sclera_style());
+        }
+    }
+    let layer_start_micros = SCLERA_BENCH.record(is_left, start_micros);
+
+    frame.begin_layer(EyeLayer::Iris);
+    // the iris and the shadow the upper lid casts on it are textured, fitted to the iris and pupil on the panel
+    if let Some(iris) = &shapes.iris {
+        let iris_panel_bounds = panel_bounds(iris.vertices());
+        let pupil_panel_bounds = shapes.pupil.as_ref()
+            .map_or(Rectangle::new(iris_panel_bounds.center(), Size::zero()), |pupil| panel_bounds(pupil.vertices()));
+        let seed = if is_left { LEFT_IRIS_SEED } else { RIGHT_IRIS_SEED };
+        let shader = IrisTexture::new(iris_color, seed).shader(iris_panel_bounds, pupil_panel_bounds);
+        draw_shaded_vertices(frame, iris.vertices(), |at| shader.shade(at));
+        // a darker rim keeps the iris apart from the sclera where the limbal ring is faint
+        let iris_outline_style = PrimitiveStyleBuilder::new()
+            .stroke_color(adjust_lightness_rgb565(iris_color, FACTOR_DARKEN_10))
+            .stroke_width(1)
+            .stroke_alignment(StrokeAlignment::Center)
+            .build();
+        draw_vertices(frame, iris.vertices(), &iris_outline_style);
+        if let Some(shadow) = &shapes.iris_shadow {
+            draw_shaded_vertices(frame, shadow.vertices(),
+                |at| adjust_lightness_rgb565(shader.shade(at), FACTOR_DARKEN_10));
+        }
+    }
+    if let Some(pupil) = &shapes.pupil {
+        draw_vertices(frame, pupil.vertices(), &PrimitiveStyle::with_fill(Rgb565::BLACK));
+    }
+    for glint in shapes.glints.iter().flatten() {
+        draw_vertices(frame, glint.vertices(), &PrimitiveStyle::with_fill(Rgb565::WHITE));
+    }
+    IRIS_BENCH.record(is_left, layer_start_micros);
+
+    INNER_BENCH.record(is_left, start_micros);
+}
+
+/**
+ Draw shapes that overlay the eyeball (sclera and all) after drawing the iris &c.
+ Some overlay parts are inspired by reference to Moriyama et al paper.
+  - inner (toward nose) and outer eye corners - ref region5 and region6
+  - lower eyelid
+    - ref region2 and region3
+    - ref curve5 infraorbital furrow
+  - upper eyelid - ref region1
+
+  The lids themselves can be though of as consisting of multiple parts:
+  - bulge bright region
+  - infraorbital furrow
+
+  The lids are already closed by the blink, see `resolve_eyeball_shapes`.
+ */
+pub fn draw_eyeball_overlay_shapes(is_left:bool, shapes: &EyeballShapes, skin_color:Rgb565,
+    layers: &LayerCache, frame: &mut FrameRows) {
+    let start_micros = now_micros();
+
+    let upper_lid_skin = hex_to_rgb565(0x73369a); //TODO get this custom color elsewhere
+    let upper_lid_shine_color= adjust_lightness_rgb565(upper_lid_skin, FACTOR_BRIGHTEN_20);
+    let upper_lid_skin_darker = adjust_lightness_rgb565(upper_lid_skin, FACTOR_DARKEN_30);
+
+    let upper_lid_shine_style = PrimitiveStyleBuilder::new()
+        .fill_color(upper_lid_shine_color)
+        .stroke_color(upper_lid_skin_darker)
+        .stroke_width(2)
+        .stroke_alignment(StrokeAlignment::Center)
+        .build();
+
+    let upper_lid_style = PrimitiveStyleBuilder::new()
+        .fill_color(upper_lid_skin)
+        .build();
+
+    let upper_lid_shadow_style = PrimitiveStyleBuilder::new()
+        .fill_color(hex_to_rgb565(0x1d1c4f))
+        .build();
+
+    // draw the entire lower eyelid "module"
+    frame.begin_layer(EyeLayer::Corners);
+    if !layers.composite(StaticLayer::Corners, frame) {
+        for corner in shapes.corners.iter().flatten() {
+            draw_vertices(frame, corner.vertices(), &corner_style());
+        }
+    }
+    let layer_start_micros = CORNERS_BENCH.record(is_left, start_micros);
+
+    // the open lower lid is cached for the emotion being blended to, but blinks and blends move it
+    frame.begin_layer(EyeLayer::LowerLid);
+    let lower_lid_cached = !shapes.lower_lid_moved && layers.composite(StaticLayer::LowerLid, frame);
+    if !lower_lid_cached {
+        let (lower_lid_bulge_style, lower_lid_shine_style) = lower_lid_styles(skin_color);
+        if let Some(lower_lid) = &shapes.lower_lid {
+            draw_vertices(frame, lower_lid.vertices(), &lower_lid_bulge_style);
+        }
+        if let Some(lower_lid_shine) = &shapes.lower_lid_shine {
+            draw_vertices(frame, lower_lid_shine.vertices(), &lower_lid_shine_style);
+        }
+    }
+    let layer_start_micros = LOWER_LID_BENCH.record(is_left, layer_start_micros);
+
+    frame.begin_layer(EyeLayer::UpperLid);
+    if let Some(shadow) = &shapes.upper_lid_shadow {
+        draw_vertices(frame, shadow.vertices(), &upper_lid_shadow_style);
+    }
+    // TODO we paint the shine below the lid because we want a line width on top?
+    if let Some(shine) = &shapes.upper_lid_shine {
+        draw_vertices(frame, shine.vertices(), &upper_lid_shine_style);
+    }
+    if let Some(upper_lid) = &shapes.upper_lid {
+        draw_vertices(frame, upper_lid.vertices(), &upper_lid_style);
+    }
+    UPPER_LID_BENCH.record(is_left, layer_start_micros);
+
+    OVERLAY_BENCH.record(is_left, start_micros);
+}
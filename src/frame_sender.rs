@@ -0,0 +1,142 @@
+//!
+//! Sending frames to the displays from strip buffers (the `strip-buffer` feature),
+//! so that each core goes on to draw its next frame while the last is still going out over SPI.
+//!
+//! The redraw loop packs its dirty region, a band at a time, into whichever of its eye's
+//! strip buffers is free, and queues it. A sender task sends each queued band in a single
+//! address window, then hands the strip buffer back. The left eye's sender runs on a higher
+//! priority interrupt executor on core0. The right eye's runs on core1's thread mode executor
+//! beside the redraw loop, as an interrupt executor's wakers can fire on core0 and pend a
+//! software interrupt that core1 never sees; its bands go out whenever the redraw loop awaits.
+//! Two full frames per eye won't fit in RAM, but a couple of strips will (see build_support/ram_budget.rs).
+//!
+//! With the `band-render` feature there's no frame buffer to pack from: the redraw loop
+//! renders each band straight into a strip buffer instead.
+//!
+
+use defmt::unwrap;
+use embassy_executor::{InterruptExecutor, Spawner};
+use embassy_rp::interrupt;
+use embassy_rp::interrupt::{InterruptExt, Priority};
+use embassy_rp::peripherals::{SPI0, SPI1};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embedded_graphics::primitives::Rectangle;
+use static_cell::StaticCell;
+
+use eyemodelz::dirty_rect::bands;
+#[cfg(not(feature = "band-render"))]
+use eyemodelz::dirty_rect::pack_rect;
+
+#[cfg(not(feature = "band-render"))]
+use crate::eyerender::{FullFrameBuf, DISPLAY_WIDTH};
+use crate::RealDisplayType;
+
+// STRIP_BUFFER_BYTES and STRIP_BUFFERS_PER_EYE, checked against the RAM in memory.x
+include!(concat!(env!("OUT_DIR"), "/frame_memory.rs"));
+
+pub type StripBuf = [u8; STRIP_BUFFER_BYTES];
+
+/// One band of a frame's dirty region, packed into a strip buffer
+struct Band {
+    strip: &'static mut StripBuf,
+    rect: Rectangle,
+    len: usize,
+}
+
+/// The strip buffers of one eye, passed back and forth between its redraw loop and sender task
+pub struct StripQueue {
+    queued: Channel<CriticalSectionRawMutex, Band, STRIP_BUFFERS_PER_EYE>,
+    free: Channel<CriticalSectionRawMutex, &'static mut StripBuf, STRIP_BUFFERS_PER_EYE>,
+}
+
+impl StripQueue {
+    const fn new() -> Self {
+        Self { queued: Channel::new(), free: Channel::new() }
+    }
+
+    /// Queue the pixels of `rect` to be sent to the display, a band at a time, waiting for
+    /// a free strip buffer for each. Returns once the last band is queued, probably before it's sent,
+    /// after which the frame buffer can be drawn into again.
+    #[cfg(not(feature = "band-render"))]
+    pub async fn queue_rect(&self, frame_buf: &FullFrameBuf, rect: Rectangle) {
+        self.queue_bands(rect, |band, strip| pack_rect(frame_buf, DISPLAY_WIDTH as u32, band, strip)).await
+    }
+
+    /// Queue `rect` to be sent to the display a band at a time, top to bottom,
+    /// with `fill` writing each band's pixels into a free strip buffer and returning the bytes written
+    pub async fn queue_bands<F>(&self, rect: Rectangle, mut fill: F)
+    where F: FnMut(Rectangle, &mut [u8]) -> usize
+    {
+        for band in bands(rect, STRIP_BUFFER_BYTES) {
+            let strip = self.free.receive().await;
+            let len = fill(band, &mut strip[..]);
+            self.queued.send(Band { strip, rect: band, len }).await;
+        }
+    }
+}
+
+static LEFT_STRIPS: StaticCell<[StripBuf; STRIP_BUFFERS_PER_EYE]> = StaticCell::new();
+static RIGHT_STRIPS: StaticCell<[StripBuf; STRIP_BUFFERS_PER_EYE]> = StaticCell::new();
+static LEFT_QUEUE: StripQueue = StripQueue::new();
+static RIGHT_QUEUE: StripQueue = StripQueue::new();
+
+static LEFT_SENDER_EXECUTOR: InterruptExecutor = InterruptExecutor::new();
+
+#[interrupt]
+unsafe fn SWI_IRQ_0() {
+    LEFT_SENDER_EXECUTOR.on_interrupt()
+}
+
+/// Preempts the thread mode executor that draws, so bands go out while the next frame is drawn
+const SENDER_PRIORITY: Priority = Priority::P3;
+
+/// Send queued bands to the display as they arrive
+async fn send_strips<T>(mut display: RealDisplayType<T>, queue: &'static StripQueue) -> !
+where T: embassy_rp::spi::Instance
+{
+    loop {
+        let band = queue.queued.receive().await;
+        let Rectangle { top_left, size } = band.rect;
+        display
+            .show_raw_data(top_left.x as u16, top_left.y as u16,
+                size.width as u16, size.height as u16,
+                &band.strip[..band.len])
+            .await
+            .unwrap();
+        queue.free.send(band.strip).await;
+    }
+}
+
+#[embassy_executor::task]
+async fn left_sender_task(display: RealDisplayType<SPI0>, queue: &'static StripQueue) {
+    send_strips(display, queue).await
+}
+
+#[embassy_executor::task]
+async fn right_sender_task(display: RealDisplayType<SPI1>, queue: &'static StripQueue) {
+    send_strips(display, queue).await
+}
+
+fn fill_free_strips(queue: &'static StripQueue, strips: &'static mut [StripBuf; STRIP_BUFFERS_PER_EYE]) {
+    for strip in strips.iter_mut() {
+        unwrap!(queue.free.try_send(strip).ok());
+    }
+}
+
+/// Start sending the left eye's frames, on the calling core (core0), returning the queue to send them with
+pub fn start_left_sender(display: RealDisplayType<SPI0>) -> &'static StripQueue {
+    fill_free_strips(&LEFT_QUEUE, LEFT_STRIPS.init_with(|| [[0; STRIP_BUFFER_BYTES]; STRIP_BUFFERS_PER_EYE]));
+    interrupt::SWI_IRQ_0.set_priority(SENDER_PRIORITY);
+    let spawner = LEFT_SENDER_EXECUTOR.start(interrupt::SWI_IRQ_0);
+    unwrap!(spawner.spawn(left_sender_task(display, &LEFT_QUEUE)));
+    &LEFT_QUEUE
+}
+
+/// Start sending the right eye's frames, as a task on core1's executor `spawner`,
+/// returning the queue to send them with
+pub fn start_right_sender(spawner: &Spawner, display: RealDisplayType<SPI1>) -> &'static StripQueue {
+    fill_free_strips(&RIGHT_QUEUE, RIGHT_STRIPS.init_with(|| [[0; STRIP_BUFFER_BYTES]; STRIP_BUFFERS_PER_EYE]));
+    unwrap!(spawner.spawn(right_sender_task(display, &RIGHT_QUEUE)));
+    &RIGHT_QUEUE
+}
@@ -9,19 +9,18 @@
 
 use {defmt_rtt as _, panic_probe as _};
 
-use defmt::{info, warn, Format, unwrap};
+use defmt::{info, warn, unwrap};
 
 use embassy_rp::clocks::ClockConfig;
-use embedded_graphics::primitives::StrokeAlignment;
 
 use core::u8;
 use core::{default::Default};
-use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU8, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8, Ordering};
 
 use embassy_embedded_hal::shared_bus::asynch::spi::SpiDevice;
 use embassy_executor::{Spawner, Executor};
 use embassy_rp:: {
-    self as hal, block::ImageDef, gpio::{Input, Level, Output, Pull}, peripherals::{self, SPI0, SPI1}, pwm::{self, Pwm, SetDutyCycle}, spi::{self, Async, Spi},
+    self as hal, block::ImageDef, gpio::{Input, Level, Output, Pull}, peripherals::{SPI0, SPI1}, pwm::{self, Pwm, SetDutyCycle}, spi::{self, Async, Spi},
 };
 
 use embassy_sync::{blocking_mutex::{raw::{NoopRawMutex,CriticalSectionRawMutex}}, mutex::Mutex, pubsub::PubSubChannel, signal::Signal};
@@ -29,9 +28,8 @@ use embassy_time::{Delay, Instant, Timer};
 
 use embedded_graphics::{
     prelude::*,
-    image::Image,
     pixelcolor::{raw::RawU16, Rgb565}, 
-    primitives::{Polyline, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle}
+    primitives::Rectangle,
 };
 
 use embassy_rp::multicore::{Stack};
@@ -40,24 +38,36 @@ use static_cell::StaticCell;
 
 use lcd_async::{
     interface::SpiInterface,
-    models::ST7789,
-    options::{ColorInversion, Orientation, Rotation},
-    raw_framebuf::RawFrameBuf,
     Builder,
 };
 
-use tinyqoi::Qoi;
-use num_enum::TryFromPrimitive;
-
 
 // example/src/main.rs
 use closed_svg_path_proc::import_svg_paths;
-use closed_svg_path::{ClosedPolygon,ScanlineIntersections};
 
-// TODO move eyemodelz into a separate lib crate?
+use eyemodelz::*;
+use eyemodelz::backlight::{self, BacklightFade};
+use eyemodelz::blink::{BlinkController, BlinkKind, BlinkTiming, LidClosure};
+#[cfg(not(feature = "strip-buffer"))]
+use eyemodelz::dirty_rect::transfer_rect;
+use eyemodelz::emotion_blend::{EmotionBlend, EmotionBlender, DEFAULT_EMOTION_BLEND_MS};
+use eyemodelz::gaze_control::{GazeController, GazeMotion, GazeTargets, GazeTiming};
+use eyemodelz::pupil::{PupilAnimator, PupilSize, PupilTiming};
+
+// Rendering is shared with the host-side simulator, see `eyesim`
 #[allow(dead_code)]
-mod eyemodelz;
-use crate::eyemodelz::*;
+mod eyerender;
+use crate::eyerender::*;
+
+mod usb_command;
+mod settings_flash;
+#[cfg(feature = "strip-buffer")]
+mod frame_sender;
+mod display_config;
+mod board;
+#[cfg(feature = "ambient-light")]
+mod ambient_light;
+use crate::display_config::{DisplayConfig, PanelModel, PANEL_MODEL, LEFT_DISPLAY, RIGHT_DISPLAY};
 
 use {defmt_rtt as _, panic_probe as _};
 
@@ -81,53 +91,31 @@ pub static PICOTOOL_ENTRIES: [embassy_rp::binary_info::EntryAddr; 4] = [
 
 const DISPLAY_FREQ: u32 = 72_000_000; //apparently the fastest we can drive with short wire lengths
 
-const ORIGIN_POINT:Point = Point::new(0, 0);
-const DISPLAY_WIDTH: u16 =  320; 
-const DISPLAY_HEIGHT: u16 = 240; 
-const PIXEL_SIZE: u16 = 2; // RGB565 = 2 bytes per pixel
-const FRAME_SIZE_BYTES: usize = DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize * PIXEL_SIZE as usize;
-type FullFrameBuf = [u8; FRAME_SIZE_BYTES];
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, Format)]
-#[repr(u8)]
-enum TestModeA {
-    ClockStar = 0,
-    HStep = 1,
-    VStep = 2,
-    HSweep = 3,
-    VSweep = 4,
-    SurpriseHSweep = 5,
-    Meander = 6,
-    SlowRandMeander = 7,
-    Randomize = 8,
-    MaxCount
-}
-
 // const INTERFRAME_DELAY_MILLIS:usize = 100;
 const INTERFRAME_DELAY_MILLIS:usize = 50;
+// Redraw at least this often while a blink or saccade is in progress
+const BLINK_FRAME_GAP_MILLIS:usize = 20;
+// The time it takes to set up a display address window for a partial update,
+// in equivalent bytes of pixel data at DISPLAY_FREQ (roughly: three commands plus async SPI turnarounds)
+#[cfg(not(feature = "strip-buffer"))]
+const DISPLAY_WINDOW_OVERHEAD_BYTES: u32 = 256;
+// How long a change of emotion takes to blend from the old one to the new (0 to switch instantly)
+const EMOTION_BLEND_MILLIS: u32 = DEFAULT_EMOTION_BLEND_MS;
 
 const MAX_MODE_B_COUNT: u8 = GazeDirection::NUM_FULL_SWEEP_STEPS as u8;
 
-const IRIS_PALETTE_PURPLE: [Rgb565; 8] = [ 
-    Rgb565::CSS_INDIGO, 
-    Rgb565::CSS_REBECCAPURPLE,  
-    Rgb565::CSS_DARK_ORCHID,
-    Rgb565::CSS_BLUE_VIOLET,
-    Rgb565::CSS_MEDIUM_PURPLE,
-    Rgb565::CSS_MEDIUM_ORCHID,
-    Rgb565::CSS_VIOLET,
-    Rgb565::CSS_PLUM,
-
-];
-
-
-
-#[link_section = ".core1_stack"]
-static mut CORE1_STACK: Stack<4096> = Stack::new();
+// Rendering resolves each frame's morphed and blink-closed polygons up front (about 1K each, 11K all told),
+// which doesn't fit in the 4K SRAM9 bank, so core1's stack lives in main RAM.
+static mut CORE1_STACK: Stack<24576> = Stack::new();
 static EXECUTOR1: StaticCell<Executor> = StaticCell::new();
 
+#[cfg(not(feature = "band-render"))]
 static DISPLAY0_FRAMEBUF: StaticCell<FullFrameBuf> = StaticCell::new();
+#[cfg(not(feature = "band-render"))]
 static DISPLAY1_FRAMEBUF: StaticCell<FullFrameBuf> = StaticCell::new();
+// Each eye's static layers, pre-rasterized (too big for core1's stack)
+static DISPLAY0_LAYER_CACHE: StaticCell<LayerCache> = StaticCell::new();
+static DISPLAY1_LAYER_CACHE: StaticCell<LayerCache> = StaticCell::new();
 
         
 
@@ -137,11 +125,27 @@ static CUR_MODE_B: AtomicU8 = AtomicU8::new(0);
 static CUR_BRIGHTNESS_PCT: AtomicU8 = AtomicU8::new(50);
 static CUR_IRIS_COLOR: AtomicU16 = AtomicU16::new(0x18ff);
 static CUR_SKIN_COLOR: AtomicU16 = AtomicU16::new(0x000777);  
-static CUR_LOOK_STEP: AtomicU8 = AtomicU8::new(0);
 static CUR_BG_DIRTY: AtomicBool = AtomicBool::new(true);
 static CUR_IRIS_DIRTY: AtomicBool = AtomicBool::new(true);
-static CUR_EMOTION: AtomicU8 = AtomicU8::new(EmotionExpression::Neutral as u8);
-static CUR_GAZE_DIR: AtomicU8 = AtomicU8::new(GazeDirection::StraightAhead as u8);
+// A packed EmotionBlend, so that both eyes render the same point of a change of emotion
+static CUR_EMOTION: AtomicU32 = AtomicU32::new(EmotionBlend::steady(EmotionExpression::Neutral).to_bits());
+// A packed GazeVector, so that both axes update together
+static CUR_GAZE_VECTOR: AtomicU32 = AtomicU32::new(GazeVector::STRAIGHT_AHEAD.to_bits());
+static CUR_LID_CLOSURE_LEFT: AtomicU16 = AtomicU16::new(0);
+static CUR_LID_CLOSURE_RIGHT: AtomicU16 = AtomicU16::new(0);
+static CUR_PUPIL_PCT: AtomicU8 = AtomicU8::new(PupilSize::AUTHORED.pct());
+
+// Live overrides set by serial commands, applied on top of the current mode.
+// Values that aren't valid settings mean no override.
+const NO_OVERRIDE: u8 = u8::MAX;
+const NO_COLOR_OVERRIDE: u32 = u32::MAX;
+static OVERRIDE_GAZE_ACTIVE: AtomicBool = AtomicBool::new(false);
+static OVERRIDE_GAZE: AtomicU32 = AtomicU32::new(GazeVector::STRAIGHT_AHEAD.to_bits());
+static OVERRIDE_EMOTION: AtomicU8 = AtomicU8::new(NO_OVERRIDE);
+static OVERRIDE_IRIS_COLOR: AtomicU32 = AtomicU32::new(NO_COLOR_OVERRIDE);
+static OVERRIDE_SKIN_COLOR: AtomicU32 = AtomicU32::new(NO_COLOR_OVERRIDE);
+static OVERRIDE_BRIGHTNESS_PCT: AtomicU8 = AtomicU8::new(NO_OVERRIDE);
+static OVERRIDE_PUPIL_PCT: AtomicU8 = AtomicU8::new(NO_OVERRIDE);
 
 // Static signals that can be shared between tasks
 static EYE_DATA_READY_CHANNEL: PubSubChannel<embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex, usize, 4, 4, 1> = PubSubChannel::new();
@@ -149,127 +153,32 @@ static LEFT_EYE_DONE_SIGNAL: Signal<CriticalSectionRawMutex, usize> = Signal::ne
 static RIGHT_EYE_DONE_SIGNAL: Signal<CriticalSectionRawMutex, usize> = Signal::new();
 
 
-static NEUTRAL_EYEBG_BYTES: &[u8] = include_bytes!("../img/gradient_bg.qoi");
-const fn get_emotion_bg_bytes(emotion: EmotionExpression, is_left: bool) -> Option<&'static [u8]> {
-    match (is_left, emotion) {
-        // (true, EmotionExpression::Neutral) => Some(NEUTRAL_EYEBG_BYTES),
-        (false, EmotionExpression::Neutral) => Some(NEUTRAL_EYEBG_BYTES),
-        // (true, EmotionExpression::Surprise) => Some(include_bytes!("../img/eyebg-left-surprise.qoi")),
-        // (false, EmotionExpression::Surprise) => Some(include_bytes!("../img/eyebg-right-surprise.qoi")),
-        _ => None
-    }
-}
-
-
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive, Format)]
-#[repr(u32)]
-enum SvgFileId {
-    EyeLeft,
-    EyeRight,
-    SvgFileIdCount
-}
-
 import_svg_paths!(EyeLeft, "img/eyestack-left-gen.svg");
 import_svg_paths!(EyeRight, "img/eyestack-right-gen.svg");
 
-type RealDisplayType<T>=lcd_async::Display<SpiInterface<SpiDevice<'static, NoopRawMutex, Spi<'static, T, embassy_rp::spi::Async>, Output<'static>>, Output<'static>>, ST7789, Output<'static>>;
-
-// type Spi0CsnType = embassy_rp::Peri<'static,peripherals::PIN_4>;
-// type Spi1CsnType = embassy_rp::Peri<'static,peripherals::PIN_9>;
-type Spi0CsnType = embassy_rp::Peri<'static,peripherals::PIN_17>;
-type Spi1CsnType = embassy_rp::Peri<'static,peripherals::PIN_13> ;
-
-
-fn get_svg_path_by_id<'a>(file_id: SvgFileId, path_id: &'a str) -> Option<&'a ClosedPolygon<'a>> {
-    match file_id {
-        SvgFileId::EyeLeft => get_svg_path_by_id_file_EyeLeft(path_id),
-        SvgFileId::EyeRight => get_svg_path_by_id_file_EyeRight(path_id),
-        _ => { warn!("Missing: {}", path_id); None }
-    }
+/// Timestamp source for rendering benchmarks
+fn now_micros() -> u64 {
+    Instant::now().as_micros()
 }
 
-fn get_svg_path_by_id_checked<'a>(file_id: SvgFileId, path_id: &'a str) -> Option<&'a ClosedPolygon<'a>> {
-    let check = get_svg_path_by_id(file_id, path_id);
-    if check.is_none() {
-        warn!("No path for  {}:{}", file_id, path_id);
-    }
-    check
-}
+type RealDisplayType<T>=lcd_async::Display<SpiInterface<SpiDevice<'static, NoopRawMutex, Spi<'static, T, embassy_rp::spi::Async>, Output<'static>>, Output<'static>>, PanelModel, Output<'static>>;
 
-/// Convert an RGB888 hex code (commonly used for defining colors) and convert to RGB565
-fn hex_to_rgb565(hex_color: u32) -> Rgb565 {
-    // Extract 8-bit R, G, B components
-    let r_8bit = ((hex_color >> 16) & 0xFF) as u8;
-    let g_8bit = ((hex_color >> 8) & 0xFF) as u8;
-    let b_8bit = (hex_color & 0xFF) as u8;
+type Spi0CsnType = board::LeftCsPin;
+type Spi1CsnType = board::RightCsPin;
 
-    // Convert to 5-bit R, 6-bit G, 5-bit B for Rgb565
-    let r_5bit = r_8bit >> 3; // Take the most significant 5 bits
-    let g_6bit = g_8bit >> 2; // Take the most significant 6 bits
-    let b_5bit = b_8bit >> 3; // Take the most significant 5 bits
 
-    // Combine into a u16 and create Rgb565
-    let rgb565_value = ((r_5bit as u16) << 11) | ((g_6bit as u16) << 5) | (b_5bit as u16);
-
-    Rgb565::from(RawU16::new(rgb565_value))
-}
-
-fn render_one_bg_image<T>(
-    frame_buf: &mut FullFrameBuf, 
-    bg_img: &embedded_graphics::image::Image<'_, T>) 
-    where T: ImageDrawable,  Rgb565: From<<T as embedded_graphics::image::ImageDrawable>::Color>
-{      
-    let mut raw_fb =
-        RawFrameBuf::<Rgb565, _>::new(frame_buf.as_mut_slice(), DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize);
-    bg_img.draw(&mut raw_fb.color_converted()).unwrap(); 
-}
-
-/// Draw the asset defined by the id, gaze direction, and step.
-/// This assumes that stepped gaze transitions always begin at GazeDirection::StraightAhead
-/// and end at the provided gaze_direction. In order to perform a reverse transition
-/// (from gaze_direction to StraightAhead), you need to recalculate look_step appropriately.
-fn draw_stepped_asset(frame_buf: &mut FullFrameBuf, 
-    file_id: SvgFileId, 
-    id_prefix: &str, 
-    gaze_direction: GazeDirection,
-    look_step_idx: u8, 
-    style: &PrimitiveStyle<Rgb565>) 
-{
-    let mut asset_id = stepped_asset_name(id_prefix, gaze_direction, look_step_idx);
-    let cpoly_opt = 
-        if let Some(cpoly) = get_svg_path_by_id(file_id,&asset_id) { Some(cpoly) }
-            else {
-                // try using the extreme of the gaze direction arm
-                asset_id = stepped_asset_name(id_prefix, gaze_direction, LAST_LOOK_STEP_IDX);
-                if let Some(edge_cpoly) = get_svg_path_by_id(file_id, &asset_id) { Some(edge_cpoly) }
-                else {
-                    // try obtaining the center asset for this prefix
-                    asset_id = stepped_asset_name(id_prefix, GazeDirection::StraightAhead, 0);
-                    get_svg_path_by_id(file_id,&asset_id)
-                }
-            };
-
-    if let Some(cpoly) = cpoly_opt {
-        let mut raw_fb =
-            RawFrameBuf::<Rgb565, &mut [u8]>::new(frame_buf.as_mut_slice(), DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize);
-        let _ = cpoly.into_styled(*style).draw(&mut raw_fb);
-    }
-    else {
-        warn!("no asset for file {} prefix {} gaze {} step {}", file_id, id_prefix, gaze_direction, look_step_idx);
-    }
-}
+/// Fast enough that the backlight can't be seen (or filmed) flickering, with thousands of duty steps
+const BACKLIGHT_PWM_HZ: u32 = 25_000;
 
-/// Lookup the preloaded ClosedPolygon and then draw it into the buffer with the style provided.
-fn draw_closed_poly(frame_buf: &mut FullFrameBuf, file_id: SvgFileId, path_id: &str, style: &PrimitiveStyle<Rgb565>) {
-    if let Some(cpoly) = get_svg_path_by_id_checked(file_id,path_id) {
-        let mut raw_fb =
-            RawFrameBuf::<Rgb565, &mut [u8]>::new(frame_buf.as_mut_slice(), DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize);
-        let _ = cpoly.clone().into_styled(*style).draw(&mut raw_fb);
-    }
+/// The backlight PWM, counting at the system clock to `BACKLIGHT_PWM_HZ`, and dark until the first frame
+fn backlight_pwm_config(sysclk_hz: u32) -> pwm::Config {
+    let mut config = pwm::Config::default();
+    config.top = (sysclk_hz / BACKLIGHT_PWM_HZ - 1) as u16;
+    config.compare_a = 0;
+    config.compare_b = 0;
+    config
 }
 
-
 // ---- TASKS defined below ---
 
 const PUSHBUTTON_DEBOUNCE_DELAY:u64 = 20;
@@ -325,43 +234,19 @@ async fn main(spawner: Spawner) {
     info!("Start {} mhz core_volts {} total_fbuf_size = {}",CFG_SYSCLK_HZ/1_000_000, core_volts, total_fbuf_size);
 
 
+    // restore what the user chose before the last reset, before core1 starts running from flash
+    let (settings_store, settings) = settings_flash::load_settings(p.FLASH);
+    settings_flash::apply_settings(&settings);
+    let mut settings_autosave = settings_flash::SettingsAutosave::new(settings_store, settings);
+
     // prep for reading mode change events
-    CUR_MODE_A.store(TestModeA::Meander as u8, Ordering::Relaxed);
     CUR_MODE_B.store(GazeDirection::StraightAhead as u8, Ordering::Relaxed);
 
-    let mut led = Output::new(p.PIN_25, Level::High);
-
-    // // LCD display 0: ST7789V pins, flavor: original SPI0 
-    // let bl0 = p.PIN_7; // --> BL
-    // let rst0 = p.PIN_6; // --> RST
-    // let dcx0 = p.PIN_5; // --> DC
-    // let cs0 = p.PIN_4; // SPI0 CSN --> CS
-    // let sda0 = p.PIN_3; // SPI0 MosiPin --> DIN 
-    // let sck0 = p.PIN_2; // SPI0 SCK -->  CLK
-
-    // LCD display 0: ST7789V pins, flavor: SPI0 on opposite side of Pico-2 board from SPI1 
-    let sck0 = p.PIN_18; // SPI0 SCK -->  SCL/CLK
-    let sda0 = p.PIN_19; // SPI0 TX --> SDA/DIN 
-    let rst0 = p.PIN_20; // --> RST
-    let dcx0 = p.PIN_21; // --> DC
-    let bl0 = p.PIN_16; // --> BL
-    let cs0 = p.PIN_17; // SPI0 CSN --> CS
-
-    // // LCD display 1: ST7789V pins, flavor: original SPI1
-    // let bl1 = p.PIN_14;// --> BL
-    // let rst1 = p.PIN_13;// --> RST
-    // let dcx1 = p.PIN_12; // --> DC
-    // let sda1 = p.PIN_11; // SPI1 TX --> SDA/DIN
-    // let sck1 = p.PIN_10; // SPI1 SCK --> CLK
-    // let cs1 = p.PIN_9; // SPI1 CSN --> CS
-
-    // LCD display 1: ST7789V pins, flavori: linear SPI1
-    let bl1 = p.PIN_15;// --> BL
-    let dcx1 = p.PIN_14; // --> DC
-    let cs1 = p.PIN_13; // SPI1 CSN --> CS
-    let rst1 = p.PIN_12; // --> DC
-    let sda1 = p.PIN_11; // SPI1 TX --> SDA/DIN
-    let sck1 = p.PIN_10; // SPI1 SCK --> SCL/CLK
+    // the pins come from the board profile picked at build time, see build_support/board_pins.rs
+    let pins = board::take_board_pins!(p);
+    info!("board profile: {}", board::BOARD_NAME);
+
+    let mut led = Output::new(pins.led, Level::High);
 
     let mut display_config = spi::Config::default();
     display_config.frequency = DISPLAY_FREQ;
@@ -370,34 +255,43 @@ async fn main(spawner: Spawner) {
     warn!("display_freq: {}",DISPLAY_FREQ);
 
     let spi0: Spi<'_, embassy_rp::peripherals::SPI0, Async> = 
-        Spi::new_txonly(p.SPI0, sck0, sda0, p.DMA_CH0, display_config.clone());
-    let dcx0_out = Output::new(dcx0, Level::Low);
-    let rst0_out = Output::new(rst0, Level::Low);
-    // let bl0_pwm_out: Pwm<'_> = Pwm::new_output_b(p.PWM_SLICE3, bl0, pwm::Config::default());//TODO doesn't work?
-    let bl0_pwm_out: Pwm<'_> = Pwm::new_output_a(p.PWM_SLICE0, bl0, pwm::Config::default());//TODO doesn't work?
+        Spi::new_txonly(p.SPI0, pins.left.sck, pins.left.mosi, p.DMA_CH0, display_config.clone());
+    let dcx0_out = Output::new(pins.left.dc, Level::Low);
+    let rst0_out = Output::new(pins.left.rst, Level::Low);
+    let backlight_config = backlight_pwm_config(CFG_SYSCLK_HZ);
+    let bl0_pwm_out: Pwm<'_> = board::LeftDisplayPins::backlight_pwm(pins.left.backlight, pins.left.backlight_slice,
+        backlight_config.clone());
 
     let spi1: Spi<'_, embassy_rp::peripherals::SPI1, Async> = 
-        Spi::new_txonly(p.SPI1, sck1, sda1, p.DMA_CH1, display_config.clone());
-    let dcx1_out = Output::new(dcx1, Level::Low);
-    let rst1_out = Output::new(rst1, Level::Low);
+        Spi::new_txonly(p.SPI1, pins.right.sck, pins.right.mosi, p.DMA_CH1, display_config.clone());
+    let dcx1_out = Output::new(pins.right.dc, Level::Low);
+    let rst1_out = Output::new(pins.right.rst, Level::Low);
 
     embassy_rp::multicore::spawn_core1(p.CORE1, 
         unsafe { core::ptr::addr_of_mut!(CORE1_STACK).as_mut().unwrap() }, //safe because we touch this once
         move || {
             let executor1 = EXECUTOR1.init(Executor::new());
-            let bl1_pwm_out: Pwm<'static> = Pwm::new_output_b(p.PWM_SLICE7, bl1, pwm::Config::default());
+            let bl1_pwm_out: Pwm<'static> = board::RightDisplayPins::backlight_pwm(pins.right.backlight,
+                pins.right.backlight_slice, backlight_config);
             executor1.run(|spawner| {
-                spawner.spawn(core1_drawing_task(spi1, cs1, rst1_out, dcx1_out, bl1_pwm_out)).unwrap()
+                spawner.spawn(core1_drawing_task(spi1, pins.right.cs, rst1_out, dcx1_out, bl1_pwm_out)).unwrap()
             });
         }
     );
 
     // spawn the core0 drawing task
-    unwrap!(spawner.spawn(core0_drawing_task(spi0,cs0,rst0_out,dcx0_out,bl0_pwm_out)));
+    unwrap!(spawner.spawn(core0_drawing_task(spi0,pins.left.cs,rst0_out,dcx0_out,bl0_pwm_out)));
 
     // read mode button events
-    unwrap!(spawner.spawn(mode_a_button_task(Input::new(p.PIN_4, Pull::Up))));
-    unwrap!(spawner.spawn(mode_b_button_task(Input::new(p.PIN_8, Pull::Up))));
+    unwrap!(spawner.spawn(mode_a_button_task(Input::new(pins.mode_a_button, Pull::Up))));
+    unwrap!(spawner.spawn(mode_b_button_task(Input::new(pins.mode_b_button, Pull::Up))));
+
+    // accept commands over USB serial
+    usb_command::spawn_usb_command_tasks(&spawner, p.USB);
+
+    // follow the room's light with the backlight
+    #[cfg(feature = "ambient-light")]
+    ambient_light::spawn_light_sensor_task(&spawner, p.ADC, pins.light_sensor);
 
     let mut iris_dirty = false;
     let mut bg_dirty = true;
@@ -409,12 +303,26 @@ async fn main(spawner: Spawner) {
     let mut old_mode_a_val  = TestModeA::MaxCount;
     let mut old_mode_b_val  = u8::MAX;
     let mut emotion_val; // = EmotionExpression::Neutral ;
-    let mut cur_gaze_dir = GazeDirection::StraightAhead;
+    let mut cur_gaze = GazeTransition::from_center(GazeDirection::StraightAhead, 0);
+    let mut blink_seed_bytes = [0u8; 4];
+    rnd_src.fill_bytes(&mut blink_seed_bytes);
+    let mut blinker = BlinkController::new(settings.behavior.blink_timing(BlinkTiming::HUMAN),
+        u32::from_le_bytes(blink_seed_bytes), Instant::now().as_millis());
+    let mut last_lid_closure = LidClosure::OPEN;
+    let mut last_gaze = GazeVector::STRAIGHT_AHEAD;
+    let mut last_iris_color = Rgb565::BLACK;
+    let mut last_skin_color = Rgb565::BLACK;
+    let mut emotion_blender = EmotionBlender::new(EmotionExpression::Neutral, EMOTION_BLEND_MILLIS);
+    let mut last_emotion_blend = EmotionBlend::steady(EmotionExpression::MaxCount);
+    let mut pupil_animator = PupilAnimator::new(PupilTiming::HUMAN, PupilSize::AUTHORED, Instant::now().as_millis());
+    let mut last_pupil_size = PupilSize::AUTHORED;
+    let mut gaze_seed_bytes = [0u8; 4];
+    rnd_src.fill_bytes(&mut gaze_seed_bytes);
+    let mut gaze_controller = GazeController::new(settings.behavior.gaze_timing(GazeTiming::HUMAN), GazeTargets::Random,
+        u32::from_le_bytes(gaze_seed_bytes), Instant::now().as_millis());
 
     let eye_redraw_data_ready_pub = EYE_DATA_READY_CHANNEL.publisher().unwrap();
 
-    let mut look_step_idx = 0 ;
-
     info!("Config done, pause for tasks to start...");
 
     // allow other tasks to begin
@@ -427,146 +335,189 @@ async fn main(spawner: Spawner) {
         let mode_b_val = CUR_MODE_B.load(Ordering::Relaxed);
         let mut brightness_percent = CUR_BRIGHTNESS_PCT.load(Ordering::Relaxed);
         let mut frame_render_gap_millis = INTERFRAME_DELAY_MILLIS;
-        let iris_color: Rgb565;
-        let mut skin_color: Rgb565 =  hex_to_rgb565(0x8EB34E); //mid-lightness of CSS_DARK_OLIVE_GREEN
 
         // Let the user manually adjust the gaze direction using the MODE_B button
         let mut freeze_gaze_dir = false;
 
         match mode_a_val {
             TestModeA::HStep | TestModeA::VStep => {
-                iris_color = 
-                    if mode_a_val ==  TestModeA::HStep { Rgb565::CSS_SLATE_GRAY } 
-                    else { Rgb565::CSS_LIME_GREEN };
-                emotion_val = EmotionExpression::Neutral;
                 brightness_percent = 75; brightness_ascending = false;
                 frame_render_gap_millis = INTERFRAME_DELAY_MILLIS * 4;
                 freeze_gaze_dir = true;
             }
-            TestModeA::HSweep | TestModeA::VSweep => { 
-                iris_color = 
-                    if mode_a_val ==  TestModeA::HSweep { Rgb565::CSS_DARK_TURQUOISE } 
-                    else { Rgb565::CSS_GOLDENROD };
-                emotion_val = EmotionExpression::Neutral;
+            TestModeA::HSweep | TestModeA::VSweep | TestModeA::Meander => { 
                 brightness_percent = 75; brightness_ascending = false;
             }
             TestModeA::SurpriseHSweep => {
-                emotion_val = EmotionExpression::Surprise;
                 brightness_percent = 90; brightness_ascending = true;
-                let color_idx = main_loop_count % IRIS_PALETTE_PURPLE.len();
-                iris_color = IRIS_PALETTE_PURPLE[color_idx] ;
-                skin_color = Rgb565::CSS_ORANGE;
-            }
-            TestModeA::Meander => {
-                iris_color = hex_to_rgb565(0x405D80); 
-                emotion_val = EmotionExpression::Neutral;
-                brightness_percent = 75; brightness_ascending = false;
             }
             TestModeA::SlowRandMeander => {
-                iris_color = Rgb565::CSS_CHOCOLATE;
-                emotion_val = EmotionExpression::Neutral;
                 brightness_percent = 75; brightness_ascending = false;
                 frame_render_gap_millis = INTERFRAME_DELAY_MILLIS * 2;
             }
             TestModeA::ClockStar => {
-                iris_color = Rgb565::CSS_DEEP_SKY_BLUE;
-                emotion_val = EmotionExpression::Neutral;
                 brightness_percent = 75; brightness_ascending = false;
                 frame_render_gap_millis = INTERFRAME_DELAY_MILLIS / 4;
             }
             TestModeA::Randomize => {
-                emotion_val = EmotionExpression::Neutral;
                 frame_render_gap_millis = INTERFRAME_DELAY_MILLIS / 2;
-                let mut rng_bytes:[u8;4] = [0; 4];
-                rnd_src.fill_bytes(&mut rng_bytes);
-                iris_color = Rgb565::new(rng_bytes[0],rng_bytes[1],rng_bytes[2]);
             }
             _ => { unreachable!() }
         }
+
+        let mut rng_bytes:[u8;3] = [0; 3];
+        if mode_a_val == TestModeA::Randomize {
+            rnd_src.fill_bytes(&mut rng_bytes);
+        }
+        let appearance = mode_a_val.appearance(main_loop_count, rng_bytes);
+        let iris_color = match OVERRIDE_IRIS_COLOR.load(Ordering::Relaxed) {
+            NO_COLOR_OVERRIDE => appearance.iris_color,
+            raw => Rgb565::from(RawU16::new(raw as u16)),
+        };
+        let skin_color = match OVERRIDE_SKIN_COLOR.load(Ordering::Relaxed) {
+            NO_COLOR_OVERRIDE => appearance.skin_color,
+            raw => Rgb565::from(RawU16::new(raw as u16)),
+        };
+        emotion_val = OVERRIDE_EMOTION.load(Ordering::Relaxed).try_into().unwrap_or(appearance.emotion);
+
+        // serial overrides can change any of these independently of the mode
+        if iris_color != last_iris_color {
+            iris_dirty = true;
+            last_iris_color = iris_color;
+        }
+        if skin_color != last_skin_color {
+            bg_dirty = true;
+            iris_dirty = true;
+            last_skin_color = skin_color;
+        }
+
+        // emotion changes blend in over several frames, redrawing the brow, lids and background as they go
+        let emotion_blend = emotion_blender.update(emotion_val, Instant::now().as_millis());
+        if emotion_blend != last_emotion_blend {
+            bg_dirty = true;
+            iris_dirty = true;
+            last_emotion_blend = emotion_blend;
+        }
     
         if old_mode_a_val != mode_a_val  {
             info!("mode_a old: {} new: {}", old_mode_a_val, mode_a_val);
             iris_dirty = true;
             bg_dirty = true;
             old_mode_a_val = mode_a_val;
+            blinker.trigger(BlinkKind::Double, Instant::now().as_millis());
         }
 
         if old_mode_b_val != mode_b_val {
             info!("mode_b old: {} new: {}", old_mode_b_val, mode_b_val);
             iris_dirty = true;
             old_mode_b_val = mode_b_val;
+            let wink = if mode_b_val % 2 == 0 { BlinkKind::WinkLeft } else { BlinkKind::WinkRight };
+            blinker.trigger(wink, Instant::now().as_millis());
+        }
+
+        // blinks run on their own clock, over whatever gaze we're showing
+        let lid_closure = blinker.update(Instant::now().as_millis());
+        if lid_closure != last_lid_closure {
+            iris_dirty = true;
+            last_lid_closure = lid_closure;
+        }
+        if blinker.is_busy() || emotion_blender.is_busy(Instant::now().as_millis()) {
+            frame_render_gap_millis = frame_render_gap_millis.min(BLINK_FRAME_GAP_MILLIS);
         }
 
         if !freeze_gaze_dir {
-            (cur_gaze_dir, look_step_idx) = match mode_a_val {
-                TestModeA::HSweep | TestModeA::SurpriseHSweep => {
-                    GazeDirection::gaze_and_step_for_hsweep(main_loop_count)
-                }
-                TestModeA::VSweep => {
-                    GazeDirection::gaze_and_step_for_vsweep(main_loop_count)
-                }
-                TestModeA::ClockStar => {
-                    GazeDirection::gaze_and_step_for_sparse_star(main_loop_count)
+            match mode_a_val.gaze_targets() {
+                Some(targets) => {
+                    // saccades, fixations and pursuit run on their own clock
+                    gaze_controller.set_targets(targets);
+                    let new_gaze = gaze_controller.update(Instant::now().as_millis());
+                    if new_gaze != cur_gaze {
+                        cur_gaze = new_gaze;
+                        iris_dirty = true;
+                    }
+                    if gaze_controller.motion() == GazeMotion::Saccade {
+                        frame_render_gap_millis = frame_render_gap_millis.min(BLINK_FRAME_GAP_MILLIS);
+                    }
                 }
-                TestModeA::Meander => {
-                    GazeDirection::gaze_and_look_for_meander(main_loop_count)
+                None => {
+                    cur_gaze = mode_a_val.gaze_transition(main_loop_count);
+                    iris_dirty = true;
                 }
-                TestModeA::SlowRandMeander | TestModeA::Randomize => {
-                    let rand_count = embassy_rp::clocks::RoscRng::next_u8();
-                    GazeDirection::gaze_and_look_for_meander(rand_count as usize)
-                }
-                _ => { unreachable!() }
-            };
-            iris_dirty = true;
+            }
         }
         else if iris_dirty { 
             // update gaze direction and step based on mode_b_val
             let sweep_count = (mode_b_val as usize) % GazeDirection::NUM_FULL_SWEEP_STEPS;
-            (cur_gaze_dir, look_step_idx)  = 
-                match mode_a_val {
-                    TestModeA::VStep => { GazeDirection::gaze_and_step_for_vsweep(sweep_count) }
-                    TestModeA::HStep => { GazeDirection::gaze_and_step_for_hsweep(sweep_count)}
-                    _ => { unreachable!() }
-                };
-            info!("new m_b {} gaze: {} step: {}", mode_b_val, cur_gaze_dir, look_step_idx);
+            cur_gaze = mode_a_val.gaze_transition(sweep_count);
+            info!("new m_b {} gaze: {}", mode_b_val, cur_gaze);
         }
 
-        // 5000/480 = steps per ten seconds
-        // 100 / num_steps = pct per step
-        // TODO brightness cycling based on mode?
-        //  100 / (10000 / frame_render_gap_millis);
-        let brightstep_pct_raw =  100*frame_render_gap_millis / 5000; //fade pwm over this interval
-        // info!("intergap_ms: {} brightstep_pct_raw: {} ",frame_render_gap_millis, brightstep_pct_raw);
-        let brightstep_pct: u8 = u8::max(brightstep_pct_raw.try_into().unwrap(), 1); //fade pwm over this interval
+        // ramp the backlight up and down along the user's brightness curve
+        let curve = settings.brightness_curve;
+        let brightstep_pct = curve.step_pct(frame_render_gap_millis as u32);
         // info!("brightstep_pct: {}", brightstep_pct);
 
         if brightness_ascending {
-            brightness_percent += brightstep_pct;
-            if brightness_percent >= 100 {
-                brightness_percent = 100;
+            brightness_percent = brightness_percent.saturating_add(brightstep_pct);
+            if brightness_percent >= curve.max_pct {
+                brightness_percent = curve.max_pct;
                 brightness_ascending = false;
             }
         }
         else {
-            if brightness_percent < brightstep_pct { 
-                brightness_percent = 5;
+            if brightness_percent < curve.min_pct.saturating_add(brightstep_pct) { 
+                brightness_percent = curve.min_pct;
                 brightness_ascending = true; 
             }
             else {
                 brightness_percent -= brightstep_pct;
             }
         }
+        // with a light sensor, the room's light sets the brightness instead, within the user's curve
+        #[cfg(feature = "ambient-light")]
+        if let Some(ambient_pct) = ambient_light::ambient_brightness_pct() {
+            brightness_percent = ambient_pct.clamp(curve.min_pct, curve.max_pct);
+        }
+
+        // a held gaze or brightness wins over whatever the mode is doing
+        let gaze = if OVERRIDE_GAZE_ACTIVE.load(Ordering::Relaxed) {
+            GazeVector::from_bits(OVERRIDE_GAZE.load(Ordering::Relaxed))
+        } else {
+            GazeVector::from(cur_gaze)
+        };
+        if gaze != last_gaze {
+            iris_dirty = true;
+            last_gaze = gaze;
+        }
+        let shown_brightness_pct = match OVERRIDE_BRIGHTNESS_PCT.load(Ordering::Relaxed) {
+            NO_OVERRIDE => brightness_percent,
+            pct => pct,
+        };
+
+        // the pupil narrows in bright light and widens in the dark, or with surprise or love,
+        // unless it's held at a size over serial
+        let pupil_target = match OVERRIDE_PUPIL_PCT.load(Ordering::Relaxed) {
+            NO_OVERRIDE => PupilSize::target(shown_brightness_pct, emotion_blend.to),
+            pct => PupilSize::from_pct(pct),
+        };
+        let pupil_size = pupil_animator.update(pupil_target, Instant::now().as_millis());
+        if pupil_size != last_pupil_size {
+            iris_dirty = true;
+            last_pupil_size = pupil_size;
+        }
 
         // ship all the redraw config values
-        // info!("emote: {} gaze: {} look_step: {}", emotion_val, cur_gaze_dir, look_step_idx);
-        CUR_GAZE_DIR.store(cur_gaze_dir as u8, Ordering::Relaxed);
-        CUR_EMOTION.store(emotion_val as u8, Ordering::Relaxed);
-        CUR_LOOK_STEP.store(look_step_idx, Ordering::Relaxed);
+        // info!("emote: {} gaze: {}", emotion_val, cur_gaze);
+        CUR_GAZE_VECTOR.store(gaze.to_bits(), Ordering::Relaxed);
+        CUR_EMOTION.store(emotion_blend.to_bits(), Ordering::Relaxed);
+        CUR_LID_CLOSURE_LEFT.store(lid_closure.left.raw(), Ordering::Relaxed);
+        CUR_LID_CLOSURE_RIGHT.store(lid_closure.right.raw(), Ordering::Relaxed);
+        CUR_PUPIL_PCT.store(pupil_size.pct(), Ordering::Relaxed);
         CUR_IRIS_COLOR.store(iris_color.into_storage(), Ordering::Relaxed);
         CUR_SKIN_COLOR.store(skin_color.into_storage(), Ordering::Relaxed);
         CUR_IRIS_DIRTY.store(iris_dirty, Ordering::Relaxed);
         CUR_BG_DIRTY.store(bg_dirty, Ordering::Relaxed);
-        CUR_BRIGHTNESS_PCT.store(brightness_percent, Ordering::Relaxed);
+        CUR_BRIGHTNESS_PCT.store(shown_brightness_pct, Ordering::Relaxed);
 
         // At this point, all of the config data points required to re-render the frame have been calculated
         // and passed as atomics. Publish a message to start rendering.
@@ -600,27 +551,51 @@ async fn main(spawner: Spawner) {
         bg_dirty = false;
         iris_dirty = false;
 
+        settings_autosave.update(Instant::now().as_millis());
+
     }
 
 }
 
-/// convenience for logging / debuggin
-fn debug_tag_for_eye_side(is_left: bool) -> &'static str {
-    if is_left {"left"} else {"right"}
+#[cfg(not(feature = "strip-buffer"))]
+/// Send one region of the frame buffer to the display: whole rows in a single window,
+/// otherwise one window per row, since the region's pixels aren't contiguous in the frame buffer
+async fn show_frame_rect<T>(display: &mut RealDisplayType<T>, frame_buf: &FullFrameBuf, rect: Rectangle)
+where T: embassy_rp::spi::Instance
+{
+    let row_bytes = DISPLAY_WIDTH as usize * PIXEL_SIZE as usize;
+    let (x, y) = (rect.top_left.x as u16, rect.top_left.y as u16);
+    let (width, height) = (rect.size.width as u16, rect.size.height as u16);
+    if width == DISPLAY_WIDTH {
+        let rows = &frame_buf[y as usize * row_bytes..(y + height) as usize * row_bytes];
+        display.show_raw_data(0, y, width, height, rows).await.unwrap();
+    }
+    else {
+        let x_bytes = x as usize * PIXEL_SIZE as usize;
+        let width_bytes = width as usize * PIXEL_SIZE as usize;
+        for row in y..y + height {
+            let start = row as usize * row_bytes + x_bytes;
+            display.show_raw_data(x, row, width, 1, &frame_buf[start..start + width_bytes]).await.unwrap();
+        }
+    }
 }
 
 /**
- * Performs the main redrawing for each eye
+ * Performs the main redrawing for each eye.
+ * With the `strip-buffer` feature, frames are sent to the display through `strips` instead,
+ * and with `band-render` they're rendered straight into them a band at a time, with no frame buffer.
  */
-async fn redraw_loop<T>(is_left: bool, mut backlight_pwm_out:   Pwm<'static>, mut display: RealDisplayType<T>) 
+async fn redraw_loop<T>(is_left: bool, mut backlight_pwm_out:   Pwm<'static>,
+    #[cfg(not(feature = "strip-buffer"))] mut display: RealDisplayType<T>,
+    #[cfg(feature = "strip-buffer")] strips: &'static frame_sender::StripQueue)
 where T: embassy_rp::spi::Instance
 {
     let eye_debug_tag = if is_left {"left"} else { "right"};
     info!("begin {} eye redraw_loop", eye_debug_tag);
 
-    // "warm up" the backlight-- this only runs once after a restart
-    backlight_pwm_out.set_duty_cycle_percent(25).unwrap();
+    // the backlight starts dark, and fades in once the first frame is out
 
+    #[cfg(not(feature = "band-render"))]
     let disp_frame_buf: &'static mut [u8; FRAME_SIZE_BYTES] = 
         if is_left {
             DISPLAY0_FRAMEBUF.init_with(move || [0; FRAME_SIZE_BYTES])
@@ -629,16 +604,20 @@ where T: embassy_rp::spi::Instance
             DISPLAY1_FRAMEBUF.init_with(move || [0; FRAME_SIZE_BYTES])
         };
     
-    let mut eyebg_qoi: Option<Qoi> = None;
+    let layers: &'static mut LayerCache =
+        if is_left { DISPLAY0_LAYER_CACHE.init_with(LayerCache::new) } else { DISPLAY1_LAYER_CACHE.init_with(LayerCache::new) };
+
+    let mut backgrounds = EmotionBackgrounds::for_blend(EmotionBlend::steady(EmotionExpression::Neutral), is_left);
+    // the first frame has to fill the whole display
     let mut display_dirty = true;
 
     let mut eye_ready_sub = EYE_DATA_READY_CHANNEL.subscriber().unwrap();
-    let mut last_emotion_val = EmotionExpression::MaxCount;
+    let mut last_emotion_pair = (EmotionExpression::MaxCount, EmotionExpression::MaxCount);
 
     let mut redraw_loop_count: usize = 0;
     let mut recent_redraw_loop_count: usize = 0;
     let mut loop_elapsed_total: u64 = 0;
-    let mut last_brightness_pct: u8 = 25;
+    let mut backlight_fade = BacklightFade::new(BacklightFade::DEFAULT_RATE_PCT_PER_S);
 
     loop {
         // sync on eye parameters data ready
@@ -657,29 +636,20 @@ where T: embassy_rp::spi::Instance
         let loop_start_micros = Instant::now().as_micros();
         // info!("{} eye redraw start ", eye_debug_tag);
   
-        // dim slightly while we start redrawing
-        let dim_light_pct = if last_brightness_pct >= 7 { last_brightness_pct - 7 } else {0};
-        backlight_pwm_out.set_duty_cycle_percent(dim_light_pct).unwrap();
-
-        let brightness_percent: u8 = CUR_BRIGHTNESS_PCT.load(Ordering::Relaxed);
-        let mid_light_pct = (brightness_percent + dim_light_pct) / 2;
-
         let bg_dirty = CUR_BG_DIRTY.load(Ordering::Relaxed);
         let iris_dirty = CUR_IRIS_DIRTY.load(Ordering::Relaxed);
-        let emotion_val: EmotionExpression = CUR_EMOTION.load(Ordering::Relaxed).try_into().unwrap();
-        let gaze_dir: GazeDirection = CUR_GAZE_DIR.load(Ordering::Relaxed).try_into().unwrap();
-        let look_step: u8 = CUR_LOOK_STEP.load(Ordering::Relaxed);
+        let emotion_blend = EmotionBlend::from_bits(CUR_EMOTION.load(Ordering::Relaxed));
+        let gaze = GazeVector::from_bits(CUR_GAZE_VECTOR.load(Ordering::Relaxed));
+        let lid_closure_src = if is_left { &CUR_LID_CLOSURE_LEFT } else { &CUR_LID_CLOSURE_RIGHT };
+        let lid_closure = MorphFraction::from_raw(lid_closure_src.load(Ordering::Relaxed));
+        let pupil_size = PupilSize::from_pct(CUR_PUPIL_PCT.load(Ordering::Relaxed));
         let iris_color: Rgb565 = Rgb565::from(RawU16::new(CUR_IRIS_COLOR.load(Ordering::Relaxed)));
         let skin_color: Rgb565 = Rgb565::from(RawU16::new(CUR_SKIN_COLOR.load(Ordering::Relaxed)));
 
-        if emotion_val != last_emotion_val {
-            if let Some(src_bytes) = get_emotion_bg_bytes(emotion_val, is_left) {
-                eyebg_qoi = Qoi::new(src_bytes).ok();
-            } else { 
-                eyebg_qoi = None; 
-            }
-
-            last_emotion_val = emotion_val;
+        // swap in the backgrounds at each end of a change of emotion
+        if (emotion_blend.from, emotion_blend.to) != last_emotion_pair {
+            backgrounds = EmotionBackgrounds::for_blend(emotion_blend, is_left);
+            last_emotion_pair = (emotion_blend.from, emotion_blend.to);
         }
 
         /*
@@ -702,45 +672,52 @@ where T: embassy_rp::spi::Instance
          - infraorbital furrow
         */
 
-        if bg_dirty || display_dirty  {
-            if let Some(ref qoi) = eyebg_qoi {
-                // recreating the Image drawable each time has low overhead
-                let bg_img = Image::new(qoi, ORIGIN_POINT);
-                render_one_bg_image(disp_frame_buf, &bg_img);
-            }
-            else { // just set a background skincolor
-                let mut raw_fb =
-                    RawFrameBuf::<Rgb565, &mut [u8]>::new(disp_frame_buf.as_mut_slice(), DISPLAY_WIDTH as usize, DISPLAY_HEIGHT as usize);
-                let _ = raw_fb.clear(skin_color); 
+        #[cfg(not(feature = "band-render"))]
+        let dirty_rect = {
+            // each layer adds the regions it draws into to `frame.dirty`
+            let mut frame = FrameRows::full(disp_frame_buf);
+            if bg_dirty || display_dirty  {
+                render_background_layer(is_left, &backgrounds, gaze.nearest_direction(), emotion_blend, skin_color, &mut frame);
             }
 
-            draw_background_shapes(is_left, gaze_dir, emotion_val, skin_color, disp_frame_buf);
-            display_dirty = true;
-        }
+            if iris_dirty || display_dirty  {
+                render_eyeball_layers(is_left, gaze, emotion_blend, lid_closure, pupil_size, iris_color, skin_color, layers, &mut frame);
+            }
 
-        if iris_dirty || display_dirty  {
-            draw_inner_eye_shapes(is_left, gaze_dir, emotion_val, look_step, iris_color, disp_frame_buf);
-            draw_eyeball_overlay_shapes(is_left, gaze_dir, emotion_val, look_step, skin_color, disp_frame_buf);
-            display_dirty = true;
-        }
+            if display_dirty {
+                frame.dirty.add_frame(FRAME_SIZE);
+                display_dirty = false;
+            }
+            frame.dirty.take(FRAME_SIZE)
+        };
 
-        if display_dirty {
-            // start transition to new brightness
-            backlight_pwm_out.set_duty_cycle_percent(mid_light_pct).unwrap();
-        
-            // blit frame buffer to display via SPI
-            display
-                .show_raw_data(0, 0, 
-                    DISPLAY_WIDTH, DISPLAY_HEIGHT, 
-                    disp_frame_buf)
-                .await
-                .unwrap();
+        // with no frame buffer to keep the unchanged parts, any change redraws the whole frame, band by band
+        #[cfg(feature = "band-render")]
+        if bg_dirty || iris_dirty || display_dirty {
+            let mut renderer = band_renderer(is_left, &backgrounds, gaze, emotion_blend, lid_closure, pupil_size,
+                iris_color, skin_color, layers);
+            strips.queue_bands(Rectangle::new(ORIGIN_POINT, FRAME_SIZE),
+                |band, strip| renderer.render_band(strip, band)).await;
             display_dirty = false;
         }
-        // Now set the brightness to the desired level
-        backlight_pwm_out.set_duty_cycle_percent(brightness_percent).unwrap();
 
-        last_brightness_pct = brightness_percent;
+        #[cfg(not(feature = "band-render"))]
+        if let Some(dirty_rect) = dirty_rect {
+            // blit just the changed part of the frame buffer to the display via SPI
+            #[cfg(not(feature = "strip-buffer"))]
+            {
+                let send_rect = transfer_rect(dirty_rect, DISPLAY_WIDTH as u32, DISPLAY_WINDOW_OVERHEAD_BYTES);
+                show_frame_rect(&mut display, disp_frame_buf, send_rect).await;
+            }
+            // or just copy it out, and let the sender task finish sending while we draw the next frame
+            #[cfg(feature = "strip-buffer")]
+            strips.queue_rect(disp_frame_buf, dirty_rect).await;
+        }
+        // glide toward the brightness asked for, along the perceptual curve
+        let brightness_percent: u8 = CUR_BRIGHTNESS_PCT.load(Ordering::Relaxed);
+        let luminance = backlight_fade.update(brightness_percent, Instant::now().as_millis());
+        let duty = backlight::duty_cycle(luminance, backlight_pwm_out.max_duty_cycle());
+        backlight_pwm_out.set_duty_cycle(duty).unwrap();
 
         redraw_loop_count += 1;
         recent_redraw_loop_count += 1;
@@ -770,166 +747,22 @@ where T: embassy_rp::spi::Instance
 }
 
 
-fn draw_background_shapes(is_left: bool, _gaze_dir: GazeDirection, _emotion: EmotionExpression, _skin_color:Rgb565, frame_buf: &mut FullFrameBuf) 
-{
-    let start_micros = Instant::now().as_micros();
-    let file_id = if is_left { SvgFileId::EyeLeft } else { SvgFileId::EyeRight };
-
-    let brow_style = PrimitiveStyleBuilder::new()
-        .fill_color( Rgb565::CSS_BLACK )
-        .stroke_color(Rgb565::BLACK)
-        .stroke_width(1)
-        .stroke_alignment(StrokeAlignment::Center)
-        .build();
-    let test_ellipse_style  = PrimitiveStyleBuilder::new()
-        .fill_color( Rgb565::CSS_LIGHT_GREEN )
-        .stroke_color(Rgb565::BLACK)
-        .build();
-        
-    if is_left {
-        // TODO ensure that this ellipse is also reflected correctly on right eye
-        draw_closed_poly(frame_buf, file_id, "grande_ellipse", &test_ellipse_style);
-    }
-
-    // The eyebrow covers a lot of area, so we don't want to redraw too often
-    draw_closed_poly(frame_buf, file_id, "eyebrow", &brow_style);
-
-    let _elapsed_micros = Instant::now().as_micros() - start_micros;
-    info!("bg redraw {} {}µs", debug_tag_for_eye_side(is_left), _elapsed_micros);
-
-}
-
-
-
-
-fn draw_inner_eye_shapes(is_left:bool, end_gaze_dir: GazeDirection, _emotion: EmotionExpression, look_step: u8, 
-    iris_color: Rgb565, frame_buf: &mut FullFrameBuf) 
+/// Initialize one eye's display as its `config` describes
+async fn init_display<T>(
+    config: &DisplayConfig,
+    spi_int: SpiInterface<SpiDevice<'static, NoopRawMutex, Spi<'static, T, Async>, Output<'static>>, Output<'static>>,
+    rst_out: Output<'static>) -> RealDisplayType<T>
+where T: embassy_rp::spi::Instance
 {
-    static RUN_COUNT:AtomicUsize = AtomicUsize::new(0);
-    static TOTAL_ELAPSED_MICROS:AtomicUsize = AtomicUsize::new(0);
-    let start_micros = Instant::now().as_micros();
-    let file_id = if is_left { SvgFileId::EyeLeft } else { SvgFileId::EyeRight };
-
-    let darker_iris_color = adjust_lightness_rgb565(iris_color, FACTOR_DARKEN_10);
-    let iris_style = PrimitiveStyleBuilder::new()
-        .fill_color(iris_color)
-        .stroke_color(darker_iris_color)
-        .stroke_width(1) // TODO polyline redraw with stroke width > 1 is currently very slow-- why?
-        .stroke_alignment(StrokeAlignment::Center)
-        .build();
-        
-    // In our model, the sclera never changes. Other things draw over this.
-    draw_closed_poly(frame_buf, file_id, "sclera", &PrimitiveStyle::with_fill(hex_to_rgb565(0xf4eed7)));
-    
-    draw_stepped_asset(frame_buf, file_id, "iris", end_gaze_dir, look_step, &iris_style);
-    draw_stepped_asset(frame_buf, file_id, "iris_shadow_top", end_gaze_dir, look_step, &PrimitiveStyle::with_fill(darker_iris_color));
-    draw_stepped_asset(frame_buf, file_id, "pupil", end_gaze_dir, look_step, &PrimitiveStyle::with_fill(Rgb565::BLACK));
-    draw_stepped_asset(frame_buf, file_id, "glint_lg", end_gaze_dir, look_step, &PrimitiveStyle::with_fill(Rgb565::WHITE));
-    draw_stepped_asset(frame_buf, file_id, "glint_sm", end_gaze_dir, look_step, &PrimitiveStyle::with_fill(Rgb565::WHITE));
-
-    let _elapsed_micros:usize = (Instant::now().as_micros() - start_micros).try_into().unwrap();
-    if !is_left {
-        let total_elapsed = TOTAL_ELAPSED_MICROS.fetch_add(_elapsed_micros, Ordering::Relaxed);
-        let total_runs = RUN_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
-        if total_runs > 1000 {
-            let total_elapsed = total_elapsed + _elapsed_micros;
-            let avg_elapsed_micros = total_elapsed / total_runs;
-            info!("{} inner redraw {}µs", debug_tag_for_eye_side(is_left), avg_elapsed_micros);
-            // reset benchmarker
-            TOTAL_ELAPSED_MICROS.store(_elapsed_micros, Ordering::Relaxed);
-            RUN_COUNT.store(1, Ordering::Relaxed);
-        }
-    }
-
-    // info!("inner redraw {} {}µs", debug_tag_for_eye_side(is_left), _elapsed_micros);
-
-}
-
-/**
- Draw shapes that overlay the eyeball (sclera and all) after drawing the iris &c.
- Some overlay parts are inspired by reference to Moriyama et al paper.        
-  - inner (toward nose) and outer eye corners - ref region5 and region6 
-  - lower eyelid
-    - ref region2 and region3
-    - ref curve5 infraorbital furrow
-  - upper eyelid - ref region1
-
-  The lids themselves can be though of as consisting of multiple parts:
-  - bulge bright region
-  - infraorbital furrow
- */
-fn draw_eyeball_overlay_shapes(is_left:bool, 
-    gaze_dir: GazeDirection, _emotion:EmotionExpression, look_step: u8, skin_color:Rgb565, frame_buf: &mut FullFrameBuf) {
-    static RUN_COUNT:AtomicUsize = AtomicUsize::new(0);
-    static TOTAL_ELAPSED_MICROS:AtomicUsize = AtomicUsize::new(0);
-
-    let start_micros = Instant::now().as_micros();
-    let file_id = if is_left { SvgFileId::EyeLeft } else { SvgFileId::EyeRight };
-
-    let upper_lid_skin = hex_to_rgb565(0x73369a); //TODO get this custom color elsewhere
-    let upper_lid_shine_color= adjust_lightness_rgb565(upper_lid_skin, FACTOR_BRIGHTEN_20);
-    let upper_lid_skin_darker = adjust_lightness_rgb565(upper_lid_skin, FACTOR_DARKEN_30);
-
-    let slightly_brighter_skin = adjust_lightness_rgb565(skin_color, FACTOR_BRIGHTEN_20);
-    let slightly_darker_skin = adjust_lightness_rgb565(skin_color, FACTOR_DARKEN_20);
-
-    let upper_lid_shine_style = PrimitiveStyleBuilder::new()
-        .fill_color(upper_lid_shine_color)
-        .stroke_color(upper_lid_skin_darker)
-        .stroke_width(2)
-        .stroke_alignment(StrokeAlignment::Center)
-        .build();
-
-    let upper_lid_style = PrimitiveStyleBuilder::new()
-        .fill_color(upper_lid_skin) 
-        .build();
-
-    let upper_lid_shadow_style = PrimitiveStyleBuilder::new()
-        .fill_color(hex_to_rgb565(0x1d1c4f))
-        .build();
-
-    let lower_lid_bulge_style = PrimitiveStyleBuilder::new()
-        .fill_color(slightly_darker_skin)
-        .stroke_color(skin_color)
-        .stroke_width(1)
-        .build();
-
-    let lower_lid_shine_style = PrimitiveStyleBuilder::new()
-        .fill_color(slightly_brighter_skin)
-        .stroke_color(Rgb565::CSS_BLACK)
-        .stroke_width(1)
-        .stroke_alignment(StrokeAlignment::Center)
-        .build();
- 
-    // if emotion == EmotionExpression::Surprise { //TODO handle emotions differently
-
-    // draw the entire lower eyelid "module"
-    draw_closed_poly(frame_buf, file_id, "outer_corner_11", &PrimitiveStyle::with_fill(hex_to_rgb565(0x24102f))); // TODO
-    draw_closed_poly(frame_buf, file_id, "inner_corner_11", &PrimitiveStyle::with_fill(hex_to_rgb565(0x24102f))); // TODO
-    draw_closed_poly(frame_buf, file_id, "lower_lid_bulge_11", &lower_lid_bulge_style);
-    draw_closed_poly(frame_buf, file_id, "lower_lid_shine_11", &lower_lid_shine_style);
-
-
-    draw_stepped_asset(frame_buf, file_id, "upper_lid_shadow", gaze_dir, look_step, &upper_lid_shadow_style);
-    // TODO we paint the shine below the lid because we want a line width on top?
-    draw_stepped_asset(frame_buf, file_id, "upper_lid_shine", gaze_dir, look_step, &upper_lid_shine_style);
-    draw_stepped_asset(frame_buf, file_id, "upper_lid_bulge", gaze_dir, look_step, &upper_lid_style);
-
-    let _elapsed_micros:usize = (Instant::now().as_micros() - start_micros).try_into().unwrap();
-    if !is_left {
-        let total_elapsed = TOTAL_ELAPSED_MICROS.fetch_add(_elapsed_micros, Ordering::Relaxed);
-        let total_runs = RUN_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
-        if total_runs > 1000 {
-            let total_elapsed = total_elapsed + _elapsed_micros;
-            let avg_elapsed_micros = total_elapsed / total_runs;
-            info!("overlay redraw {} {}µs", debug_tag_for_eye_side(is_left), avg_elapsed_micros);
-            // reset benchmarker
-            TOTAL_ELAPSED_MICROS.store(_elapsed_micros, Ordering::Relaxed);
-            RUN_COUNT.store(1, Ordering::Relaxed);
-        }
-    }
-    // info!("overlay redraw {} {}µs", debug_tag_for_eye_side(is_left), _elapsed_micros);
-
+    Builder::new(PANEL_MODEL, spi_int)
+        .reset_pin(rst_out)
+        .display_size(config.native_size.0, config.native_size.1)
+        .orientation(config.orientation())
+        .invert_colors(config.color_inversion)
+        .color_order(config.color_order)
+        .init(&mut Delay)
+        .await
+        .unwrap()
 }
 
 #[embassy_executor::task]
@@ -953,16 +786,11 @@ async fn core0_drawing_task(
     let spi_int = SpiInterface::new(spi_device, dcx_out);
 
     // Define the display from the display interface and initialize it
-    let display = Builder::new(ST7789, spi_int)
-        .reset_pin(rst_out)
-        .display_size(DISPLAY_HEIGHT, DISPLAY_WIDTH)
-        .orientation(Orientation::new().rotate(Rotation::Deg270))
-        .invert_colors(ColorInversion::Inverted)
-        .init(&mut Delay)
-        .await
-        .unwrap();
+    let display = init_display(&LEFT_DISPLAY, spi_int, rst_out).await;
 
-    redraw_loop(true, backlight_pwm_out, display).await;
+    #[cfg(feature = "strip-buffer")]
+    let display = frame_sender::start_left_sender(display);
+    redraw_loop::<SPI0>(true, backlight_pwm_out, display).await;
 
 }
 
@@ -987,15 +815,10 @@ async fn core1_drawing_task(
     let spi_int = SpiInterface::new(spi_device, dcx_out);
 
     // Define the display from the display interface and initialize it
-    let  display = Builder::new(ST7789, spi_int)
-        .reset_pin(rst_out)
-        .display_size(DISPLAY_HEIGHT, DISPLAY_WIDTH)
-        .orientation(Orientation::new().rotate(Rotation::Deg90))
-        .invert_colors(ColorInversion::Inverted)
-        .init(&mut Delay)
-        .await
-        .unwrap();
+    let display = init_display(&RIGHT_DISPLAY, spi_int, rst_out).await;
 
-    redraw_loop(false, backlight_pwm_out, display).await;
+    #[cfg(feature = "strip-buffer")]
+    let display = frame_sender::start_right_sender(&Spawner::for_current_executor().await, display);
+    redraw_loop::<SPI1>(false, backlight_pwm_out, display).await;
 
 }
\ No newline at end of file
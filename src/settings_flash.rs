@@ -0,0 +1,123 @@
+//!
+//! Keep user settings in sectors of flash reserved by `memory.x`, using the
+//! record store in `eyemodelz::settings`.
+//!
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt::{info, warn};
+use embassy_rp::flash::{Blocking, Flash};
+use embassy_rp::peripherals::FLASH;
+use embassy_rp::Peri;
+use embedded_graphics::pixelcolor::{raw::RawU16, Rgb565};
+use embedded_graphics::prelude::IntoStorage;
+
+use eyemodelz::settings::{Settings, SettingsStore};
+
+use crate::eyerender::TestModeA;
+use crate::{
+    CUR_MODE_A, NO_COLOR_OVERRIDE, NO_OVERRIDE, OVERRIDE_BRIGHTNESS_PCT, OVERRIDE_IRIS_COLOR, OVERRIDE_SKIN_COLOR,
+};
+
+/// The whole flash, including the settings sectors that `memory.x` leaves out of FLASH
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
+/// Two sectors, at the very end of flash, so a save never erases the latest record
+const SETTINGS_FLASH_LEN: usize = 8192;
+const SETTINGS_FLASH_OFFSET: usize = FLASH_SIZE - SETTINGS_FLASH_LEN;
+/// Wait for settings to stop changing before writing them, eg while clicking through modes
+const SETTINGS_SAVE_DELAY_MILLIS: u64 = 3000;
+
+pub type SettingsFlash = Flash<'static, FLASH, Blocking, FLASH_SIZE>;
+
+/// The settings we start with when none have been saved
+pub const DEFAULT_SETTINGS: Settings = Settings::defaults(TestModeA::Meander as u8);
+
+/// Open the settings region and load whatever was last saved there.
+/// Call this before core1 starts, since it reads flash directly.
+pub fn load_settings(flash: Peri<'static, FLASH>) -> (SettingsStore<SettingsFlash>, Settings) {
+    let mut store = SettingsStore::new(
+        Flash::new_blocking(flash), SETTINGS_FLASH_OFFSET as u32, SETTINGS_FLASH_LEN as u32);
+    let settings = match store.load() {
+        Ok(Some(settings)) if settings.is_valid(TestModeA::MaxCount as u8) => {
+            info!("loaded settings: mode {}", settings.mode_a);
+            settings
+        }
+        Ok(Some(_)) => {
+            warn!("saved settings out of range, using defaults");
+            DEFAULT_SETTINGS
+        }
+        Ok(None) => {
+            info!("no saved settings");
+            DEFAULT_SETTINGS
+        }
+        Err(err) => {
+            warn!("settings load failed: {}", err);
+            DEFAULT_SETTINGS
+        }
+    };
+    (store, settings)
+}
+
+fn color_override(raw: u32) -> Option<Rgb565> {
+    (raw != NO_COLOR_OVERRIDE).then(|| Rgb565::from(RawU16::new(raw as u16)))
+}
+
+/// Put settings into the shared state that the buttons and serial commands also change
+pub fn apply_settings(settings: &Settings) {
+    CUR_MODE_A.store(settings.mode_a, Ordering::Relaxed);
+    let store_color = |atomic: &AtomicU32, color: Option<Rgb565>| {
+        atomic.store(color.map_or(NO_COLOR_OVERRIDE, |color| color.into_storage() as u32), Ordering::Relaxed)
+    };
+    store_color(&OVERRIDE_IRIS_COLOR, settings.iris_color);
+    store_color(&OVERRIDE_SKIN_COLOR, settings.skin_color);
+    OVERRIDE_BRIGHTNESS_PCT.store(settings.brightness_pct.unwrap_or(NO_OVERRIDE), Ordering::Relaxed);
+}
+
+/// The settings as they are now, from the shared state.
+/// Anything that can't be changed at runtime comes from `base`.
+fn current_settings(base: &Settings) -> Settings {
+    let brightness_pct = OVERRIDE_BRIGHTNESS_PCT.load(Ordering::Relaxed);
+    Settings {
+        mode_a: CUR_MODE_A.load(Ordering::Relaxed),
+        iris_color: color_override(OVERRIDE_IRIS_COLOR.load(Ordering::Relaxed)),
+        skin_color: color_override(OVERRIDE_SKIN_COLOR.load(Ordering::Relaxed)),
+        brightness_pct: (brightness_pct != NO_OVERRIDE).then_some(brightness_pct),
+        ..*base
+    }
+}
+
+/// Saves settings once they've been left alone for a while
+pub struct SettingsAutosave {
+    store: SettingsStore<SettingsFlash>,
+    saved: Settings,
+    latest: Settings,
+    changed_at_millis: u64,
+}
+
+impl SettingsAutosave {
+    pub fn new(store: SettingsStore<SettingsFlash>, saved: Settings) -> Self {
+        Self { store, saved, latest: saved, changed_at_millis: 0 }
+    }
+
+    /// Call regularly to pick up changes to the shared state
+    pub fn update(&mut self, now_millis: u64) {
+        let settings = current_settings(&self.latest);
+        if settings != self.latest {
+            self.latest = settings;
+            self.changed_at_millis = now_millis;
+        }
+        if self.latest != self.saved && now_millis - self.changed_at_millis >= SETTINGS_SAVE_DELAY_MILLIS {
+            match self.store.save(&self.latest) {
+                Ok(()) => {
+                    info!("saved settings");
+                    self.saved = self.latest;
+                }
+                Err(err) => {
+                    warn!("settings save failed: {}", err);
+                    // try again after another delay, rather than on every frame
+                    self.changed_at_millis = now_millis;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//!
+//! Live control over USB serial (CDC ACM), using the line protocol in `eyemodelz::command`.
+//!
+//! Commands change the same shared state as the push buttons, or set overrides
+//! that the main loop applies on top of the current mode.
+//!
+
+use core::fmt::Write;
+use core::sync::atomic::Ordering;
+
+use defmt::{info, unwrap};
+use embassy_executor::Spawner;
+use embassy_rp::{bind_interrupts, Peri};
+use embassy_rp::peripherals::USB;
+use embassy_rp::usb::{Driver, InterruptHandler};
+use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use embassy_usb::driver::EndpointError;
+use embassy_usb::{Builder as UsbBuilder, Config as UsbConfig, UsbDevice};
+use embedded_graphics::pixelcolor::{raw::RawU16, Rgb565};
+use embedded_graphics::prelude::IntoStorage;
+use heapless::{String, Vec};
+use static_cell::StaticCell;
+
+use eyemodelz::*;
+use eyemodelz::command::{parse_command, Command, CommandError, EyeStatus, HELP_TEXT, MAX_COMMAND_LEN};
+use eyemodelz::emotion_blend::EmotionBlend;
+use eyemodelz::pupil::PupilSize;
+
+use crate::eyerender::TestModeA;
+use crate::{
+    CUR_BRIGHTNESS_PCT, CUR_EMOTION, CUR_GAZE_VECTOR, CUR_IRIS_COLOR, CUR_MODE_A, CUR_PUPIL_PCT, CUR_SKIN_COLOR,
+    NO_COLOR_OVERRIDE, NO_OVERRIDE, OVERRIDE_BRIGHTNESS_PCT, OVERRIDE_EMOTION, OVERRIDE_GAZE, OVERRIDE_GAZE_ACTIVE,
+    OVERRIDE_IRIS_COLOR, OVERRIDE_PUPIL_PCT, OVERRIDE_SKIN_COLOR,
+};
+
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => InterruptHandler<USB>;
+});
+
+type UsbDriver = Driver<'static, USB>;
+
+const USB_MAX_PACKET_SIZE: u16 = 64;
+// The longest reply is the help text
+const MAX_REPLY_LEN: usize = 256;
+
+static USB_CONFIG_DESCRIPTOR: StaticCell<[u8; 256]> = StaticCell::new();
+static USB_BOS_DESCRIPTOR: StaticCell<[u8; 256]> = StaticCell::new();
+static USB_CONTROL_BUF: StaticCell<[u8; 64]> = StaticCell::new();
+static USB_CDC_STATE: StaticCell<State> = StaticCell::new();
+
+/// Set up the USB device as a serial port, and spawn the tasks that run it
+pub fn spawn_usb_command_tasks(spawner: &Spawner, usb: Peri<'static, USB>) {
+    let driver = Driver::new(usb, Irqs);
+
+    let mut config = UsbConfig::new(0xc0de, 0xcafe);
+    config.manufacturer = Some("Kerplonk");
+    config.product = Some("eyebulbz");
+    config.serial_number = Some("00000001");
+    config.max_power = 100;
+    config.max_packet_size_0 = USB_MAX_PACKET_SIZE as u8;
+
+    let mut builder = UsbBuilder::new(
+        driver,
+        config,
+        USB_CONFIG_DESCRIPTOR.init([0; 256]),
+        USB_BOS_DESCRIPTOR.init([0; 256]),
+        &mut [], // no msos descriptors
+        USB_CONTROL_BUF.init([0; 64]),
+    );
+    let class = CdcAcmClass::new(&mut builder, USB_CDC_STATE.init(State::new()), USB_MAX_PACKET_SIZE);
+    let device = builder.build();
+
+    unwrap!(spawner.spawn(usb_device_task(device)));
+    unwrap!(spawner.spawn(usb_command_task(class)));
+}
+
+#[embassy_executor::task]
+async fn usb_device_task(mut device: UsbDevice<'static, UsbDriver>) -> ! {
+    device.run().await
+}
+
+#[embassy_executor::task]
+async fn usb_command_task(mut class: CdcAcmClass<'static, UsbDriver>) {
+    loop {
+        class.wait_connection().await;
+        info!("usb serial connected");
+        let _ = serve_commands(&mut class).await;
+        info!("usb serial disconnected");
+    }
+}
+
+/// Read command lines until the host disconnects, replying to each
+async fn serve_commands(class: &mut CdcAcmClass<'static, UsbDriver>) -> Result<(), EndpointError> {
+    let mut packet = [0u8; USB_MAX_PACKET_SIZE as usize];
+    let mut line: Vec<u8, MAX_COMMAND_LEN> = Vec::new();
+    let mut overflowed = false;
+    loop {
+        let len = class.read_packet(&mut packet).await?;
+        for &byte in &packet[..len] {
+            if byte != b'\r' && byte != b'\n' {
+                overflowed |= line.push(byte).is_err();
+                continue;
+            }
+            if overflowed || !line.is_empty() {
+                let text = if overflowed { None } else { core::str::from_utf8(&line).ok() };
+                let reply = handle_command_line(text);
+                write_reply(class, &reply).await?;
+            }
+            line.clear();
+            overflowed = false;
+        }
+    }
+}
+
+/// Send a reply line, split into packets
+async fn write_reply(class: &mut CdcAcmClass<'static, UsbDriver>, reply: &str) -> Result<(), EndpointError> {
+    let mut bytes: Vec<u8, { MAX_REPLY_LEN + 2 }> = Vec::new();
+    let _ = bytes.extend_from_slice(reply.as_bytes());
+    let _ = bytes.extend_from_slice(b"\r\n");
+    for chunk in bytes.chunks(USB_MAX_PACKET_SIZE as usize) {
+        class.write_packet(chunk).await?;
+    }
+    // a full final packet needs a zero-length packet to end the transfer
+    if bytes.len() % USB_MAX_PACKET_SIZE as usize == 0 {
+        class.write_packet(&[]).await?;
+    }
+    Ok(())
+}
+
+fn color_override(color: Option<Rgb565>) -> u32 {
+    color.map_or(NO_COLOR_OVERRIDE, |color| color.into_storage() as u32)
+}
+
+/// Apply one command line (None if it couldn't be read), returning the reply
+fn handle_command_line(text: Option<&str>) -> String<MAX_REPLY_LEN> {
+    let mut reply = String::new();
+    let command = match text {
+        Some(text) => parse_command(text, &TestModeA::NAMES),
+        None => Err(CommandError::TooLong),
+    };
+    match command {
+        Ok(Command::SetMode(mode_idx)) => CUR_MODE_A.store(mode_idx, Ordering::Relaxed),
+        Ok(Command::SetGaze(gaze)) => {
+            if let Some(gaze) = gaze {
+                OVERRIDE_GAZE.store(gaze.to_bits(), Ordering::Relaxed);
+            }
+            OVERRIDE_GAZE_ACTIVE.store(gaze.is_some(), Ordering::Relaxed);
+        }
+        Ok(Command::SetEmotion(emotion)) =>
+            OVERRIDE_EMOTION.store(emotion.map_or(NO_OVERRIDE, |emotion| emotion as u8), Ordering::Relaxed),
+        Ok(Command::SetIrisColor(color)) => OVERRIDE_IRIS_COLOR.store(color_override(color), Ordering::Relaxed),
+        Ok(Command::SetSkinColor(color)) => OVERRIDE_SKIN_COLOR.store(color_override(color), Ordering::Relaxed),
+        Ok(Command::SetBrightness(pct)) =>
+            OVERRIDE_BRIGHTNESS_PCT.store(pct.unwrap_or(NO_OVERRIDE), Ordering::Relaxed),
+        Ok(Command::SetPupil(size)) =>
+            OVERRIDE_PUPIL_PCT.store(size.map_or(NO_OVERRIDE, PupilSize::pct), Ordering::Relaxed),
+        Ok(Command::Status) => {
+            let mode_idx = CUR_MODE_A.load(Ordering::Relaxed) as usize;
+            let status = EyeStatus {
+                mode_name: TestModeA::NAMES.get(mode_idx).copied().unwrap_or("?"),
+                gaze: GazeVector::from_bits(CUR_GAZE_VECTOR.load(Ordering::Relaxed)),
+                // report where a change of emotion is heading
+                emotion: EmotionBlend::from_bits(CUR_EMOTION.load(Ordering::Relaxed)).to,
+                iris_color: Rgb565::from(RawU16::new(CUR_IRIS_COLOR.load(Ordering::Relaxed))),
+                skin_color: Rgb565::from(RawU16::new(CUR_SKIN_COLOR.load(Ordering::Relaxed))),
+                brightness_pct: CUR_BRIGHTNESS_PCT.load(Ordering::Relaxed),
+                pupil: PupilSize::from_pct(CUR_PUPIL_PCT.load(Ordering::Relaxed)),
+            };
+            let _ = write!(reply, "{}", status);
+            return reply;
+        }
+        Ok(Command::Help) => {
+            let _ = reply.push_str(HELP_TEXT);
+            return reply;
+        }
+        Err(err) => {
+            let _ = write!(reply, "err {}", err.message());
+            return reply;
+        }
+    }
+    let _ = reply.push_str("ok");
+    reply
+}